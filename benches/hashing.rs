@@ -0,0 +1,30 @@
+// Measures the hashing hot path: leaf hashing and pairing for the built-in hashers over 65536 leaves.
+// Run with: cargo bench --bench hashing
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use merkle_tree::{DomainSeparated, LegacyHexConcat, MerkleTree, Sha256Hasher};
+
+const LEAVES: usize = 65536;
+
+fn bench_hashing(c: &mut Criterion) {
+    let data: Vec<String> = (0..LEAVES).map(|i| format!("leaf-{}", i)).collect();
+
+    c.bench_function("SHA-256 tree (65536 leaves)", |b| {
+        b.iter(|| MerkleTree::<String, Sha256Hasher>::from_slice(black_box(&data)).unwrap())
+    });
+
+    c.bench_function("RFC 6962 tree (65536 leaves)", |b| {
+        b.iter(|| {
+            MerkleTree::<String, DomainSeparated>::with_hasher(black_box(data.clone())).unwrap()
+        })
+    });
+
+    c.bench_function("legacy hex tree (65536 leaves)", |b| {
+        b.iter(|| {
+            MerkleTree::<String, LegacyHexConcat>::with_hasher(black_box(data.clone())).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_hashing);
+criterion_main!(benches);
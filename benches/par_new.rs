@@ -0,0 +1,24 @@
+// Compares sequential and parallel construction over 1M leaves.
+// Run with: cargo bench --features rayon --bench par_new
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use merkle_tree::MerkleTree;
+
+const LEAVES: usize = 1_000_000;
+
+fn bench_par_new(c: &mut Criterion) {
+    let data: Vec<String> = (0..LEAVES).map(|i| format!("leaf-{}", i)).collect();
+
+    let mut group = c.benchmark_group("construction (1M leaves)");
+    group.sample_size(10);
+    group.bench_function("MerkleTree::new", |b| {
+        b.iter(|| MerkleTree::new(black_box(data.clone())).unwrap())
+    });
+    group.bench_function("MerkleTree::par_new", |b| {
+        b.iter(|| MerkleTree::par_new(black_box(data.clone())).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_par_new);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+// Compares updating one leaf in place against rebuilding the whole tree.
+// Run with: cargo bench --bench update_leaf
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use merkle_tree::MerkleTree;
+
+const LEAVES: usize = 65536;
+
+fn bench_update_leaf(c: &mut Criterion) {
+    let data: Vec<String> = (0..LEAVES).map(|i| format!("leaf-{}", i)).collect();
+    let mut tree = MerkleTree::new(data.clone()).unwrap();
+    let new_value = "updated".to_string();
+
+    c.bench_function("update_leaf (65536 leaves)", |b| {
+        b.iter(|| tree.update_leaf(black_box(LEAVES / 2), &new_value).unwrap())
+    });
+
+    c.bench_function("MerkleTree::new (65536 leaves)", |b| {
+        b.iter(|| MerkleTree::new(black_box(data.clone())).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_update_leaf);
+criterion_main!(benches);
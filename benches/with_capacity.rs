@@ -0,0 +1,36 @@
+// Compares filling a tree batch by batch with and without a capacity hint.
+// Run with: cargo bench --bench with_capacity
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use merkle_tree::MerkleTree;
+
+const LEAVES: usize = 16384;
+const BATCH: usize = 256;
+
+fn bench_with_capacity(c: &mut Criterion) {
+    let data: Vec<String> = (0..LEAVES).map(|i| format!("leaf-{}", i)).collect();
+    let batches: Vec<Vec<String>> = data.chunks(BATCH).map(|chunk| chunk.to_vec()).collect();
+
+    c.bench_function("extend without capacity (16384 leaves)", |b| {
+        b.iter(|| {
+            let mut tree = MerkleTree::new(batches[0].clone()).unwrap();
+            for batch in &batches[1..] {
+                tree.extend(black_box(batch.clone())).unwrap();
+            }
+            tree
+        })
+    });
+
+    c.bench_function("with_capacity + extend (16384 leaves)", |b| {
+        b.iter(|| {
+            let mut tree = MerkleTree::<String>::with_capacity(LEAVES);
+            for batch in &batches {
+                tree.extend(black_box(batch.clone())).unwrap();
+            }
+            tree
+        })
+    });
+}
+
+criterion_group!(benches, bench_with_capacity);
+criterion_main!(benches);
@@ -0,0 +1,115 @@
+// `#[derive(Hashable)]` for structs. Enable it through the `derive` feature of the main crate and use it as
+// `merkle_tree::Hashable`; on its own this crate only produces the impl, the encoding lives in `merkle_tree`.
+
+use proc_macro::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Index, parse_macro_input};
+
+/// Implements `merkle_tree::Hashable` for a struct by hashing its fields in declaration order.
+/// Each field is encoded as its length (u64 little-endian) followed by its bytes: `AsRef<[u8]>` fields as they are,
+/// anything else through `to_string()`, which also raises a deprecation warning on that field.
+/// The leaf is `H::hash_leaf` of the whole encoding, so it is plain SHA-256 in a default tree.
+///
+/// ```
+/// #[derive(merkle_tree::Hashable)]
+/// struct Transfer {
+///     from: String,
+///     memo: Vec<u8>,
+/// }
+/// ```
+///
+/// Structs without fields, enums and unions are rejected at compile time:
+///
+/// ```compile_fail
+/// #[derive(merkle_tree::Hashable)]
+/// struct Unit;
+/// ```
+///
+/// ```compile_fail
+/// #[derive(merkle_tree::Hashable)]
+/// struct NoFields {}
+/// ```
+///
+/// ```compile_fail
+/// #[derive(merkle_tree::Hashable)]
+/// enum Side {
+///     Left(String),
+///     Right(String),
+/// }
+/// ```
+#[proc_macro_derive(Hashable)]
+pub fn derive_hashable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "#[derive(Hashable)] only works on structs",
+        ));
+    };
+
+    // 1. Refer to every field by name, or by position for tuple structs
+    let accessors: Vec<proc_macro2::TokenStream> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let name = &field.ident;
+                quote_spanned!(field.span()=> #name)
+            })
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(position, field)| {
+                let index = Index {
+                    index: position as u32,
+                    span: field.span(),
+                };
+                quote!(#index)
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+    if accessors.is_empty() {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "#[derive(Hashable)] needs at least one field to hash",
+        ));
+    }
+
+    // 2. Spanned on the field, so a to_string() fallback warns about the field and not about the derive
+    let pushes = data.fields.iter().zip(&accessors).map(|(field, accessor)| {
+        quote_spanned! {field.span()=>
+            ::merkle_tree::derive_support::push_field(
+                &mut encoded,
+                &(&&::merkle_tree::derive_support::FieldBytes(&self.#accessor)).field_bytes(),
+            );
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::merkle_tree::Hashable for #name #type_generics #where_clause {
+            fn hash(&self) -> [u8; 32] {
+                self.hash_with::<::merkle_tree::Sha256Hasher>()
+            }
+
+            fn hash_with<H: ::merkle_tree::MerkleHasher>(&self) -> [u8; 32] {
+                #[allow(unused_imports)]
+                use ::merkle_tree::derive_support::{ViaAsRef as _, ViaDisplay as _};
+                let mut encoded = ::merkle_tree::derive_support::Vec::new();
+                #(#pushes)*
+                H::hash_leaf(&encoded)
+            }
+        }
+    })
+}
@@ -0,0 +1,91 @@
+// The derive as a user crate sees it: through `merkle_tree::Hashable` with the `derive` feature on.
+
+use merkle_tree::{DomainSeparated, Hashable, MerkleTree, hash_data};
+
+#[derive(Hashable)]
+struct Transfer {
+    from: String,
+    to: String,
+    memo: Vec<u8>,
+}
+
+#[derive(Hashable)]
+struct Pair(String, &'static str);
+
+// u64 has no `AsRef<[u8]>`, so `amount` goes through `to_string()` and warns; this module opts out of the warning.
+#[allow(deprecated)]
+mod with_fallback {
+    use merkle_tree::Hashable;
+
+    #[derive(Hashable)]
+    pub struct Tx {
+        pub from: String,
+        pub to: String,
+        pub amount: u64,
+    }
+}
+
+// The bytes the derive is documented to hash: u64 LE length, then the field, for every field in order.
+fn encode(fields: &[&[u8]]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for field in fields {
+        encoded.extend_from_slice(&(field.len() as u64).to_le_bytes());
+        encoded.extend_from_slice(field);
+    }
+    encoded
+}
+
+#[test]
+fn test_fields_are_hashed_in_order_with_lengths() {
+    let transfer = Transfer {
+        from: "alice".to_string(),
+        to: "bob".to_string(),
+        memo: vec![1, 2, 3],
+    };
+    assert_eq!(
+        transfer.hash(),
+        hash_data(&encode(&[b"alice", b"bob", &[1, 2, 3]]))
+    );
+
+    // Moving bytes from one field to the next changes the hash
+    let shifted = Transfer {
+        from: "alic".to_string(),
+        to: "ebob".to_string(),
+        memo: vec![1, 2, 3],
+    };
+    assert_ne!(shifted.hash(), transfer.hash());
+
+    assert_eq!(
+        Pair("a".to_string(), "b").hash(),
+        hash_data(&encode(&[b"a", b"b"]))
+    );
+}
+
+#[test]
+fn test_display_fallback_and_trees() {
+    let txs: Vec<with_fallback::Tx> = (0..5)
+        .map(|i| with_fallback::Tx {
+            from: format!("account-{}", i),
+            to: "exchange".to_string(),
+            amount: 100 * i,
+        })
+        .collect();
+    assert_eq!(
+        txs[3].hash(),
+        hash_data(&encode(&[b"account-3", b"exchange", b"300"]))
+    );
+
+    let leaf = txs[2].hash();
+    let tree = MerkleTree::new(txs).unwrap();
+    assert_eq!(tree.leaves()[2], leaf);
+    assert!(tree.get_proof(2).unwrap().verify());
+}
+
+#[test]
+fn test_other_hashers_hash_the_same_encoding() {
+    let pair = Pair("x".to_string(), "y");
+    let tree = MerkleTree::<Pair, DomainSeparated>::with_hasher(vec![pair]).unwrap();
+    let mut prefixed = vec![0x00];
+    prefixed.extend(encode(&[b"x", b"y"]));
+    assert_eq!(tree.leaves()[0], hash_data(&prefixed));
+}
@@ -0,0 +1,830 @@
+// Merkle accumulator (append-only log): a tree that only ever grows on the right,
+// shaped like RFC 6962 / Certificate Transparency so old sizes can be proven to be prefixes of new ones.
+// RFC-shaped `MerkleTree`s (PromoteOdd padding) get the same consistency proofs.
+
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleProof, MerkleTree, PaddingStrategy, ProofDirection,
+    ProofNode, Sha256Hasher, hash_pair_with, to_hex,
+};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A consistency proof: the node hashes RFC 6962 section 2.1.2 defines, bottom of the tree first.
+pub type ConsistencyProof = Vec<[u8; 32]>;
+
+/// A "tree head": a log's root together with how many leaves it had.
+/// The size is what gives the root meaning for consistency and inclusion checks, so pass these around
+/// instead of bare roots. Both checks assume the RFC 6962 shape (`MerkleAccumulator`, `new_ct`, `PromoteOdd`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    pub root: [u8; 32],
+    pub size: usize,
+}
+
+impl Checkpoint {
+    /// True if `newer` is this log with leaves appended (and nothing rewritten), per a SHA-256 `proof`
+    /// from `consistency_proof(self.size, newer.size)`.
+    pub fn verify_extension(&self, newer: &Checkpoint, proof: &ConsistencyProof) -> bool {
+        self.verify_extension_with::<Sha256Hasher>(newer, proof)
+    }
+
+    /// Same as `verify_extension`, for logs that combine hashes with `H`.
+    pub fn verify_extension_with<H: MerkleHasher>(
+        &self,
+        newer: &Checkpoint,
+        proof: &ConsistencyProof,
+    ) -> bool {
+        // Two sizes can never share a root; don't leave that to the hash function alone
+        if self.size != newer.size && self.root == newer.root {
+            return false;
+        }
+        verify_consistency_with::<H>(self.size, newer.size, &self.root, &newer.root, proof)
+    }
+
+    /// True if `proof` shows `leaf_hash` is in the SHA-256 log this checkpoint describes.
+    /// The proof must have exactly the steps a tree of `size` leaves gives that position, so a proof taken
+    /// from a tree of another size is rejected even if it otherwise reaches the root.
+    pub fn verify_inclusion(&self, leaf_hash: &[u8; 32], proof: &MerkleProof) -> bool {
+        self.verify_inclusion_with::<Sha256Hasher>(leaf_hash, proof)
+    }
+
+    /// Same as `verify_inclusion`, for logs that combine hashes with `H`.
+    pub fn verify_inclusion_with<H: MerkleHasher>(
+        &self,
+        leaf_hash: &[u8; 32],
+        proof: &MerkleProof,
+    ) -> bool {
+        proof.verify_for_size_with::<H>(
+            leaf_hash,
+            &self.root,
+            self.size,
+            PaddingStrategy::PromoteOdd,
+        )
+    }
+}
+
+/// An append-only Merkle log. Leaves can only be added at the end, never changed or removed,
+/// which is what makes `consistency_proof` possible: every earlier tree is a prefix of the current one.
+///
+/// Internally it is a "mountain range" of perfect subtrees. Only complete subtrees are stored,
+/// so an append never rewrites a node: it pushes the leaf and then merges equal-sized subtrees,
+/// doing one hash on average and at most log2(n) (O(log n) worst case, O(1) amortized).
+/// The root is the RFC 6962 Merkle Tree Hash, i.e. the root of `MerkleTree::with_padding(data, PaddingStrategy::PromoteOdd)`.
+#[derive(Debug)]
+pub struct MerkleAccumulator<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    // levels[k][i] is the root of the perfect subtree over leaves [i * 2^k, (i + 1) * 2^k).
+    // The last node of every odd-length level is one of the peaks of the mountain range.
+    // Keeping the smaller subtrees (not just the peaks) lets us prove consistency with any older size.
+    levels: Vec<Vec<[u8; 32]>>,
+    // Marker to link the log to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+impl<T: Hashable> MerkleAccumulator<T> {
+    /// Creates an empty SHA-256 accumulator.
+    pub fn new() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Default for MerkleAccumulator<T, H> {
+    fn default() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleAccumulator<T, H> {
+    /// Creates an empty accumulator that combines hashes with `H`,
+    /// e.g. `MerkleAccumulator::<T, DomainSeparated>::with_hasher()` for a Certificate Transparency log.
+    pub fn with_hasher() -> Self {
+        MerkleAccumulator {
+            levels: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Number of leaves appended so far.
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// True until the first `append`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds `item` as the next leaf.
+    pub fn append(&mut self, item: T) {
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels[0].push(item.hash_with::<H>());
+
+        // Each time a level reaches an even length, its last two nodes complete a subtree one level up
+        let mut level = 0;
+        while self.levels[level].len().is_multiple_of(2) {
+            let layer = &self.levels[level];
+            let parent = hash_pair_with::<H>(&layer[layer.len() - 2], &layer[layer.len() - 1]);
+
+            if self.levels.len() == level + 1 {
+                self.levels.push(Vec::new());
+            }
+            self.levels[level + 1].push(parent);
+            level += 1;
+        }
+    }
+
+    /// The roots of the perfect subtrees that make up the log, largest (leftmost) first.
+    /// There is one peak per set bit of `len()`.
+    pub fn peaks(&self) -> Vec<[u8; 32]> {
+        self.levels
+            .iter()
+            .rev()
+            .filter(|level| !level.len().is_multiple_of(2))
+            .map(|level| level[level.len() - 1])
+            .collect()
+    }
+
+    /// The current root. The peaks are folded from the right: `H(p1, H(p2, ... H(pk-1, pk)))`.
+    /// An empty accumulator has the all-zero root, like an empty `MerkleTree`.
+    pub fn root_bytes(&self) -> [u8; 32] {
+        let peaks = self.peaks();
+        let Some((smallest, rest)) = peaks.split_last() else {
+            return [0u8; 32];
+        };
+        rest.iter()
+            .rev()
+            .fold(*smallest, |acc, peak| hash_pair_with::<H>(peak, &acc))
+    }
+
+    /// Returns the current root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(&self.root_bytes())
+    }
+
+    /// The root the log had when it held only its first `size` leaves.
+    pub fn root_at(&self, size: usize) -> Result<[u8; 32], MerkleError> {
+        if size == 0 {
+            return Err(MerkleError::EmptyInput);
+        }
+        if size > self.len() {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: size,
+                len: self.len(),
+            });
+        }
+        Ok(self.subtree_root(0, size))
+    }
+
+    /// The current root and size.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            root: self.root_bytes(),
+            size: self.len(),
+        }
+    }
+
+    /// The checkpoint the log had at `size` leaves.
+    pub fn checkpoint_at(&self, size: usize) -> Result<Checkpoint, MerkleError> {
+        Ok(Checkpoint {
+            root: self.root_at(size)?,
+            size,
+        })
+    }
+
+    /// Proves that the log at `from_size` leaves is a prefix of the log at `to_size` leaves
+    /// (RFC 6962 section 2.1.2). Check it with `verify_consistency` and the two roots.
+    /// `from_size` must be at least 1 and at most `to_size`, which can't exceed `len()`.
+    pub fn consistency_proof(
+        &self,
+        from_size: usize,
+        to_size: usize,
+    ) -> Result<ConsistencyProof, MerkleError> {
+        check_consistency_sizes(from_size, to_size, self.len())?;
+        Ok(consistency_proof_from::<H>(
+            from_size,
+            to_size,
+            &|level, index| self.levels[level][index],
+        ))
+    }
+
+    /// The audit path (RFC 6962 section 2.1.1) of leaf `leaf_index` in the log at `size` leaves,
+    /// as a `MerkleProof` that `checkpoint_at(size)?.verify_inclusion` accepts.
+    /// It is the same proof `get_proof` gives on a `new_ct` tree over those `size` leaves.
+    pub fn audit_proof(&self, leaf_index: usize, size: usize) -> Result<MerkleProof, MerkleError> {
+        let root = self.root_at(size)?;
+        if leaf_index >= size {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: size,
+            });
+        }
+
+        let mut siblings = Vec::new();
+        audit_path::<H>(
+            leaf_index,
+            0,
+            size,
+            &|level, index| self.levels[level][index],
+            &mut siblings,
+        );
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count: size,
+            leaf_hash: self.levels[0][leaf_index],
+            siblings,
+            root,
+        })
+    }
+
+    fn subtree_root(&self, start: usize, len: usize) -> [u8; 32] {
+        subtree_root::<H>(start, len, &|level, index| self.levels[level][index])
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// The tree's root and leaf count as a `Checkpoint`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            root: *self.root_bytes(),
+            size: self.layers[0].len(),
+        }
+    }
+
+    /// Same as `MerkleAccumulator::consistency_proof`, for a tree with the RFC 6962 shape
+    /// (`PaddingStrategy::PromoteOdd`, as built by `new_ct`). The proof shows the tree's first `from_size`
+    /// leaves form a prefix of its first `to_size` leaves; check it with `verify_consistency`.
+    /// Other paddings give a different tree shape, so they return an Error.
+    pub fn consistency_proof(
+        &self,
+        from_size: usize,
+        to_size: usize,
+    ) -> Result<ConsistencyProof, MerkleError> {
+        if self.padding != PaddingStrategy::PromoteOdd {
+            return Err(MerkleError::InvalidLayers(
+                "consistency proofs need the RFC 6962 shape (PaddingStrategy::PromoteOdd)"
+                    .to_string(),
+            ));
+        }
+        check_consistency_sizes(from_size, to_size, self.layers[0].len())?;
+
+        // A complete, aligned block of leaves is stored as is when the odd nodes are only carried up
+        Ok(consistency_proof_from::<H>(
+            from_size,
+            to_size,
+            &|level, index| self.layers[level][index],
+        ))
+    }
+
+    /// Same as `MerkleAccumulator::audit_proof` at the tree's full size: the RFC 6962 audit path of leaf `leaf_index`,
+    /// for a tree with the RFC shape (`PaddingStrategy::PromoteOdd`, as built by `new_ct`).
+    /// Check it with `checkpoint().verify_inclusion`. Other paddings return an Error, like `consistency_proof`.
+    pub fn audit_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        if self.padding != PaddingStrategy::PromoteOdd {
+            return Err(MerkleError::InvalidLayers(
+                "audit proofs need the RFC 6962 shape (PaddingStrategy::PromoteOdd)".to_string(),
+            ));
+        }
+        let size = self.layers[0].len();
+        if leaf_index >= size {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: size,
+            });
+        }
+
+        let mut siblings = Vec::new();
+        audit_path::<H>(
+            leaf_index,
+            0,
+            size,
+            &|level, index| self.layers[level][index],
+            &mut siblings,
+        );
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count: size,
+            leaf_hash: self.layers[0][leaf_index],
+            siblings,
+            root: *self.root_bytes(),
+        })
+    }
+}
+
+fn check_consistency_sizes(
+    from_size: usize,
+    to_size: usize,
+    len: usize,
+) -> Result<(), MerkleError> {
+    if from_size == 0 {
+        return Err(MerkleError::EmptyInput);
+    }
+    if to_size > len {
+        return Err(MerkleError::IndexOutOfBounds {
+            index: to_size,
+            len,
+        });
+    }
+    if from_size > to_size {
+        return Err(MerkleError::IndexOutOfBounds {
+            index: from_size,
+            len: to_size,
+        });
+    }
+    Ok(())
+}
+
+// `perfect(level, index)` returns the root of the complete subtree over leaves [index * 2^level, (index + 1) * 2^level).
+type PerfectSubtree<'a> = dyn Fn(usize, usize) -> [u8; 32] + 'a;
+
+fn consistency_proof_from<H: MerkleHasher>(
+    from_size: usize,
+    to_size: usize,
+    perfect: &PerfectSubtree,
+) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    subproof::<H>(from_size, 0, to_size, true, perfect, &mut proof);
+    proof
+}
+
+// SUBPROOF(m, D[start..start + n], complete) from the RFC.
+// `complete` is true while the old tree is still the left edge of the range, so its root is known to the verifier.
+fn subproof<H: MerkleHasher>(
+    m: usize,
+    start: usize,
+    n: usize,
+    complete: bool,
+    perfect: &PerfectSubtree,
+    proof: &mut Vec<[u8; 32]>,
+) {
+    if m == n {
+        if !complete {
+            proof.push(subtree_root::<H>(start, n, perfect));
+        }
+        return;
+    }
+
+    let k = split_point(n);
+    if m <= k {
+        // The old tree fits in the left half, so the whole right half is new
+        subproof::<H>(m, start, k, complete, perfect, proof);
+        proof.push(subtree_root::<H>(start + k, n - k, perfect));
+    } else {
+        // The old tree covers the whole left half and part of the right one
+        subproof::<H>(m - k, start + k, n - k, false, perfect, proof);
+        proof.push(subtree_root::<H>(start, k, perfect));
+    }
+}
+
+// PATH(m, D[start..start + n]) from the RFC: the sibling of every subtree holding leaf `start + m`, bottom first.
+fn audit_path<H: MerkleHasher>(
+    m: usize,
+    start: usize,
+    n: usize,
+    perfect: &PerfectSubtree,
+    siblings: &mut Vec<ProofNode>,
+) {
+    if n == 1 {
+        return;
+    }
+
+    let k = split_point(n);
+    if m < k {
+        audit_path::<H>(m, start, k, perfect, siblings);
+        siblings.push(ProofNode {
+            direction: ProofDirection::Right,
+            hash: subtree_root::<H>(start + k, n - k, perfect),
+        });
+    } else {
+        audit_path::<H>(m - k, start + k, n - k, perfect, siblings);
+        siblings.push(ProofNode {
+            direction: ProofDirection::Left,
+            hash: subtree_root::<H>(start, k, perfect),
+        });
+    }
+}
+
+// MTH of leaves [start, start + len). Aligned power-of-two ranges are looked up; anything else is split like the RFC does.
+fn subtree_root<H: MerkleHasher>(start: usize, len: usize, perfect: &PerfectSubtree) -> [u8; 32] {
+    if len.is_power_of_two() && start.is_multiple_of(len) {
+        let level = len.trailing_zeros() as usize;
+        return perfect(level, start >> level);
+    }
+
+    let k = split_point(len);
+    hash_pair_with::<H>(
+        &subtree_root::<H>(start, k, perfect),
+        &subtree_root::<H>(start + k, len - k, perfect),
+    )
+}
+
+// The largest power of two strictly below n (n >= 2): where the RFC splits a tree of n leaves.
+fn split_point(n: usize) -> usize {
+    1 << (n - 1).ilog2()
+}
+
+/// Checks a SHA-256 consistency proof from `MerkleAccumulator::consistency_proof`:
+/// that `old_root` (at `from_size` leaves) and `new_root` (at `to_size` leaves) belong to the same append-only log.
+pub fn verify_consistency(
+    from_size: usize,
+    to_size: usize,
+    old_root: &[u8; 32],
+    new_root: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> bool {
+    verify_consistency_with::<Sha256Hasher>(from_size, to_size, old_root, new_root, proof)
+}
+
+/// Same as `verify_consistency`, for logs that combine hashes with `H`.
+/// This is the verification algorithm of RFC 9162 section 2.1.4.2.
+pub fn verify_consistency_with<H: MerkleHasher>(
+    from_size: usize,
+    to_size: usize,
+    old_root: &[u8; 32],
+    new_root: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> bool {
+    if from_size == 0 || from_size > to_size {
+        return false;
+    }
+    if from_size == to_size {
+        return proof.is_empty() && old_root == new_root;
+    }
+
+    // 1. When the old tree is a perfect subtree its root is the starting point and isn't sent
+    let mut path = Vec::with_capacity(proof.len() + 1);
+    if from_size.is_power_of_two() {
+        path.push(*old_root);
+    }
+    path.extend_from_slice(proof);
+
+    // 2. Skip the levels where the old tree's last node is a right child
+    let mut first = from_size - 1;
+    let mut second = to_size - 1;
+    while first & 1 == 1 {
+        first >>= 1;
+        second >>= 1;
+    }
+
+    // 3. Rebuild both roots at once: `old` only takes the nodes left of the old edge, `new` takes everything
+    let Some((start, rest)) = path.split_first() else {
+        return false;
+    };
+    let mut old = *start;
+    let mut new = *start;
+    for node in rest {
+        if second == 0 {
+            return false;
+        }
+        if first & 1 == 1 || first == second {
+            old = hash_pair_with::<H>(node, &old);
+            new = hash_pair_with::<H>(node, &new);
+            while first & 1 == 0 && first != 0 {
+                first >>= 1;
+                second >>= 1;
+            }
+        } else {
+            new = hash_pair_with::<H>(&new, node);
+        }
+        first >>= 1;
+        second >>= 1;
+    }
+
+    old == *old_root && new == *new_root && second == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainSeparated, from_hex};
+
+    // Raw byte leaves for the RFC 6962 vectors, hashed with H's leaf hashing
+    struct RawLeaf(Vec<u8>);
+
+    impl Hashable for RawLeaf {
+        fn hash(&self) -> [u8; 32] {
+            self.hash_with::<Sha256Hasher>()
+        }
+
+        fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+            H::hash_leaf(&self.0)
+        }
+    }
+
+    // The eight inputs of the Certificate Transparency reference test suite
+    fn ct_leaves() -> Vec<RawLeaf> {
+        let inputs = [
+            "",
+            "00",
+            "10",
+            "2021",
+            "3031",
+            "40414243",
+            "5051525354555657",
+            "606162636465666768696a6b6c6d6e6f",
+        ];
+        inputs
+            .iter()
+            .map(|hex| {
+                let bytes = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                    .collect();
+                RawLeaf(bytes)
+            })
+            .collect()
+    }
+
+    fn ct_log() -> MerkleAccumulator<RawLeaf, DomainSeparated> {
+        let mut log = MerkleAccumulator::<RawLeaf, DomainSeparated>::with_hasher();
+        for leaf in ct_leaves() {
+            log.append(leaf);
+        }
+        log
+    }
+
+    #[test]
+    fn test_root_matches_promote_odd_tree() {
+        let mut log = MerkleAccumulator::new();
+        let mut data = Vec::new();
+        assert_eq!(log.root_bytes(), [0u8; 32]);
+
+        for i in 0..40 {
+            log.append(i.to_string());
+            data.push(i.to_string());
+
+            let tree: MerkleTree<String> =
+                MerkleTree::with_padding(data.clone(), PaddingStrategy::PromoteOdd).unwrap();
+            assert_eq!(&log.root_bytes(), tree.root_bytes(), "{} leaves", i + 1);
+            assert_eq!(log.peaks().len(), log.len().count_ones() as usize);
+        }
+    }
+
+    #[test]
+    fn test_ct_reference_consistency_proofs() {
+        let log = ct_log();
+        assert_eq!(
+            log.root_hex(),
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"
+        );
+
+        // (from, to, proof) from the Certificate Transparency reference implementation's test suite
+        let vectors: [(usize, usize, &[&str]); 4] = [
+            (1, 1, &[]),
+            (
+                1,
+                8,
+                &[
+                    "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+                ],
+            ),
+            (
+                6,
+                8,
+                &[
+                    "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                    "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                ],
+            ),
+            (
+                2,
+                5,
+                &[
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                ],
+            ),
+        ];
+
+        for (from, to, expected) in vectors {
+            let proof = log.consistency_proof(from, to).unwrap();
+            let expected: Vec<[u8; 32]> =
+                expected.iter().map(|hex| from_hex(hex).unwrap()).collect();
+            assert_eq!(proof, expected, "{} -> {}", from, to);
+
+            assert!(verify_consistency_with::<DomainSeparated>(
+                from,
+                to,
+                &log.root_at(from).unwrap(),
+                &log.root_at(to).unwrap(),
+                &proof
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rfc_example_tree_of_seven() {
+        // The seven-leaf tree drawn in RFC 6962 section 2.1.3, with its node names:
+        //             hash
+        //            /    \
+        //           k      l
+        //          / \    / \
+        //         g   h  i   j
+        //        / \ / \ / \ |
+        //        a b c d e f d6
+        let log = ct_log();
+        let leaf = |i: usize| log.levels[0][i];
+        let pair = hash_pair_with::<DomainSeparated>;
+        let [a, b, c, d, e, f] = [0, 1, 2, 3, 4, 5].map(leaf);
+        let (g, h, i, j) = (pair(&a, &b), pair(&c, &d), pair(&e, &f), leaf(6));
+        let (k, l) = (pair(&g, &h), pair(&i, &j));
+
+        // The audit paths the RFC lists for d0, d3, d4 and d6
+        let paths: [(usize, &[[u8; 32]]); 4] = [
+            (0, &[b, h, l]),
+            (3, &[c, g, l]),
+            (4, &[f, j, k]),
+            (6, &[i, k]),
+        ];
+        let head = log.checkpoint_at(7).unwrap();
+        let tree = MerkleTree::new_ct(ct_leaves().into_iter().take(7).collect()).unwrap();
+        assert_eq!(tree.checkpoint(), head);
+        for (index, expected) in paths {
+            let proof = log.audit_proof(index, 7).unwrap();
+            let hashes: Vec<[u8; 32]> = proof.siblings.iter().map(|node| node.hash).collect();
+            assert_eq!(hashes, expected, "d{}", index);
+            assert!(head.verify_inclusion_with::<DomainSeparated>(&leaf(index), &proof));
+
+            // A tree over the same seven leaves gives the same paths
+            assert_eq!(tree.audit_proof(index).unwrap(), proof);
+        }
+        assert_eq!(
+            tree.audit_proof(7),
+            Err(MerkleError::IndexOutOfBounds { index: 7, len: 7 })
+        );
+
+        // And its consistency proof from the three-leaf tree: PROOF(3, D[7]) = [c, d, g, l]
+        assert_eq!(log.consistency_proof(3, 7).unwrap(), vec![c, d, g, l]);
+    }
+
+    #[test]
+    fn test_audit_proofs_for_every_size() {
+        let log = ct_log();
+        for size in 1..=8 {
+            let tree = MerkleTree::new_ct(ct_leaves().into_iter().take(size).collect()).unwrap();
+            let head = log.checkpoint_at(size).unwrap();
+            for index in 0..size {
+                let proof = log.audit_proof(index, size).unwrap();
+                assert_eq!(proof, tree.get_proof(index).unwrap());
+                assert_eq!(proof, tree.audit_proof(index).unwrap());
+                assert!(head.verify_inclusion_with::<DomainSeparated>(&proof.leaf_hash, &proof));
+            }
+        }
+
+        assert_eq!(
+            log.audit_proof(3, 3),
+            Err(MerkleError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+        assert_eq!(
+            log.audit_proof(0, 9),
+            Err(MerkleError::IndexOutOfBounds { index: 9, len: 8 })
+        );
+        assert_eq!(log.audit_proof(0, 0), Err(MerkleError::EmptyInput));
+    }
+
+    #[test]
+    fn test_consistency_between_every_pair_of_sizes() {
+        let mut log = MerkleAccumulator::new();
+        let mut roots = Vec::new();
+        for i in 0..33 {
+            log.append(i.to_string());
+            roots.push(log.root_bytes());
+        }
+
+        for from in 1..=log.len() {
+            // Append-only: the root for an old size never changes as the log grows
+            assert_eq!(log.root_at(from).unwrap(), roots[from - 1]);
+
+            for to in from..=log.len() {
+                let proof = log.consistency_proof(from, to).unwrap();
+                let (old, new) = (roots[from - 1], roots[to - 1]);
+                assert!(verify_consistency(from, to, &old, &new, &proof));
+
+                // A rewritten history must not verify
+                if from < to {
+                    let mut forged = old;
+                    forged[0] ^= 1;
+                    assert!(!verify_consistency(from, to, &forged, &new, &proof));
+
+                    for tampered in 0..proof.len() {
+                        let mut bad = proof.clone();
+                        bad[tampered][31] ^= 1;
+                        assert!(!verify_consistency(from, to, &old, &new, &bad));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_proof_rejects_bad_sizes() {
+        let log = ct_log();
+        assert_eq!(log.consistency_proof(0, 4), Err(MerkleError::EmptyInput));
+        assert_eq!(
+            log.consistency_proof(5, 4),
+            Err(MerkleError::IndexOutOfBounds { index: 5, len: 4 })
+        );
+        assert_eq!(
+            log.consistency_proof(2, 9),
+            Err(MerkleError::IndexOutOfBounds { index: 9, len: 8 })
+        );
+        assert!(!verify_consistency(0, 4, &[0u8; 32], &[0u8; 32], &[]));
+    }
+
+    #[test]
+    fn test_tree_consistency_proofs_match_the_log() {
+        // new_ct has the RFC 6962 shape, so the reference vectors above hold for it too
+        let log = ct_log();
+        let tree = MerkleTree::new_ct(ct_leaves()).unwrap();
+        for from in 1..=8 {
+            for to in from..=8 {
+                let proof = tree.consistency_proof(from, to).unwrap();
+                assert_eq!(proof, log.consistency_proof(from, to).unwrap());
+                assert!(verify_consistency_with::<DomainSeparated>(
+                    from,
+                    to,
+                    &log.root_at(from).unwrap(),
+                    &log.root_at(to).unwrap(),
+                    &proof
+                ));
+            }
+        }
+        assert_eq!(
+            tree.consistency_proof(6, 5),
+            Err(MerkleError::IndexOutOfBounds { index: 6, len: 5 })
+        );
+
+        // A duplicate-last-node tree has another shape, so there is no RFC proof for it
+        let duplicated = MerkleTree::new((0..5).map(|i| i.to_string()).collect()).unwrap();
+        assert!(matches!(
+            duplicated.consistency_proof(2, 5),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+        assert!(matches!(
+            duplicated.audit_proof(2),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+    }
+
+    #[test]
+    fn test_checkpoints_of_a_growing_log() {
+        let leaves = ct_leaves();
+        let mut log = MerkleAccumulator::<RawLeaf, DomainSeparated>::with_hasher();
+        let mut checkpoints = Vec::new();
+        for (i, leaf) in leaves.into_iter().enumerate() {
+            log.append(leaf);
+            if [1, 2, 3, 5, 8].contains(&(i + 1)) {
+                checkpoints.push(log.checkpoint());
+            }
+        }
+
+        // Every older checkpoint extends to every newer one, adjacent or not, and never the other way round
+        for (i, older) in checkpoints.iter().enumerate() {
+            for newer in &checkpoints[i + 1..] {
+                let proof = log.consistency_proof(older.size, newer.size).unwrap();
+                assert!(older.verify_extension_with::<DomainSeparated>(newer, &proof));
+                assert!(!newer.verify_extension_with::<DomainSeparated>(older, &proof));
+
+                // Same root under another size is never consistent
+                let resized = Checkpoint {
+                    root: older.root,
+                    size: newer.size,
+                };
+                assert!(!older.verify_extension_with::<DomainSeparated>(&resized, &proof));
+            }
+        }
+        assert_eq!(log.checkpoint_at(5).unwrap(), checkpoints[3]);
+
+        // The RFC 6962 tree over the same leaves has the same checkpoint, and its proofs check against it
+        let tree = MerkleTree::new_ct(ct_leaves()).unwrap();
+        let head = tree.checkpoint();
+        assert_eq!(head, log.checkpoint());
+        for i in 0..8 {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(head.verify_inclusion_with::<DomainSeparated>(&tree.leaves()[i], &proof));
+
+            // A proof with a step missing is implausible for 8 leaves
+            let mut short = proof.clone();
+            short.siblings.pop();
+            assert!(!head.verify_inclusion_with::<DomainSeparated>(&tree.leaves()[i], &short));
+        }
+        let wrong_size = Checkpoint { size: 7, ..head };
+        let proof = tree.get_proof(7).unwrap();
+        assert!(!wrong_size.verify_inclusion_with::<DomainSeparated>(&tree.leaves()[7], &proof));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_checkpoint_serde_round_trip() {
+        let head = ct_log().checkpoint();
+        let json = serde_json::to_string(&head).unwrap();
+        assert_eq!(serde_json::from_str::<Checkpoint>(&json).unwrap(), head);
+    }
+}
@@ -0,0 +1,241 @@
+// Flat storage for a tree's layers: every hash lives in one Vec, layer after layer, leaves first.
+// Layer i owns the slots from offsets[i] up to the next layer's offset and uses the first lens[i] of them,
+// so `tree[level][index]` is a single lookup at offsets[level] + index.
+//
+// The regions are sized for a leaf capacity C (layer i gets ceil(C / 2^i) slots), so a tree can grow to C leaves
+// without moving a single node. Growing past it lays everything out again at twice the capacity,
+// which keeps `push` amortized O(log n) like the Vec-per-layer layout it replaces.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Index, IndexMut};
+
+#[derive(Clone)]
+pub(crate) struct Layers {
+    nodes: Vec<[u8; 32]>,
+    // Start of every reserved region, one per layer a tree of the reserved capacity would have.
+    offsets: Vec<usize>,
+    // Nodes in use per layer; its length is the number of layers the tree has right now.
+    lens: Vec<usize>,
+}
+
+impl Layers {
+    // A single empty leaf layer with room for `leaf_capacity` leaves (and their parents).
+    pub(crate) fn with_leaf_capacity(leaf_capacity: usize) -> Self {
+        let mut offsets = Vec::new();
+        let mut total = 0;
+        for len in shape(leaf_capacity) {
+            offsets.push(total);
+            total += len;
+        }
+
+        Layers {
+            nodes: vec![[0u8; 32]; total],
+            offsets,
+            lens: vec![0],
+        }
+    }
+
+    // The layers of a full tree over `leaf_count` leaves, every slot still to be written.
+    pub(crate) fn for_leaves(leaf_count: usize) -> Self {
+        let mut layers = Self::with_leaf_capacity(leaf_count);
+        layers.resize(leaf_count);
+        layers
+    }
+
+    // Copies in layers of any shape, e.g. ones read back from storage that still have to be checked.
+    pub(crate) fn from_vecs(layers: Vec<Vec<[u8; 32]>>) -> Self {
+        let mut offsets = Vec::with_capacity(layers.len());
+        let mut lens = Vec::with_capacity(layers.len());
+        let mut nodes = Vec::with_capacity(layers.iter().map(Vec::len).sum());
+        for layer in layers {
+            offsets.push(nodes.len());
+            lens.push(layer.len());
+            nodes.extend(layer);
+        }
+
+        Layers {
+            nodes,
+            offsets,
+            lens,
+        }
+    }
+
+    // Number of layers, leaves and root included.
+    pub(crate) fn len(&self) -> usize {
+        self.lens.len()
+    }
+
+    pub(crate) fn get(&self, level: usize) -> Option<&[[u8; 32]]> {
+        (level < self.len()).then(|| &self[level])
+    }
+
+    pub(crate) fn last(&self) -> Option<&[[u8; 32]]> {
+        self.len().checked_sub(1).map(|level| &self[level])
+    }
+
+    pub(crate) fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &[[u8; 32]]> + ExactSizeIterator + '_ {
+        (0..self.len()).map(|level| &self[level])
+    }
+
+    pub(crate) fn to_vecs(&self) -> Vec<Vec<[u8; 32]>> {
+        self.iter().map(<[[u8; 32]]>::to_vec).collect()
+    }
+
+    // Layer `level` to read from and layer `level + 1` to write into, both borrowed at once.
+    pub(crate) fn split_level_mut(&mut self, level: usize) -> (&[[u8; 32]], &mut [[u8; 32]]) {
+        let (below, above) = self.nodes.split_at_mut(self.offsets[level + 1]);
+        let start = self.offsets[level];
+        (
+            &below[start..start + self.lens[level]],
+            &mut above[..self.lens[level + 1]],
+        )
+    }
+
+    // Gives the layers the shape of a tree over `leaf_count` leaves: ceil(log2(n)) + 1 layers of ceil(n / 2^i) nodes.
+    // Nodes that stay in range keep their hashes; new slots hold stale data until the caller rehashes them.
+    pub(crate) fn resize(&mut self, leaf_count: usize) {
+        let fits = shape(leaf_count).count() <= self.offsets.len()
+            && shape(leaf_count)
+                .enumerate()
+                .all(|(level, len)| len <= self.region_len(level));
+        if !fits {
+            self.relayout(leaf_count.max(2 * self.leaf_capacity()));
+        }
+
+        self.lens.clear();
+        self.lens.extend(shape(leaf_count));
+    }
+
+    // How many leaves fit before the next `resize` has to move everything.
+    pub(crate) fn leaf_capacity(&self) -> usize {
+        self.region_len(0)
+    }
+
+    // Slots reserved for `level`.
+    fn region_len(&self, level: usize) -> usize {
+        let end = self
+            .offsets
+            .get(level + 1)
+            .copied()
+            .unwrap_or(self.nodes.len());
+        end - self.offsets[level]
+    }
+
+    // Moves every layer into a fresh arena sized for `leaf_capacity` leaves.
+    fn relayout(&mut self, leaf_capacity: usize) {
+        let mut grown = Self::with_leaf_capacity(leaf_capacity);
+        for level in 0..self.len() {
+            let start = grown.offsets[level];
+            grown.nodes[start..start + self.lens[level]].copy_from_slice(&self[level]);
+        }
+        grown.lens = core::mem::take(&mut self.lens);
+        *self = grown;
+    }
+}
+
+// Layer lengths of a tree over `leaf_count` leaves, leaves first. Every padding gives ceil(len / 2) parents,
+// and an empty tree still has its (empty) leaf layer, like `with_capacity`.
+fn shape(leaf_count: usize) -> impl Iterator<Item = usize> {
+    core::iter::successors(Some(leaf_count), |&len| (len > 1).then(|| len.div_ceil(2)))
+}
+
+impl Index<usize> for Layers {
+    type Output = [[u8; 32]];
+
+    fn index(&self, level: usize) -> &[[u8; 32]] {
+        let start = self.offsets[level];
+        &self.nodes[start..start + self.lens[level]]
+    }
+}
+
+impl IndexMut<usize> for Layers {
+    fn index_mut(&mut self, level: usize) -> &mut [[u8; 32]] {
+        let start = self.offsets[level];
+        &mut self.nodes[start..start + self.lens[level]]
+    }
+}
+
+// Only the nodes in use count: two trees with the same layers are equal whatever their spare capacity.
+impl PartialEq for Layers {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Layers {}
+
+impl fmt::Debug for Layers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(leaf_count: usize) -> Layers {
+        let mut layers = Layers::for_leaves(leaf_count);
+        // Number every node by its position in the arena
+        for (i, node) in layers.nodes.iter_mut().enumerate() {
+            *node = [i as u8; 32];
+        }
+        layers
+    }
+
+    #[test]
+    fn test_offsets_of_small_and_odd_trees() {
+        let one = filled(1);
+        assert_eq!(one.offsets, [0]);
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0], [[0u8; 32]]);
+
+        let two = filled(2);
+        assert_eq!(two.offsets, [0, 2]);
+        assert_eq!(two.lens, [2, 1]);
+        assert_eq!(two.last().unwrap(), [[2u8; 32]]);
+
+        // 13 -> 7 -> 4 -> 2 -> 1, laid out back to back
+        let thirteen = filled(13);
+        assert_eq!(thirteen.offsets, [0, 13, 20, 24, 26]);
+        assert_eq!(thirteen.lens, [13, 7, 4, 2, 1]);
+        assert_eq!(thirteen.nodes.len(), 27);
+        assert_eq!(thirteen[2][3], [23u8; 32]);
+        assert_eq!(thirteen.get(5), None);
+
+        let mut copy = thirteen.clone();
+        let (below, above) = copy.split_level_mut(1);
+        assert_eq!((below.len(), above.len()), (7, 4));
+        assert_eq!(above[0], [20u8; 32]);
+    }
+
+    #[test]
+    fn test_growing_keeps_nodes_and_spare_room() {
+        let mut layers = filled(5);
+        let before = layers.to_vecs();
+
+        // Past the capacity: everything moves, nothing changes
+        layers.resize(6);
+        assert_eq!(layers.leaf_capacity(), 10);
+        assert_eq!(layers.lens, [6, 3, 2, 1]);
+        for (level, old) in before.iter().enumerate() {
+            assert_eq!(&layers[level][..old.len()], &old[..]);
+        }
+
+        // Within the new capacity: nothing moves at all
+        let offsets = layers.offsets.clone();
+        layers.resize(10);
+        assert_eq!(layers.offsets, offsets);
+        assert_eq!(layers.lens, [10, 5, 3, 2, 1]);
+
+        // Shrinking back to nothing leaves the empty leaf layer
+        layers.resize(0);
+        assert_eq!(layers.len(), 1);
+        assert!(layers[0].is_empty());
+        assert_eq!(layers, Layers::with_leaf_capacity(3));
+    }
+}
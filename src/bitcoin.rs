@@ -0,0 +1,77 @@
+// Bitcoin helpers, enabled with the `bitcoin` feature.
+// Txids and block Merkle roots are shown byte-reversed ("display order"), but hashed in their natural order,
+// so everything has to be flipped on the way in and on the way out.
+
+use crate::{DoubleSha256Hasher, MerkleError, MerkleTree, from_hex, to_hex};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Computes a block's Merkle root from its txids, all in the display order block explorers use.
+/// Each txid is byte-reversed, the double SHA-256 tree is built (odd layers duplicate their last node),
+/// and the root is reversed back for display.
+/// Returns an Error if there are no txids or one of them is not 64 hex characters.
+pub fn bitcoin_block_root(txids_hex: &[&str]) -> Result<String, MerkleError> {
+    let mut leaves = Vec::with_capacity(txids_hex.len());
+    for (position, txid) in txids_hex.iter().enumerate() {
+        let mut bytes = from_hex(txid).map_err(|err| match err {
+            MerkleError::HashDecodingError(reason) => {
+                MerkleError::HashDecodingError(format!("txid {}: {}", position, reason))
+            }
+            other => other,
+        })?;
+        bytes.reverse();
+        leaves.push(bytes);
+    }
+
+    let tree = MerkleTree::<(), DoubleSha256Hasher>::from_hashes_with_hasher(leaves)?;
+
+    let mut root = *tree.root_bytes();
+    root.reverse();
+    Ok(to_hex(&root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_100000_root() {
+        let txids = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ];
+        assert_eq!(
+            bitcoin_block_root(&txids).unwrap(),
+            "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766"
+        );
+
+        // A block with only a coinbase has the coinbase txid as its root
+        assert_eq!(bitcoin_block_root(&txids[..1]).unwrap(), txids[0]);
+    }
+
+    #[test]
+    fn test_block_root_rejects_bad_txids() {
+        assert_eq!(bitcoin_block_root(&[]), Err(MerkleError::EmptyInput));
+
+        let odd_length = "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d8";
+        let err = bitcoin_block_root(&[odd_length]).unwrap_err();
+        assert_eq!(
+            err,
+            MerkleError::HashDecodingError(
+                "txid 0: expected 64 hex characters, got 63".to_string()
+            )
+        );
+
+        // The error names the txid that failed
+        let good = "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87";
+        let not_hex = "zz14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87";
+        let err = bitcoin_block_root(&[good, not_hex]).unwrap_err();
+        assert_eq!(
+            err,
+            MerkleError::HashDecodingError("txid 1: invalid hex pair: zz".to_string())
+        );
+    }
+}
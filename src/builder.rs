@@ -0,0 +1,126 @@
+// Incremental construction: feed items one at a time and get a `MerkleTree` at the end,
+// without first collecting the items themselves into a Vec.
+//
+// Only the 32-byte leaf hashes are kept, so memory is 32 bytes per item however big the items are.
+// A builder that never holds all leaves at once is possible: fold the hashes into one completed subtree
+// per level like `MerkleAccumulator` (the "mountain range"). `compute_root` already does that when only
+// the root is needed, but a full `MerkleTree` has to keep every leaf anyway.
+
+use crate::{Hashable, MerkleError, MerkleHasher, MerkleTree, PaddingStrategy, Sha256Hasher};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// Collects leaves for a `MerkleTree` one `push` at a time and builds all layers in `finalize`.
+/// The tree is the same as `MerkleTree::with_padding` over the same items in the same order.
+#[derive(Debug)]
+pub struct MerkleTreeBuilder<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    leaves: Vec<[u8; 32]>,
+    padding: PaddingStrategy,
+    // Marker to link the builder to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+impl<T: Hashable> MerkleTreeBuilder<T> {
+    /// Creates a builder for a SHA-256 tree with the default padding.
+    pub fn new() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Default for MerkleTreeBuilder<T, H> {
+    fn default() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTreeBuilder<T, H> {
+    /// Same as `new`, for a tree that combines hashes with `H`.
+    pub fn with_hasher() -> Self {
+        MerkleTreeBuilder {
+            leaves: Vec::new(),
+            padding: PaddingStrategy::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reserves room for `additional` more leaves, e.g. when the item count is known up front.
+    pub fn reserve(&mut self, additional: usize) {
+        self.leaves.reserve(additional);
+    }
+
+    /// Pads odd layers of the finished tree with `padding` instead of the default.
+    pub fn padding(mut self, padding: PaddingStrategy) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Hashes `item` as the next leaf; the item itself is dropped right away.
+    pub fn push(&mut self, item: T) {
+        self.leaves.push(item.hash_with::<H>());
+    }
+
+    /// Number of leaves pushed so far.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// True until the first `push`.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Builds the tree over everything pushed. Returns an Error if nothing was.
+    pub fn finalize(self) -> Result<MerkleTree<T, H>, MerkleError> {
+        MerkleTree::from_leaf_layer(self.leaves, self.padding)
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Extend<T> for MerkleTreeBuilder<T, H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.leaves
+            .extend(iter.into_iter().map(|item| item.hash_with::<H>()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DomainSeparated;
+    use alloc::string::{String, ToString};
+
+    #[test]
+    fn test_builder_matches_new() {
+        let data: Vec<String> = (0..37).map(|i| i.to_string()).collect();
+
+        let mut builder = MerkleTreeBuilder::new();
+        for item in data.iter().cloned() {
+            builder.push(item);
+        }
+        assert_eq!(builder.len(), 37);
+        let built = builder.finalize().unwrap();
+        let expected = MerkleTree::new(data).unwrap();
+        assert_eq!(built.layers(), expected.layers());
+        assert!(built.get_proof(36).unwrap().verify());
+    }
+
+    #[test]
+    fn test_builder_hasher_and_padding() {
+        let mut builder = MerkleTreeBuilder::<String, DomainSeparated>::with_hasher()
+            .padding(PaddingStrategy::PromoteOdd);
+        builder.reserve(5);
+        builder.extend((0..5).map(|i| i.to_string()));
+
+        let expected = MerkleTree::<String, DomainSeparated>::with_padding(
+            (0..5).map(|i| i.to_string()).collect(),
+            PaddingStrategy::PromoteOdd,
+        )
+        .unwrap();
+        let built = builder.finalize().unwrap();
+        assert_eq!(built.root_bytes(), expected.root_bytes());
+        assert_eq!(built.padding(), PaddingStrategy::PromoteOdd);
+
+        let empty = MerkleTreeBuilder::<String>::default();
+        assert!(empty.is_empty());
+        assert_eq!(empty.finalize().err(), Some(MerkleError::EmptyInput));
+    }
+}
@@ -0,0 +1,42 @@
+// What `#[derive(Hashable)]` expands to calls into here. Not part of the public API.
+//
+// A struct is encoded as the bytes of each field in declaration order, each one behind its length as a u64
+// little-endian, so ("ab", "c") and ("a", "bc") can't collide. The bytes of a field come from `AsRef<[u8]>` if it
+// has it, else from its `Display` output. The choice is made at compile time with autoref specialization:
+// `(&&FieldBytes(&field)).field_bytes()` finds the `AsRef` impl one reference deeper before falling back.
+
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use core::fmt::Display;
+
+pub use alloc::vec::Vec;
+
+pub struct FieldBytes<'a, T: ?Sized>(pub &'a T);
+
+pub trait ViaAsRef {
+    fn field_bytes(&self) -> Cow<'_, [u8]>;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> ViaAsRef for &FieldBytes<'_, T> {
+    fn field_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.0.as_ref())
+    }
+}
+
+pub trait ViaDisplay {
+    #[deprecated(
+        note = "#[derive(Hashable)] hashes this field through `to_string()`; give it `AsRef<[u8]>` or implement `Hashable` by hand"
+    )]
+    fn field_bytes(&self) -> Cow<'_, [u8]>;
+}
+
+impl<T: Display + ?Sized> ViaDisplay for FieldBytes<'_, T> {
+    fn field_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(self.0.to_string().into_bytes())
+    }
+}
+
+pub fn push_field(encoded: &mut Vec<u8>, bytes: &[u8]) {
+    encoded.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    encoded.extend_from_slice(bytes);
+}
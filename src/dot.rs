@@ -0,0 +1,104 @@
+// Graphviz export, for looking at a tree's shape instead of reading hex dumps.
+
+use crate::{Hashable, MerkleHasher, MerkleTree, to_hex};
+use alloc::string::String;
+use core::fmt::Write;
+
+// Beyond this many leaves the bottom levels are left out, so `dot -Tsvg` stays readable.
+const MAX_DRAWN_NODES: usize = 32;
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Renders the tree as a Graphviz DOT graph, root at the top, e.g. `dot -Tsvg tree.dot -o tree.svg`.
+    /// Each node is labeled with the first 8 hex characters of its hash, every level is one rank,
+    /// and each node has edges to its children.
+    /// Trees with more than 32 leaves only show the levels with at most 32 nodes, and say so in a comment.
+    pub fn to_dot(&self) -> String {
+        // The lowest level that is still drawn
+        let lowest = self
+            .layers
+            .iter()
+            .position(|layer| layer.len() <= MAX_DRAWN_NODES)
+            .unwrap_or(self.depth());
+
+        let mut dot = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(dot, "digraph MerkleTree {{");
+        if lowest > 0 {
+            let _ = writeln!(
+                dot,
+                "    // {} leaves: levels 0 to {} are not shown",
+                self.leaf_count(),
+                lowest - 1
+            );
+        }
+        let _ = writeln!(dot, "    node [shape=box, fontname=\"monospace\"];");
+
+        // 1. One rank per level, root first
+        for level in (lowest..self.layers.len()).rev() {
+            let _ = write!(dot, "    {{ rank=same;");
+            for (index, hash) in self.layers[level].iter().enumerate() {
+                let _ = write!(
+                    dot,
+                    " n{}_{} [label=\"{}\"];",
+                    level,
+                    index,
+                    to_hex(&hash[..4])
+                );
+            }
+            let _ = writeln!(dot, " }}");
+        }
+
+        // 2. Edges from every parent to the one or two nodes below it
+        for level in (lowest + 1..self.layers.len()).rev() {
+            let below = self.layers[level - 1].len();
+            for index in 0..self.layers[level].len() {
+                for child in (index * 2..below).take(2) {
+                    let _ = writeln!(dot, "    n{}_{} -> n{}_{};", level, index, level - 1, child);
+                }
+            }
+        }
+
+        dot.push('}');
+        dot.push('\n');
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_four_leaf_snapshot() {
+        let data = ["A", "B", "C", "D"].map(|s| s.to_string()).to_vec();
+        let tree = MerkleTree::new(data).unwrap();
+        let expected = r#"digraph MerkleTree {
+    node [shape=box, fontname="monospace"];
+    { rank=same; n2_0 [label="1b3faa3f"]; }
+    { rank=same; n1_0 [label="63956f0c"]; n1_1 [label="98a2fbfd"]; }
+    { rank=same; n0_0 [label="559aead0"]; n0_1 [label="df7e70e5"]; n0_2 [label="6b23c0d5"]; n0_3 [label="3f39d5c3"]; }
+    n2_0 -> n1_0;
+    n2_0 -> n1_1;
+    n1_0 -> n0_0;
+    n1_0 -> n0_1;
+    n1_1 -> n0_2;
+    n1_1 -> n0_3;
+}
+"#;
+        assert_eq!(tree.to_dot(), expected);
+    }
+
+    #[test]
+    fn test_large_trees_are_abbreviated() {
+        let tree = MerkleTree::new((0..100).map(|i| i.to_string()).collect()).unwrap();
+        let dot = tree.to_dot();
+
+        // 100 -> 50 -> 25 nodes: the two bottom levels are dropped
+        assert!(dot.contains("// 100 leaves: levels 0 to 1 are not shown"));
+        assert!(!dot.contains("n1_0 "));
+        assert!(dot.contains("n2_24 [label="));
+        assert!(dot.contains("n3_12 -> n2_24;"));
+        assert!(!dot.contains("n2_0 ->"));
+    }
+}
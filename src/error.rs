@@ -0,0 +1,70 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Everything that can go wrong when building or querying a Merkle Tree.
+/// Every fallible public function in the crate returns this type, so it works with `?` next to your own errors.
+/// New variants may be added as the crate grows, so `match` on it with a `_` arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MerkleError {
+    /// The tree was asked to build itself from nothing.
+    EmptyInput,
+    /// An index past the end of a leaf list, layer list or value list.
+    IndexOutOfBounds {
+        /// The requested index.
+        index: usize,
+        /// The number of available elements.
+        len: usize,
+    },
+    /// A hash could not be decoded from its text form.
+    HashDecodingError(String),
+    /// A set of restored layers does not form a valid tree.
+    InvalidLayers(String),
+    /// A leaf value does not fit the type it is supposed to be encoded as.
+    InvalidLeafValue(String),
+    /// A proof could not be read from its text form.
+    InvalidProof(String),
+    /// Two trees that had to be the same size were not: (this tree's leaves, the other tree's leaves)
+    LeafCountMismatch(usize, usize),
+    /// A k-ary tree was asked for fewer than two children per node.
+    InvalidBranchingFactor(usize),
+    /// A sparse tree was asked for a depth outside 1..=256 bits.
+    InvalidDepth(usize),
+    /// Serialized data was written in a format version this build does not know.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::EmptyInput => write!(f, "Cannot create a Merkle Tree with no data."),
+            MerkleError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} is out of bounds for {} elements.", index, len)
+            }
+            MerkleError::HashDecodingError(reason) => {
+                write!(f, "Could not decode hash: {}", reason)
+            }
+            MerkleError::InvalidLayers(reason) => write!(f, "Invalid tree layers: {}", reason),
+            MerkleError::InvalidLeafValue(reason) => write!(f, "Invalid leaf value: {}", reason),
+            MerkleError::InvalidProof(reason) => write!(f, "Invalid proof: {}", reason),
+            MerkleError::LeafCountMismatch(ours, theirs) => {
+                write!(
+                    f,
+                    "Trees have different leaf counts: {} and {}.",
+                    ours, theirs
+                )
+            }
+            MerkleError::InvalidBranchingFactor(k) => {
+                write!(f, "Branching factor must be at least 2, got {}.", k)
+            }
+            MerkleError::InvalidDepth(depth) => {
+                write!(f, "Depth must be between 1 and 256 bits, got {}.", depth)
+            }
+            MerkleError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version {}.", version)
+            }
+        }
+    }
+}
+
+impl core::error::Error for MerkleError {}
@@ -0,0 +1,164 @@
+// Forests: one tree per shard and a tree over the shard roots, so a coordinator commits to every shard
+// with a single super-root. The tree over the roots is only built when someone asks for it.
+
+use crate::{Hashable, MerkleError, MerkleHasher, MerkleProof, MerkleTree, Sha256Hasher, to_hex};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+
+/// A list of shard trees and the super-root over their roots, in shard order.
+/// The super tree takes the shard roots as its leaves unchanged, so a `shard_proof` starts from a shard's `root_bytes`.
+/// It is built on the first `super_root` or `shard_proof` and rebuilt after `push_shard` or `shard_mut`.
+#[derive(Debug)]
+pub struct MerkleForest<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    shards: Vec<MerkleTree<T, H>>,
+    // The tree over the shard roots, dropped whenever a shard may have changed.
+    super_tree: OnceCell<MerkleTree<(), H>>,
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleForest<T, H> {
+    /// Groups `shards` into a forest. Returns an Error if there are none.
+    pub fn new(shards: Vec<MerkleTree<T, H>>) -> Result<Self, MerkleError> {
+        if shards.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        Ok(MerkleForest {
+            shards,
+            super_tree: OnceCell::new(),
+        })
+    }
+
+    /// Adds a shard on the right. The super-root is recomputed the next time it is read.
+    pub fn push_shard(&mut self, shard: MerkleTree<T, H>) {
+        self.shards.push(shard);
+        self.super_tree = OnceCell::new();
+    }
+
+    /// Number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// All shards, in order.
+    pub fn shards(&self) -> &[MerkleTree<T, H>] {
+        &self.shards
+    }
+
+    /// The shard at `shard_index`, or None if there is no such shard.
+    pub fn shard(&self, shard_index: usize) -> Option<&MerkleTree<T, H>> {
+        self.shards.get(shard_index)
+    }
+
+    /// Mutable access to one shard, e.g. for `update_leaf`. The super-root is recomputed the next time it is read.
+    pub fn shard_mut(&mut self, shard_index: usize) -> Option<&mut MerkleTree<T, H>> {
+        self.super_tree = OnceCell::new();
+        self.shards.get_mut(shard_index)
+    }
+
+    /// The root of the tree over all shard roots.
+    pub fn super_root(&self) -> [u8; 32] {
+        *self.super_tree().root_bytes()
+    }
+
+    /// Returns the super-root as a 64-character hex string.
+    pub fn super_root_hex(&self) -> String {
+        to_hex(&self.super_root())
+    }
+
+    /// Proves that shard `shard_index`'s root is part of the super-root.
+    /// Its `leaf_hash` is the shard's root; check it with `verify_with::<H>()` like any other proof.
+    pub fn shard_proof(&self, shard_index: usize) -> Result<MerkleProof, MerkleError> {
+        self.super_tree().get_proof(shard_index)
+    }
+
+    fn super_tree(&self) -> &MerkleTree<(), H> {
+        self.super_tree.get_or_init(|| {
+            let roots = self
+                .shards
+                .iter()
+                .map(|shard| *shard.root_bytes())
+                .collect();
+            // `new` and `push_shard` keep at least one shard, so there is always a root to build from
+            MerkleTree::from_hashes_with_hasher(roots).expect("a forest has at least one shard")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DomainSeparated;
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn shard(name: &str) -> MerkleTree<String> {
+        MerkleTree::new((0..4).map(|i| format!("{}-{}", name, i)).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_super_root_and_shard_proofs() {
+        let forest = MerkleForest::new(["a", "b", "c", "d"].map(shard).to_vec()).unwrap();
+        assert_eq!(forest.shard_count(), 4);
+
+        // The super-root is the root of a tree over the four shard roots
+        let roots: Vec<[u8; 32]> = forest.shards().iter().map(|s| *s.root_bytes()).collect();
+        assert_eq!(
+            forest.super_root(),
+            *MerkleTree::from_hashes(roots.clone()).unwrap().root_bytes()
+        );
+
+        for (i, root) in roots.iter().enumerate() {
+            let proof = forest.shard_proof(i).unwrap();
+            assert_eq!(&proof.leaf_hash, root);
+            assert!(proof.verify_against(root, &forest.super_root()));
+        }
+        assert!(forest.shard_proof(4).is_err());
+    }
+
+    #[test]
+    fn test_changes_recompute_the_super_root() {
+        let mut forest = MerkleForest::new(["a", "b", "c", "d"].map(shard).to_vec()).unwrap();
+        let before = forest.super_root();
+
+        // Changing one leaf of one shard moves the super-root
+        forest
+            .shard_mut(2)
+            .unwrap()
+            .update_leaf(1, &"changed".to_string())
+            .unwrap();
+        let changed = forest.super_root();
+        assert_ne!(changed, before);
+        assert!(
+            forest
+                .shard_proof(2)
+                .unwrap()
+                .verify_against(forest.shard(2).unwrap().root_bytes(), &changed)
+        );
+
+        forest.push_shard(shard("e"));
+        assert_eq!(forest.shard_count(), 5);
+        assert_ne!(forest.super_root(), changed);
+        assert!(forest.shard_proof(4).unwrap().verify());
+
+        assert_eq!(
+            MerkleForest::<String>::new(vec![]).err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_other_hasher() {
+        let shards: Vec<MerkleTree<String, DomainSeparated>> = (0..3)
+            .map(|s| {
+                MerkleTree::with_hasher((0..4).map(|i| format!("{}-{}", s, i)).collect()).unwrap()
+            })
+            .collect();
+        let forest = MerkleForest::new(shards).unwrap();
+        let proof = forest.shard_proof(2).unwrap();
+        assert!(proof.verify_with::<DomainSeparated>());
+        assert!(!proof.verify());
+        assert_eq!(forest.super_root_hex().len(), 64);
+    }
+}
@@ -0,0 +1,320 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use digest::Digest;
+use digest::consts::U32;
+
+use crate::hash_pair_with_prefix;
+
+/// A contract for hash algorithms the tree can be built with.
+/// Implement this to swap SHA-256 for BLAKE2b, Keccak-256, or anything else with a 32-byte output.
+pub trait MerkleHasher {
+    fn hash(data: &[u8]) -> [u8; 32];
+
+    /// Hashes raw leaf data into the value stored in the bottom layer. Plain hashers just hash it.
+    fn hash_leaf(data: &[u8]) -> [u8; 32] {
+        Self::hash(data)
+    }
+
+    /// Turns an already computed leaf fingerprint into the value stored in the bottom layer.
+    /// Plain hashers keep it as is.
+    fn wrap_leaf(digest: &[u8; 32]) -> [u8; 32] {
+        *digest
+    }
+
+    /// Combines two child hashes into their parent. Plain hashers hash `left || right`.
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut combined = [0u8; 64];
+        combined[..32].copy_from_slice(left);
+        combined[32..].copy_from_slice(right);
+        Self::hash(&combined)
+    }
+
+    /// Hashes the concatenation of `parts` (e.g. a prefix byte and the data) as if they were one input.
+    /// The default copies them into one buffer; hashers with a streaming API override it to feed them one by one.
+    fn hash_parts(parts: &[&[u8]]) -> [u8; 32] {
+        Self::hash(&parts.concat())
+    }
+
+    /// Combines all children of a node in a k-ary tree (see `KaryMerkleTree`) into their parent.
+    /// Plain hashers hash the concatenation, so two children give the same parent as `hash_nodes`.
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        Self::hash(&children.concat())
+    }
+}
+
+/// Adapts any `digest::Digest` with a 32-byte output (SHA-256, SHA-512/256, SHA3-256, BLAKE2s-256, ...)
+/// into a `MerkleHasher`. Wider digests such as SHA-384 don't fit the tree's 32-byte nodes;
+/// use their truncated variants (e.g. SHA-512/256) instead.
+#[derive(Debug)]
+pub struct DigestHasher<D>(PhantomData<D>);
+
+impl<D: Digest<OutputSize = U32>> MerkleHasher for DigestHasher<D> {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        // 1. Initialize the hashing engine.
+        // We use 'mut' (mutable) because the hasher's internal state changes as we feed it data.
+        let mut hasher = D::new();
+
+        // 2. Feed it the raw bytes. Hashing algorithms operate on binary data, not text directly.
+        hasher.update(data);
+
+        // 3. "Finalize" the calculation.
+        // This consumes the hasher and spits out a fixed-size byte array (32 bytes).
+        hasher.finalize().into()
+    }
+
+    // The Digest API takes input in pieces, so nothing has to be concatenated first
+    fn hash_parts(parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = D::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        D::new()
+            .chain_update(left)
+            .chain_update(right)
+            .finalize()
+            .into()
+    }
+
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        let mut hasher = D::new();
+        for child in children {
+            hasher.update(child);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// The default hasher: plain SHA-256 from the `sha2` crate.
+pub type Sha256Hasher = DigestHasher<sha2::Sha256>;
+
+/// Bitcoin's double SHA-256: `SHA256(SHA256(data))`, used for both txids and the block Merkle tree.
+#[derive(Debug)]
+pub struct DoubleSha256Hasher;
+
+impl MerkleHasher for DoubleSha256Hasher {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        Sha256Hasher::hash(&Sha256Hasher::hash(data))
+    }
+}
+
+/// Keccak-256 as used by Ethereum and Solidity's `keccak256`, available with the `keccak` feature.
+/// Note this is the original Keccak padding, not the standardized SHA3-256.
+#[cfg(feature = "keccak")]
+pub type Keccak256Hasher = DigestHasher<sha3::Keccak256>;
+
+/// The hashing of OpenZeppelin's Merkle trees, available with the `keccak` feature:
+/// leaves are `keccak256(keccak256(leaf_bytes))` and pairs are sorted before hashing (see `SortedPairs`),
+/// so proofs pass Solidity's `MerkleProof.verify`.
+/// A leaf that only comes as a digest is taken to be the inner `keccak256` and gets hashed once more.
+#[cfg(feature = "keccak")]
+#[derive(Debug)]
+pub struct OpenZeppelinHasher;
+
+#[cfg(feature = "keccak")]
+impl MerkleHasher for OpenZeppelinHasher {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        Keccak256Hasher::hash(data)
+    }
+
+    fn hash_leaf(data: &[u8]) -> [u8; 32] {
+        Self::wrap_leaf(&Keccak256Hasher::hash(data))
+    }
+
+    fn wrap_leaf(digest: &[u8; 32]) -> [u8; 32] {
+        Keccak256Hasher::hash(digest)
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        SortedPairs::<Keccak256Hasher>::hash_nodes(left, right)
+    }
+
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        SortedPairs::<Keccak256Hasher>::hash_children(children)
+    }
+}
+
+/// BLAKE3 hasher, available with the `blake3` feature.
+#[cfg(feature = "blake3")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Blake3Hasher;
+
+#[cfg(feature = "blake3")]
+impl MerkleHasher for Blake3Hasher {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        blake3::hash(data).into()
+    }
+
+    fn hash_parts(parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        Self::hash_parts(&[left, right])
+    }
+
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        for child in children {
+            hasher.update(child);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Wraps a hasher with RFC 6962 domain separation:
+/// leaves are hashed as `H(0x00 || leaf_bytes)` and internal nodes as `H(0x01 || left || right)`,
+/// so an internal node can never be passed off as a leaf (second-preimage protection).
+#[derive(Debug)]
+pub struct DomainSeparated<H: MerkleHasher = Sha256Hasher>(PhantomData<H>);
+
+/// Prefix byte for leaf hashes.
+pub const LEAF_PREFIX: u8 = 0x00;
+/// Prefix byte for internal node hashes.
+pub const NODE_PREFIX: u8 = 0x01;
+
+impl<H: MerkleHasher> MerkleHasher for DomainSeparated<H> {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        H::hash(data)
+    }
+
+    fn hash_leaf(data: &[u8]) -> [u8; 32] {
+        H::hash_parts(&[&[LEAF_PREFIX], data])
+    }
+
+    fn hash_parts(parts: &[&[u8]]) -> [u8; 32] {
+        H::hash_parts(parts)
+    }
+
+    // A leaf that only comes as a digest still gets the leaf prefix, so it can't collide with a node
+    fn wrap_leaf(digest: &[u8; 32]) -> [u8; 32] {
+        Self::hash_leaf(digest)
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        hash_pair_with_prefix::<H>(NODE_PREFIX, left, right)
+    }
+
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(children.len() + 1);
+        parts.push(&[NODE_PREFIX]);
+        parts.extend(children.iter().map(|child| &child[..]));
+        H::hash_parts(&parts)
+    }
+}
+
+/// The original pairing scheme of this crate, kept for anyone who persisted roots from it:
+/// internal nodes are `H(hex(left) || hex(right))` over the 128 hex characters instead of the 64 raw bytes.
+/// Nothing else reproduces these roots, so only use it to check old data.
+#[derive(Debug)]
+pub struct LegacyHexConcat<H: MerkleHasher = Sha256Hasher>(PhantomData<H>);
+
+impl<H: MerkleHasher> MerkleHasher for LegacyHexConcat<H> {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        H::hash(data)
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        // Hex goes into stack buffers instead of two Strings per node
+        H::hash_parts(&[&hex_digits(left), &hex_digits(right)])
+    }
+
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        let digits: Vec<[u8; 64]> = children.iter().map(hex_digits).collect();
+        let parts: Vec<&[u8]> = digits.iter().map(|hex| &hex[..]).collect();
+        H::hash_parts(&parts)
+    }
+}
+
+// The 64 lowercase hex digits of a hash, same as `to_hex` but without allocating.
+fn hex_digits(hash: &[u8; 32]) -> [u8; 64] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = [0u8; 64];
+    for (i, byte) in hash.iter().enumerate() {
+        hex[2 * i] = DIGITS[(byte >> 4) as usize];
+        hex[2 * i + 1] = DIGITS[(byte & 0x0f) as usize];
+    }
+    hex
+}
+
+/// Wraps a hasher so the two children are put in byte order before they are combined:
+/// `hash_nodes(a, b) == hash_nodes(b, a)`. This is the "sorted pairs" scheme Solidity verifiers such as
+/// OpenZeppelin's `MerkleProof.verify` expect, where a proof is just a list of sibling hashes with no directions.
+/// Leaves are hashed exactly as `H` hashes them.
+#[derive(Debug)]
+pub struct SortedPairs<H: MerkleHasher = Sha256Hasher>(PhantomData<H>);
+
+impl<H: MerkleHasher> MerkleHasher for SortedPairs<H> {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        H::hash(data)
+    }
+
+    fn hash_leaf(data: &[u8]) -> [u8; 32] {
+        H::hash_leaf(data)
+    }
+
+    fn wrap_leaf(digest: &[u8; 32]) -> [u8; 32] {
+        H::wrap_leaf(digest)
+    }
+
+    fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        // Arrays compare byte by byte, so this is the lexicographic order
+        if left <= right {
+            H::hash_nodes(left, right)
+        } else {
+            H::hash_nodes(right, left)
+        }
+    }
+
+    fn hash_children(children: &[[u8; 32]]) -> [u8; 32] {
+        let mut sorted = children.to_vec();
+        sorted.sort_unstable();
+        H::hash_children(&sorted)
+    }
+}
+
+// A readable name for the hashers this crate ships, told apart by the same fingerprint the binary tree format
+// stores, so wrappers and aliases need no registry. Anything else is shown by that fingerprint.
+pub(crate) fn hasher_name<H: MerkleHasher>() -> String {
+    let id = crate::wire::hasher_id::<H>();
+    let known = [
+        (crate::wire::hasher_id::<Sha256Hasher>(), "SHA-256"),
+        (
+            crate::wire::hasher_id::<DoubleSha256Hasher>(),
+            "double SHA-256",
+        ),
+        (
+            crate::wire::hasher_id::<DomainSeparated>(),
+            "SHA-256 (RFC 6962)",
+        ),
+        (
+            crate::wire::hasher_id::<SortedPairs>(),
+            "SHA-256 (sorted pairs)",
+        ),
+        (
+            crate::wire::hasher_id::<LegacyHexConcat>(),
+            "SHA-256 (hex concat)",
+        ),
+        #[cfg(feature = "keccak")]
+        (crate::wire::hasher_id::<Keccak256Hasher>(), "Keccak-256"),
+        #[cfg(feature = "keccak")]
+        (
+            crate::wire::hasher_id::<OpenZeppelinHasher>(),
+            "Keccak-256 (OpenZeppelin)",
+        ),
+        #[cfg(feature = "blake3")]
+        (crate::wire::hasher_id::<Blake3Hasher>(), "BLAKE3"),
+    ];
+    match known.iter().find(|(known_id, _)| *known_id == id) {
+        Some((_, name)) => String::from(*name),
+        None => alloc::format!("hasher {}", crate::to_hex(&id)),
+    }
+}
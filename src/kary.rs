@@ -0,0 +1,261 @@
+// k-ary Merkle trees: every node hashes up to k children at once instead of a pair,
+// which makes the tree log_k(n) deep at the cost of k - 1 siblings per proof level.
+
+use crate::{Hashable, MerkleError, MerkleHasher, Sha256Hasher, to_hex};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A Merkle Tree where each internal node has up to `branching_factor` children.
+/// A short last group is padded by repeating its last hash until it has k entries,
+/// so with k = 2 this is exactly the default binary `MerkleTree`.
+#[derive(Debug)]
+pub struct KaryMerkleTree<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    // layers[0] = leaves, layers[last] = root, like `MerkleTree`.
+    layers: Vec<Vec<[u8; 32]>>,
+    branching_factor: usize,
+    // Marker to link the tree to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+/// Proof that one leaf is part of a `KaryMerkleTree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KaryProof {
+    /// Position of the leaf in the bottom layer. It also gives the leaf's slot in each group on the way up.
+    pub leaf_index: usize,
+    /// The fingerprint of the leaf being proven.
+    pub leaf_hash: [u8; 32],
+    /// For each level from the leaves up, the other k - 1 members of the group, in order, without the node itself.
+    /// Padding copies of a short group's last hash are included.
+    pub siblings: Vec<Vec<[u8; 32]>>,
+    /// The root of the tree the proof was taken from.
+    pub root: [u8; 32],
+}
+
+impl<T: Hashable> KaryMerkleTree<T> {
+    /// Creates a SHA-256 tree with `branching_factor` children per node. This is the k-ary constructor;
+    /// `MerkleTree` stays binary because each `MerkleProof` level holds exactly one sibling.
+    /// Returns an Error if the data is empty or `branching_factor` is below 2.
+    pub fn new(data: Vec<T>, branching_factor: usize) -> Result<Self, MerkleError> {
+        Self::with_hasher(data, branching_factor)
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> KaryMerkleTree<T, H> {
+    /// Same as `new`, combining hashes with `H::hash_children`.
+    pub fn with_hasher(data: Vec<T>, branching_factor: usize) -> Result<Self, MerkleError> {
+        if branching_factor < 2 {
+            return Err(MerkleError::InvalidBranchingFactor(branching_factor));
+        }
+        if data.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        // 1. Hash the leaves, then fold groups of k until a single root is left
+        let mut layers = Vec::new();
+        layers.push(
+            data.iter()
+                .map(|item| item.hash_with::<H>())
+                .collect::<Vec<_>>(),
+        );
+        while layers[layers.len() - 1].len() > 1 {
+            let layer = &layers[layers.len() - 1];
+            let next_layer = layer
+                .chunks(branching_factor)
+                .map(|group| hash_group::<H>(group, branching_factor))
+                .collect();
+            layers.push(next_layer);
+        }
+
+        Ok(KaryMerkleTree {
+            layers,
+            branching_factor,
+            _marker: PhantomData,
+        })
+    }
+
+    /// How many children each internal node has.
+    pub fn branching_factor(&self) -> usize {
+        self.branching_factor
+    }
+
+    /// How many levels sit above the leaves: ceil(log_k(n)).
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    /// How many leaves the tree was built from.
+    pub fn leaf_count(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    /// Every layer of the tree, leaves first and root last.
+    pub fn layers(&self) -> &[Vec<[u8; 32]>] {
+        &self.layers
+    }
+
+    /// The raw 32-byte Merkle Root.
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        &self.layers[self.layers.len() - 1][0]
+    }
+
+    /// Returns the Merkle Root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(self.root_bytes())
+    }
+
+    /// Collects the k - 1 group members of the leaf at `leaf_index` on every level up to the root.
+    pub fn get_proof(&self, leaf_index: usize) -> Result<KaryProof, MerkleError> {
+        let leaf_count = self.leaf_count();
+        if leaf_index >= leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: leaf_count,
+            });
+        }
+
+        let k = self.branching_factor;
+        let mut siblings = Vec::with_capacity(self.depth());
+        let mut index = leaf_index;
+
+        for layer in &self.layers[..self.layers.len() - 1] {
+            // Our group starts at the multiple of k at or below us; short groups get their padding here too
+            let start = index - index % k;
+            let mut others = padded_group(&layer[start..layer.len().min(start + k)], k);
+            others.remove(index % k);
+            siblings.push(others);
+
+            index /= k;
+        }
+
+        Ok(KaryProof {
+            leaf_index,
+            leaf_hash: self.layers[0][leaf_index],
+            siblings,
+            root: *self.root_bytes(),
+        })
+    }
+}
+
+impl KaryProof {
+    /// Recomputes the root from `leaf_hash` and the siblings and checks it against the proof's own `root`.
+    pub fn verify(&self) -> bool {
+        self.verify_with::<Sha256Hasher>()
+    }
+
+    /// Same as `verify`, for trees built with a hasher other than SHA-256.
+    pub fn verify_with<H: MerkleHasher>(&self) -> bool {
+        let Some(first) = self.siblings.first() else {
+            // A single-leaf tree: the leaf is the root
+            return self.leaf_hash == self.root;
+        };
+        let k = first.len() + 1;
+
+        let mut current = self.leaf_hash;
+        let mut index = self.leaf_index;
+        for others in &self.siblings {
+            // Every level has to hold exactly the k - 1 other group members
+            if others.len() + 1 != k {
+                return false;
+            }
+            let mut group = others.clone();
+            group.insert(index % k, current);
+            current = H::hash_children(&group);
+            index /= k;
+        }
+
+        // Nothing may be left over once the root is reached
+        index == 0 && current == self.root
+    }
+}
+
+// Pads a short last group by repeating its last hash until it has k entries.
+fn padded_group(group: &[[u8; 32]], k: usize) -> Vec<[u8; 32]> {
+    let mut padded = group.to_vec();
+    padded.resize(k, group[group.len() - 1]);
+    padded
+}
+
+fn hash_group<H: MerkleHasher>(group: &[[u8; 32]], k: usize) -> [u8; 32] {
+    if group.len() == k {
+        H::hash_children(group)
+    } else {
+        H::hash_children(&padded_group(group, k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainSeparated, MerkleTree, hash_data};
+
+    #[test]
+    fn test_four_ary_tree_of_sixteen_leaves() {
+        let data: Vec<String> = (0..16).map(|i| i.to_string()).collect();
+        let tree = KaryMerkleTree::new(data, 4).unwrap();
+        assert_eq!(tree.depth(), 2);
+
+        // Work the root out by hand: four groups of four leaves, then one group of four parents
+        let leaves: Vec<[u8; 32]> = (0..16)
+            .map(|i| hash_data(i.to_string().as_bytes()))
+            .collect();
+        let parents: Vec<[u8; 32]> = leaves
+            .chunks(4)
+            .map(|group| hash_data(&group.concat()))
+            .collect();
+        assert_eq!(tree.root_bytes(), &hash_data(&parents.concat()));
+
+        for i in 0..16 {
+            let proof = tree.get_proof(i).unwrap();
+            assert_eq!(proof.siblings.len(), 2);
+            assert!(proof.siblings.iter().all(|level| level.len() == 3));
+            assert!(proof.verify());
+        }
+        assert!(tree.get_proof(16).is_err());
+    }
+
+    #[test]
+    fn test_binary_case_matches_merkle_tree() {
+        for size in 1..12 {
+            let data: Vec<String> = (0..size).map(|i| i.to_string()).collect();
+            let kary = KaryMerkleTree::new(data.clone(), 2).unwrap();
+            let binary = MerkleTree::new(data.clone()).unwrap();
+            assert_eq!(kary.layers(), binary.layers());
+
+            let ct = KaryMerkleTree::<String, DomainSeparated>::with_hasher(data, 2).unwrap();
+            assert!((0..size).all(|i| ct.get_proof(i).unwrap().verify_with::<DomainSeparated>()));
+        }
+    }
+
+    #[test]
+    fn test_short_groups_and_tampering() {
+        // 10 leaves with k = 3: groups of 3, 3, 3 and a lone leaf padded to 3 copies
+        let data: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let tree = KaryMerkleTree::new(data, 3).unwrap();
+        assert_eq!(tree.layers()[1].len(), 4);
+        assert_eq!(tree.depth(), 3);
+
+        let last = tree.layers()[0][9];
+        assert_eq!(tree.layers()[1][3], hash_data(&[last, last, last].concat()));
+
+        let mut proof = tree.get_proof(9).unwrap();
+        assert_eq!(proof.siblings[0], vec![last, last]);
+        assert!(proof.verify());
+
+        proof.siblings[1][0][0] ^= 1;
+        assert!(!proof.verify());
+
+        proof = tree.get_proof(4).unwrap();
+        proof.leaf_index = 3;
+        assert!(!proof.verify());
+
+        assert_eq!(
+            KaryMerkleTree::new(vec!["A".to_string()], 1).err(),
+            Some(MerkleError::InvalidBranchingFactor(1))
+        );
+        assert_eq!(
+            KaryMerkleTree::<String>::new(vec![], 4).err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+}
@@ -0,0 +1,215 @@
+// Lazy trees: only the leaf hashes and the root are kept; any other node is recomputed when a proof needs it.
+// That halves the memory of a `MerkleTree` at the cost of O(n) work per proof.
+
+use crate::streaming::fold_leaf_hashes;
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleProof, PaddingStrategy, ProofDirection, ProofNode,
+    Sha256Hasher, hash_pair_with, lone_parent, padding_sibling, to_hex,
+};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A Merkle Tree that stores its leaf hashes and root but none of the layers in between.
+/// Roots and proofs are identical to a `MerkleTree` built from the same data and padding,
+/// but `get_proof` rebuilds each sibling from the leaves: O(n) time and O(log n) extra memory per proof.
+#[derive(Debug)]
+pub struct LazyMerkleTree<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    leaves: Vec<[u8; 32]>,
+    root: [u8; 32],
+    padding: PaddingStrategy,
+    // Marker to link the tree to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+impl<T: Hashable> LazyMerkleTree<T> {
+    /// Creates a lazy SHA-256 tree. Returns an Error if the data is empty.
+    pub fn new(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_hasher(data)
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> LazyMerkleTree<T, H> {
+    /// Same as `new`, combining hashes with `H`.
+    pub fn with_hasher(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_padding(data, PaddingStrategy::default())
+    }
+
+    /// Same as `with_hasher`, padding odd layers with `padding` like `MerkleTree::with_padding`.
+    pub fn with_padding(data: Vec<T>, padding: PaddingStrategy) -> Result<Self, MerkleError> {
+        let leaves: Vec<[u8; 32]> = data.iter().map(|item| item.hash_with::<H>()).collect();
+        let root = fold_leaf_hashes::<H>(leaves.iter().copied(), padding)?.root;
+
+        Ok(LazyMerkleTree {
+            leaves,
+            root,
+            padding,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The raw 32-byte Merkle Root.
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        &self.root
+    }
+
+    /// Returns the Merkle Root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(&self.root)
+    }
+
+    /// The leaf hashes in order.
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.leaves
+    }
+
+    /// How many leaves the tree was built from.
+    pub fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// How many levels sit above the leaves: ceil(log2(n)), same as `MerkleTree::depth`.
+    pub fn depth(&self) -> usize {
+        self.leaves.len().next_power_of_two().trailing_zeros() as usize
+    }
+
+    /// How odd layers were padded.
+    pub fn padding(&self) -> PaddingStrategy {
+        self.padding
+    }
+
+    /// Same as `MerkleTree::get_proof`, recomputing every sibling from the leaves it covers (O(n)).
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        let leaf_count = self.leaves.len();
+        if leaf_index >= leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: leaf_count,
+            });
+        }
+
+        let mut siblings = Vec::new();
+        let mut index = leaf_index;
+        // Our own node on the current level, needed when the padding pairs it with itself
+        let mut current = self.leaves[leaf_index];
+
+        for level in 0..self.depth() {
+            // Nodes on this level: every node covers 2^level leaves, the last one possibly fewer
+            let layer_len = leaf_count.div_ceil(1 << level);
+            if index.is_multiple_of(2) {
+                let sibling = if index + 1 < layer_len {
+                    Some(self.node(level, index + 1))
+                } else {
+                    padding_sibling(&current, self.padding)
+                };
+                current = match sibling {
+                    Some(hash) => {
+                        siblings.push(ProofNode {
+                            direction: ProofDirection::Right,
+                            hash,
+                        });
+                        hash_pair_with::<H>(&current, &hash)
+                    }
+                    // Carried up with no step at all
+                    None => lone_parent::<H>(&current, self.padding),
+                };
+            } else {
+                let hash = self.node(level, index - 1);
+                siblings.push(ProofNode {
+                    direction: ProofDirection::Left,
+                    hash,
+                });
+                current = hash_pair_with::<H>(&hash, &current);
+            }
+
+            index /= 2;
+        }
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count,
+            leaf_hash: self.leaves[leaf_index],
+            siblings,
+            root: self.root,
+        })
+    }
+
+    // The node at position `index` of `level`, folded from the leaves below it.
+    // A node at the ragged right edge covers fewer leaves, so it is padded up to its level like in the layered tree.
+    fn node(&self, level: usize, index: usize) -> [u8; 32] {
+        let start = index << level;
+        let end = self.leaves.len().min(start + (1 << level));
+        // Callers only ask for nodes that exist, so the range is never empty
+        let folded = fold_leaf_hashes::<H>(self.leaves[start..end].iter().copied(), self.padding)
+            .expect("node covers at least one leaf");
+
+        let mut hash = folded.root;
+        for _ in folded.level..level {
+            hash = lone_parent::<H>(&hash, self.padding);
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainSeparated, MerkleTree};
+    use alloc::format;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_proofs_match_eager_tree() {
+        let paddings = [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+            PaddingStrategy::Custom([9u8; 32]),
+        ];
+
+        for padding in paddings {
+            for size in 1..=19 {
+                let data: Vec<String> = (0..size).map(|i| format!("row-{}", i)).collect();
+                let eager =
+                    MerkleTree::<String, DomainSeparated>::with_padding(data.clone(), padding)
+                        .unwrap();
+                let lazy =
+                    LazyMerkleTree::<String, DomainSeparated>::with_padding(data, padding).unwrap();
+
+                assert_eq!(lazy.root_bytes(), eager.root_bytes());
+                assert_eq!(lazy.depth(), eager.depth());
+                for i in 0..size {
+                    let proof = lazy.get_proof(i).unwrap();
+                    assert_eq!(
+                        proof,
+                        eager.get_proof(i).unwrap(),
+                        "{:?} {}/{}",
+                        padding,
+                        i,
+                        size
+                    );
+                    assert!(proof.verify_padded_with::<DomainSeparated>(
+                        &proof.leaf_hash,
+                        eager.root_bytes(),
+                        padding
+                    ));
+                }
+                assert!(lazy.get_proof(size).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_lazy_tree() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let lazy = LazyMerkleTree::new(data.clone()).unwrap();
+        let eager = MerkleTree::new(data).unwrap();
+        assert_eq!(lazy.root_hex(), eager.root_hex());
+        assert_eq!(lazy.leaves(), eager.leaves());
+        assert!(lazy.get_proof(4).unwrap().verify());
+        assert_eq!(
+            LazyMerkleTree::<String>::new(vec![]).err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+}
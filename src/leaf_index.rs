@@ -0,0 +1,67 @@
+// The lookup table behind `MerkleTree::contains` and `index_of`: leaf hash to every position holding it.
+// It is built from the leaf layer on the first lookup and dropped by any change to the leaves, so trees that are
+// never searched don't pay for it in construction, clones or memory.
+//
+// With `std` it is a HashMap behind a OnceLock, which keeps `MerkleTree` Sync. Without it there is no thread-safe
+// once-cell to reach for, so it falls back to a BTreeMap behind a OnceCell (O(log n) lookups, and the tree is !Sync).
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell as Cell;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(feature = "std")]
+use std::sync::OnceLock as Cell;
+
+pub(crate) struct LeafIndex(Cell<Table>);
+
+struct Table {
+    // Where each hash first appears; most leaves are unique, so this is the only entry they get.
+    first: Map<[u8; 32], usize>,
+    // The remaining positions of hashes that appear more than once, smallest first.
+    more: BTreeMap<[u8; 32], Vec<usize>>,
+}
+
+impl LeafIndex {
+    pub(crate) const fn new() -> Self {
+        LeafIndex(Cell::new())
+    }
+
+    // Forgets the table; the next lookup builds it again from the current leaves.
+    pub(crate) fn clear(&mut self) {
+        self.0.take();
+    }
+
+    // Every position in `leaves` holding `hash`, smallest first.
+    // `leaves` must be the layer the table was built from, i.e. `clear` has to follow every change to it.
+    pub(crate) fn positions<'a>(
+        &'a self,
+        leaves: &[[u8; 32]],
+        hash: &[u8; 32],
+    ) -> impl Iterator<Item = usize> + 'a {
+        let table = self.0.get_or_init(|| Table::build(leaves));
+        let first = table.first.get(hash).copied();
+        let more = table.more.get(hash).into_iter().flatten().copied();
+        first.into_iter().chain(more)
+    }
+}
+
+impl Table {
+    fn build(leaves: &[[u8; 32]]) -> Self {
+        let mut table = Table {
+            first: Map::new(),
+            more: BTreeMap::new(),
+        };
+        for (position, leaf) in leaves.iter().enumerate() {
+            let first = *table.first.entry(*leaf).or_insert(position);
+            if first != position {
+                table.more.entry(*leaf).or_default().push(position);
+            }
+        }
+        table
+    }
+}
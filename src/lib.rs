@@ -0,0 +1,3447 @@
+//! A generic Merkle Tree with a pluggable hash algorithm (SHA-256 by default).
+//! The demo binary in `main.rs` uses this library.
+//!
+//! The crate is `no_std` and only needs an allocator. The default `std` feature
+//! turns on the `std` features of the hash crates; embedded and WebAssembly
+//! builds use `default-features = false, features = ["alloc"]`.
+
+#![cfg_attr(not(test), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("merkle_tree needs the `alloc` feature (or `std`, which enables it)");
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use crate::arena::Layers;
+use crate::hasher::hasher_name;
+use crate::leaf_index::LeafIndex;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::marker::PhantomData;
+
+pub mod accumulator;
+mod arena;
+#[cfg(feature = "bitcoin")]
+pub mod bitcoin;
+pub mod builder;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive_support;
+mod dot;
+pub mod error;
+pub mod forest;
+pub mod hasher;
+pub mod kary;
+pub mod lazy;
+mod leaf_index;
+pub mod mmr;
+pub mod multiproof;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub mod persistent;
+pub mod render;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod set;
+pub mod sparse;
+#[cfg(feature = "keccak")]
+pub mod standard;
+pub mod streaming;
+pub mod sum;
+mod wire;
+
+pub use accumulator::{
+    Checkpoint, ConsistencyProof, MerkleAccumulator, verify_consistency, verify_consistency_with,
+};
+#[cfg(feature = "bitcoin")]
+pub use bitcoin::bitcoin_block_root;
+pub use builder::MerkleTreeBuilder;
+pub use error::MerkleError;
+pub use forest::MerkleForest;
+#[cfg(feature = "blake3")]
+pub use hasher::Blake3Hasher;
+pub use hasher::{
+    DigestHasher, DomainSeparated, DoubleSha256Hasher, LegacyHexConcat, MerkleHasher, Sha256Hasher,
+    SortedPairs,
+};
+#[cfg(feature = "keccak")]
+pub use hasher::{Keccak256Hasher, OpenZeppelinHasher};
+pub use kary::{KaryMerkleTree, KaryProof};
+pub use lazy::LazyMerkleTree;
+#[cfg(feature = "derive")]
+pub use merkle_tree_derive::Hashable;
+pub use mmr::{MmrAccumulator, MmrProof};
+pub use multiproof::{MultiProof, verify_multiproof, verify_multiproof_with};
+pub use persistent::PersistentMerkleTree;
+pub use render::RenderOptions;
+pub use set::{MembershipProof, MerkleSet};
+pub use sparse::{SparseMerkleTree, SparseProof};
+#[cfg(feature = "keccak")]
+pub use standard::StandardMerkleTree;
+pub use streaming::{compute_root, compute_root_with};
+pub use sum::{SumMerkleTree, SumProof, SumProofNode};
+
+// --- TRAIT DEFINITION ---
+
+/// A contract for types that can be turned into a 32-byte cryptographic fingerprint.
+pub trait Hashable {
+    fn hash(&self) -> [u8; 32];
+
+    /// The leaf value under the tree's hash algorithm `H`. The tree always calls this one.
+    /// Override it to feed your raw bytes to `H::hash_leaf`; the default wraps the result of `hash`.
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::wrap_leaf(&self.hash())
+    }
+}
+
+// Implement the contract for String so we can use our existing data.
+impl Hashable for String {
+    fn hash(&self) -> [u8; 32] {
+        self.hash_with::<Sha256Hasher>()
+    }
+
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::hash_leaf(self.as_bytes())
+    }
+}
+
+// String literals hash exactly like the equivalent String, so `MerkleTree::try_from_iter(["a", "b"])` needs no allocations.
+impl Hashable for &str {
+    fn hash(&self) -> [u8; 32] {
+        self.hash_with::<Sha256Hasher>()
+    }
+
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::hash_leaf(self.as_bytes())
+    }
+}
+
+// Raw bytes are hashed as they are, so a tree over `Vec<u8>` leaves needs no newtype.
+// The same bytes as a String, Vec<u8>, &[u8] or [u8; N] all give the same leaf hash.
+impl Hashable for Vec<u8> {
+    fn hash(&self) -> [u8; 32] {
+        self.hash_with::<Sha256Hasher>()
+    }
+
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::hash_leaf(self)
+    }
+}
+
+impl Hashable for &[u8] {
+    fn hash(&self) -> [u8; 32] {
+        self.hash_with::<Sha256Hasher>()
+    }
+
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::hash_leaf(self)
+    }
+}
+
+impl<const N: usize> Hashable for [u8; N] {
+    fn hash(&self) -> [u8; 32] {
+        self.hash_with::<Sha256Hasher>()
+    }
+
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::hash_leaf(self)
+    }
+}
+
+// Integers are hashed as their big-endian (network byte order) bytes, so other languages can reproduce
+// the leaves: 256u64 is SHA-256 over [0, 0, 0, 0, 0, 0, 1, 0]. Negative i64s use two's complement.
+macro_rules! impl_hashable_big_endian {
+    ($($int:ty),*) => {
+        $(
+            impl Hashable for $int {
+                fn hash(&self) -> [u8; 32] {
+                    self.hash_with::<Sha256Hasher>()
+                }
+
+                fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+                    H::hash_leaf(&self.to_be_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_hashable_big_endian!(u32, u64, u128, i64);
+
+// The unit type stands in for "no data type" in trees built straight from hashes (see `MerkleTree::from_hashes`).
+// It hashes to the hash of empty input.
+impl Hashable for () {
+    fn hash(&self) -> [u8; 32] {
+        self.hash_with::<Sha256Hasher>()
+    }
+
+    fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+        H::hash_leaf(&[])
+    }
+}
+
+// --- CORE DATA STRUCTURE ---
+
+/// Which side of the concatenation a sibling hash sits on when climbing the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProofDirection {
+    Left,
+    Right,
+}
+
+/// How a layer with an odd number of hashes is narrowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TreeStyle {
+    /// The lone last node is hashed with itself (Bitcoin style). This is the default.
+    #[default]
+    DuplicateOdd,
+    /// The lone last node is carried up unchanged, which gives exactly the RFC 6962 Merkle Tree Hash
+    /// (splitting at the largest power of two below n). Pair it with `DomainSeparated` hashing, as `new_ct` does.
+    Rfc6962,
+}
+
+/// What a layer with an odd number of hashes pairs its lone last node with.
+/// The tree remembers its strategy (see `MerkleTree::padding`) so a verifier can be set up the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PaddingStrategy {
+    /// Hash the node with a copy of itself. This is the default, same as `TreeStyle::DuplicateOdd`.
+    #[default]
+    DuplicateSelf,
+    /// Carry the node up unchanged, same as `TreeStyle::Rfc6962`.
+    PromoteOdd,
+    /// Hash the node with 32 zero bytes.
+    FixedZeroHash,
+    /// Hash the node with a caller-chosen sibling hash.
+    Custom([u8; 32]),
+}
+
+/// The three common ways to handle the odd leaf out, for `MerkleTree::new_with_options`.
+/// `PaddingStrategy` covers the same ground plus a custom sibling; this converts into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OddLeafStrategy {
+    /// `hash_pair(node, node)`, what `MerkleTree::new` does. This is the default.
+    #[default]
+    Duplicate,
+    /// The node moves up a level unchanged.
+    Promote,
+    /// `hash_pair(node, [0; 32])`.
+    ZeroPad,
+}
+
+impl From<OddLeafStrategy> for PaddingStrategy {
+    fn from(strategy: OddLeafStrategy) -> Self {
+        match strategy {
+            OddLeafStrategy::Duplicate => PaddingStrategy::DuplicateSelf,
+            OddLeafStrategy::Promote => PaddingStrategy::PromoteOdd,
+            OddLeafStrategy::ZeroPad => PaddingStrategy::FixedZeroHash,
+        }
+    }
+}
+
+impl From<TreeStyle> for PaddingStrategy {
+    fn from(style: TreeStyle) -> Self {
+        match style {
+            TreeStyle::DuplicateOdd => PaddingStrategy::DuplicateSelf,
+            TreeStyle::Rfc6962 => PaddingStrategy::PromoteOdd,
+        }
+    }
+}
+
+// The 'filing cabinet' that stores our tree levels, all of them in one flat arena (see `arena.rs`).
+// layers[0] = the bottom (leaves)
+// layers[last] = the top (root)
+// `T` decides how each leaf is fingerprinted, `H` decides how fingerprints are combined on the way up.
+pub struct MerkleTree<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    // Raw 32-byte digests; hex is only produced when someone asks for it.
+    // Private so nobody can knock a layer out of sync with the one below it; read it through `layers()`.
+    layers: Layers,
+    // What happens to the odd node out on each layer.
+    padding: PaddingStrategy,
+    // Leaf hash to positions for `index_of` and `contains`, built on the first lookup and cleared by every change.
+    leaf_index: LeafIndex,
+    // Marker to link the tree to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+// Written by hand because #[derive(Clone)] would also demand T: Clone and H: Clone,
+// even though only the hashes are stored.
+impl<T: Hashable, H: MerkleHasher> Clone for MerkleTree<T, H> {
+    fn clone(&self) -> Self {
+        MerkleTree {
+            layers: self.layers.clone(),
+            padding: self.padding,
+            // The clone builds its own lookup table if it is ever searched
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Two trees are equal iff their Merkle Roots are equal.
+/// In theory two differently shaped trees could share a root, but finding such a pair means breaking the hash function.
+impl<T: Hashable, H: MerkleHasher> PartialEq for MerkleTree<T, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.root_bytes() == other.root_bytes()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Eq for MerkleTree<T, H> {}
+
+/// Hashes the root only, in line with `PartialEq`, so trees can be `HashMap` keys.
+/// Clippy's `mutable_key_type` flags such maps because of the lazy leaf index, but neither this nor `Ord` reads it.
+impl<T: Hashable, H: MerkleHasher> core::hash::Hash for MerkleTree<T, H> {
+    fn hash<S: core::hash::Hasher>(&self, state: &mut S) {
+        core::hash::Hash::hash(self.root_bytes(), state);
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> PartialOrd for MerkleTree<T, H> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders trees by their root bytes, so `BTreeMap` keys come out sorted like the hex roots would.
+impl<T: Hashable, H: MerkleHasher> Ord for MerkleTree<T, H> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.root_bytes().cmp(other.root_bytes())
+    }
+}
+
+/// A one-line summary: leaf count, depth, node count, hasher and the root cut down to its first and last 8 hex digits.
+/// `{:#}` adds one line per layer for trees of at most 16 leaves, root first, every hash cut down to its
+/// first 8 hex characters and centered over the leaves it covers, e.g. for four leaves:
+///
+/// ```text
+/// MerkleTree: 4 leaves, depth 2, 7 nodes, SHA-256, root 1b3faa3f…f9b13617
+/// Level 2:                1b3faa3f…
+/// Level 1:      63956f0c…           98a2fbfd…
+/// Level 0: 559aead0… df7e70e5… 6b23c0d5… 3f39d5c3…
+/// ```
+impl<T: Hashable, H: MerkleHasher> fmt::Display for MerkleTree<T, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Bigger trees would print hundreds of columns per layer
+        const MAX_DRAWN_LEAVES: usize = 16;
+
+        let root = self.root_hex();
+        write!(
+            f,
+            "MerkleTree: {} leaves, depth {}, {} nodes, {}, root {}…{}",
+            self.leaf_count(),
+            self.depth(),
+            self.node_count(),
+            hasher_name::<H>(),
+            &root[..8],
+            &root[56..]
+        )?;
+        if f.alternate() && self.leaf_count() <= MAX_DRAWN_LEAVES {
+            writeln!(f)?;
+            self.draw_layers(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The same summary as `Display`, as a struct with the full root, instead of every hash of every layer.
+impl<T: Hashable, H: MerkleHasher> fmt::Debug for MerkleTree<T, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MerkleTree")
+            .field("leaf_count", &self.leaf_count())
+            .field("depth", &self.depth())
+            .field("node_count", &self.node_count())
+            .field("hasher", &hasher_name::<H>())
+            .field("padding", &self.padding)
+            .field("root", &self.root_hex())
+            .finish()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    // The per-layer drawing of `{:#}`, root first.
+    fn draw_layers(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Every leaf gets a slot wide enough for "xxxxxxxx…" plus a space
+        const LABEL_WIDTH: usize = 9;
+        const SLOT_WIDTH: usize = LABEL_WIDTH + 1;
+
+        let leaf_count = self.layers[0].len();
+        let level_width = self.depth().to_string().len();
+
+        for (level, layer) in self.layers.iter().enumerate().rev() {
+            let mut line = format!("Level {:>width$}: ", level, width = level_width);
+            // Track the column by hand: '…' is one column but three bytes
+            let indent = line.len();
+            let mut column = indent;
+
+            for (index, hash) in layer.iter().enumerate() {
+                // The node at this level covers 2^level leaves (fewer at the ragged right edge)
+                let first_leaf = index << level;
+                let covered = (leaf_count - first_leaf).min(1 << level);
+                let start =
+                    indent + first_leaf * SLOT_WIDTH + (covered * SLOT_WIDTH - 1 - LABEL_WIDTH) / 2;
+
+                line.push_str(&" ".repeat(start - column));
+                line.push_str(&to_hex(&hash[..4]));
+                line.push('…');
+                column = start + LABEL_WIDTH;
+            }
+
+            if level > 0 {
+                writeln!(f, "{}", line)?;
+            } else {
+                write!(f, "{}", line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `iter.collect::<MerkleTree<_>>()`. An empty iterator gives an empty tree (as from `with_capacity(0)`, with the
+/// all-zero sentinel root described on `root_bytes`),
+/// because `from_iter` can't return an Error; use `MerkleTree::try_from_iter` to get `MerkleError::EmptyInput` instead.
+impl<T: Hashable, H: MerkleHasher> FromIterator<T> for MerkleTree<T, H> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        // from_leaf_layer only fails on empty input, which becomes an empty tree here
+        Self::from_leaf_layer(hash_leaves::<T, H, _>(iter), PaddingStrategy::default())
+            .unwrap_or_else(|_| Self::with_capacity(0))
+    }
+}
+
+/// Rebuilds a tree from hex layers (leaves first, root last), e.g. from a database cache.
+/// Same as `MerkleTree::from_layers`: every parent is re-checked, and a bad layer set returns `MerkleError::InvalidLayers`.
+impl<T: Hashable, H: MerkleHasher> TryFrom<Vec<Vec<String>>> for MerkleTree<T, H> {
+    type Error = MerkleError;
+
+    fn try_from(layers: Vec<Vec<String>>) -> Result<Self, Self::Error> {
+        Self::from_layers(layers)
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Extend<T> for MerkleTree<T, H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        // The only error is an empty batch, which is just a no-op here
+        let _ = MerkleTree::extend(self, iter);
+    }
+}
+
+/// `for hash in &tree` walks the leaf hashes in insertion order, same as `tree.iter()`.
+impl<'a, T: Hashable, H: MerkleHasher> IntoIterator for &'a MerkleTree<T, H> {
+    type Item = &'a [u8; 32];
+    type IntoIter = core::slice::Iter<'a, [u8; 32]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// One layer of a tree as `MerkleTree::iter_layers` yields it. A one-leaf tree's only layer is both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerView<'a> {
+    /// 0 for the leaves, `depth()` for the root.
+    pub level: usize,
+    /// The hashes on this level, left to right.
+    pub nodes: &'a [[u8; 32]],
+    pub is_leaf_layer: bool,
+    pub is_root_layer: bool,
+}
+
+/// An inclusion proof for a single leaf: everything a verifier needs to climb back up to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MerkleProof {
+    /// Position of the leaf in the bottom layer.
+    pub leaf_index: usize,
+    /// Number of leaves in the whole tree, so a verifier can tell where the padded nodes are.
+    pub leaf_count: usize,
+    /// The fingerprint of the leaf being proven.
+    pub leaf_hash: [u8; 32],
+    /// Sibling nodes ordered from the leaf layer up to the layer just below the root.
+    pub siblings: Vec<ProofNode>,
+    /// The root of the tree the proof was taken from.
+    pub root: [u8; 32],
+}
+
+/// One step of a `MerkleProof`: a sibling hash and the side it goes on in `hash_pair`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofNode {
+    pub direction: ProofDirection,
+    pub hash: [u8; 32],
+}
+
+// --- HELPERS ---
+
+// Low-level helper: Turns any bytes into a 32-byte unique fingerprint using the hasher H.
+pub fn hash_data_with<H: MerkleHasher>(input: &[u8]) -> [u8; 32] {
+    H::hash(input)
+}
+
+// Mid-level helper: Takes two fingerprints and combines them into their parent using H.
+// By default their raw bytes are glued together and hashed; the 64 bytes live on the stack, so no allocation happens here.
+// This is how we "climb" the tree levels.
+pub fn hash_pair_with<H: MerkleHasher>(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    H::hash_nodes(left, right)
+}
+
+/// Like `hash_pair_with`, but puts a one-byte `domain_prefix` in front of the two children: `H(prefix || left || right)`.
+pub fn hash_pair_with_prefix<H: MerkleHasher>(
+    domain_prefix: u8,
+    left: &[u8; 32],
+    right: &[u8; 32],
+) -> [u8; 32] {
+    H::hash_parts(&[&[domain_prefix], left, right])
+}
+
+/// SHA-256 shorthand for `hash_data_with`.
+pub fn hash_data(input: &[u8]) -> [u8; 32] {
+    hash_data_with::<Sha256Hasher>(input)
+}
+
+/// SHA-256 shorthand for `hash_pair_with`.
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hash_pair_with::<Sha256Hasher>(left, right)
+}
+
+/// SHA-256 of the two hashes in byte-wise order, so `hash_pair_sorted(a, b) == hash_pair_sorted(b, a)`.
+/// This is the pairing of `SortedPairs` trees such as `MerkleTree::new_commutative`.
+pub fn hash_pair_sorted(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    hash_pair_with::<SortedPairs>(a, b)
+}
+
+// Hashes items into the leaf layer. Takes owned or borrowed items, so every constructor can share it.
+fn hash_leaves<T: Hashable, H: MerkleHasher, B: Borrow<T>>(
+    items: impl IntoIterator<Item = B>,
+) -> Vec<[u8; 32]> {
+    items
+        .into_iter()
+        // Use the trait method here!
+        .map(|item| item.borrow().hash_with::<H>())
+        .collect()
+}
+
+// Turns a (non-empty) leaf layer into the full stack of layers.
+fn build_layers<H: MerkleHasher>(first_layer: &[[u8; 32]], padding: PaddingStrategy) -> Layers {
+    // Every layer's size is known up front, so the whole tree is one allocation
+    let mut layers = Layers::for_leaves(first_layer.len());
+    layers[0].copy_from_slice(first_layer);
+
+    // its called a tree but we are building it the reverse way so i found it makes more sense as a "Funnel":
+    // Keep creating new layers until the last layer has only 1 hash (the Root), so you start wide and go narrow
+    for level in 0..layers.len() - 1 {
+        // Hash the layer below straight into the slots reserved for the one above it
+        let (below, above) = layers.split_level_mut(level);
+        fill_parent_layer::<H>(below, padding, above);
+    }
+
+    layers
+}
+
+// Takes one layer and produces the layer above it by hashing the hashes in pairs.
+fn build_parent_layer<H: MerkleHasher>(
+    layer: &[[u8; 32]],
+    padding: PaddingStrategy,
+) -> Vec<[u8; 32]> {
+    layer
+        .chunks(2)
+        .map(|chunk| chunk_parent::<H>(chunk, padding))
+        .collect()
+}
+
+// Checks that every layer is exactly the parent layer of the one below it (which also fixes its length
+// at ceil(len / 2)) and that the last layer is a single root.
+fn check_layers<H: MerkleHasher>(
+    layers: &Layers,
+    padding: PaddingStrategy,
+) -> Result<(), MerkleError> {
+    for level in 0..layers.len().saturating_sub(1) {
+        if build_parent_layer::<H>(&layers[level], padding) != layers[level + 1] {
+            return Err(MerkleError::InvalidLayers(format!(
+                "layer {} does not match the hashes of layer {}",
+                level + 1,
+                level
+            )));
+        }
+    }
+
+    // The funnel has to end in exactly one hash
+    if layers.last().is_none_or(|top| top.len() != 1) {
+        return Err(MerkleError::InvalidLayers(
+            "top layer must contain exactly one root hash".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Same as build_parent_layer, but writes into slots that already exist (exactly ceil(len / 2) of them).
+fn fill_parent_layer<H: MerkleHasher>(
+    layer: &[[u8; 32]],
+    padding: PaddingStrategy,
+    next_layer: &mut [[u8; 32]],
+) {
+    for (slot, chunk) in next_layer.iter_mut().zip(layer.chunks(2)) {
+        *slot = chunk_parent::<H>(chunk, padding);
+    }
+}
+
+// The parent of one `chunks(2)` chunk of a layer. Every layer builder goes through here (the parallel one too),
+// so they all pair and pad the same way.
+pub(crate) fn chunk_parent<H: MerkleHasher>(
+    chunk: &[[u8; 32]],
+    padding: PaddingStrategy,
+) -> [u8; 32] {
+    match chunk {
+        // We have two hashes == ? -> Hash them together
+        [left, right] => hash_pair_with::<H>(left, right),
+        // Only one hash left ? -> the padding strategy decides what it becomes
+        [left] => lone_parent::<H>(left, padding),
+        _ => unreachable!(),
+    }
+}
+
+// The sibling a lone last node is paired with, or None if it gets carried up without hashing.
+fn padding_sibling(lone: &[u8; 32], padding: PaddingStrategy) -> Option<[u8; 32]> {
+    match padding {
+        // we hash it with itself as the last layer (every layer must be hashed)
+        PaddingStrategy::DuplicateSelf => Some(*lone),
+        PaddingStrategy::PromoteOdd => None,
+        PaddingStrategy::FixedZeroHash => Some([0u8; 32]),
+        PaddingStrategy::Custom(sibling) => Some(sibling),
+    }
+}
+
+// The parent of a lone last node.
+pub(crate) fn lone_parent<H: MerkleHasher>(lone: &[u8; 32], padding: PaddingStrategy) -> [u8; 32] {
+    match padding_sibling(lone, padding) {
+        Some(sibling) => hash_pair_with::<H>(lone, &sibling),
+        None => *lone,
+    }
+}
+
+/// The (level, index) of every node in the proof of leaf `leaf_index`, for a tree of `leaf_count` leaves padded
+/// with `padding`, leaf level first. Only the shape is used, so this works for nodes kept outside a `MerkleTree`,
+/// e.g. in a database: fetch these nodes and pair each hash with its direction to get the `ProofNode`s of `get_proof`.
+///
+/// A real right sibling always has an odd index. A `Right` step at an even index is the odd node out on that level
+/// pointing at itself: with `DuplicateSelf` that node is the sibling; with `FixedZeroHash` or `Custom` the sibling
+/// is the zero or custom hash and isn't stored anywhere. `PromoteOdd` carries the node up with no step.
+pub fn proof_node_coordinates(
+    leaf_index: usize,
+    leaf_count: usize,
+    padding: PaddingStrategy,
+) -> Result<Vec<(usize, usize, ProofDirection)>, MerkleError> {
+    if leaf_index >= leaf_count {
+        return Err(MerkleError::IndexOutOfBounds {
+            index: leaf_index,
+            len: leaf_count,
+        });
+    }
+
+    let mut coordinates = Vec::new();
+    let mut index = leaf_index;
+    let mut layer_len = leaf_count;
+    let mut level = 0;
+    // Climb until the layer is the root, like `get_proof`, but knowing only how long each layer is
+    while layer_len > 1 {
+        if !index.is_multiple_of(2) {
+            coordinates.push((level, index - 1, ProofDirection::Left));
+        } else if index + 1 < layer_len {
+            coordinates.push((level, index + 1, ProofDirection::Right));
+        } else if padding != PaddingStrategy::PromoteOdd {
+            coordinates.push((level, index, ProofDirection::Right));
+        }
+
+        index /= 2;
+        layer_len = layer_len.div_ceil(2);
+        level += 1;
+    }
+
+    Ok(coordinates)
+}
+
+/// Transforms raw bytes into a human-readable Hexadecimal string.
+/// Each byte becomes two lowercase hex characters (e.g., 255 becomes "ff"),
+/// which is the common format you see in Bitcoin or Ethereum transaction IDs.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The reverse of `to_hex`: turns a 64-character hex string back into a 32-byte digest.
+pub fn from_hex(hex: &str) -> Result<[u8; 32], MerkleError> {
+    if hex.len() != 64 {
+        return Err(MerkleError::HashDecodingError(format!(
+            "expected 64 hex characters, got {}",
+            hex.len()
+        )));
+    }
+
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        // Every two characters make up one byte
+        let pair = hex.get(i * 2..i * 2 + 2).ok_or_else(|| {
+            MerkleError::HashDecodingError(format!("invalid hex string: {}", hex))
+        })?;
+        // from_str_radix alone would also take a sign, e.g. "+f"
+        if !pair.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(MerkleError::HashDecodingError(format!(
+                "invalid hex pair: {}",
+                pair
+            )));
+        }
+        *byte = u8::from_str_radix(pair, 16)
+            .map_err(|_| MerkleError::HashDecodingError(format!("invalid hex pair: {}", pair)))?;
+    }
+
+    Ok(bytes)
+}
+
+// --- IMPLEMENTATION ---
+
+impl<T: Hashable> MerkleTree<T> {
+    /// Creates a new SHA-256 Merkle Tree. Returns an Error if the data is empty.
+    pub fn new(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_hasher(data)
+    }
+
+    /// Same as `new`, but hashes borrowed items, so the data doesn't have to be moved or cloned.
+    pub fn from_slice(data: &[T]) -> Result<Self, MerkleError> {
+        Self::from_leaf_layer(
+            hash_leaves::<T, Sha256Hasher, _>(data),
+            PaddingStrategy::default(),
+        )
+    }
+
+    /// Same as `new`, for any iterator of items. Returns an Error if the iterator is empty.
+    /// `collect()` works too (see the `FromIterator` impl), but an empty iterator gives an empty tree there.
+    /// This is not called `from_iter`: an inherent method of that name would shadow `FromIterator::from_iter`.
+    pub fn try_from_iter<I: IntoIterator<Item = T>>(iter: I) -> Result<Self, MerkleError> {
+        Self::from_leaf_layer(
+            hash_leaves::<T, Sha256Hasher, _>(iter),
+            PaddingStrategy::default(),
+        )
+    }
+
+    /// Creates a SHA-256 tree over the leaf hashes sorted byte-wise, so the root only depends on which items
+    /// there are, not on the order they came in. See `with_sorted_leaves`.
+    /// (`new_sorted` is the Solidity-compatible constructor behind the `keccak` feature.)
+    pub fn new_sorted_leaves(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_sorted_leaves(data)
+    }
+
+    /// Same as `new`, but the odd node out of each layer is handled by `strategy`.
+    /// Verify its proofs with `verify_padded(.., strategy.into())` to pin the strategy down too.
+    pub fn new_with_options(data: Vec<T>, strategy: OddLeafStrategy) -> Result<Self, MerkleError> {
+        Self::with_padding(data, strategy.into())
+    }
+}
+
+impl MerkleTree<()> {
+    /// Creates a SHA-256 tree from hashes that were computed elsewhere, skipping the leaf-hashing step.
+    /// Returns an Error if there are no hashes.
+    pub fn from_hashes(hashes: Vec<[u8; 32]>) -> Result<Self, MerkleError> {
+        Self::from_hashes_with_hasher(hashes)
+    }
+
+    /// Same as `from_hashes`, but takes 64-character hex strings and rejects anything else.
+    /// A bad entry is reported with its position, e.g. "leaf 3: expected 64 hex characters, got 4".
+    pub fn from_hex_hashes(hashes: Vec<String>) -> Result<Self, MerkleError> {
+        let mut decoded = Vec::with_capacity(hashes.len());
+        for (position, hex) in hashes.iter().enumerate() {
+            decoded.push(from_hex(hex).map_err(|err| match err {
+                MerkleError::HashDecodingError(reason) => {
+                    MerkleError::HashDecodingError(format!("leaf {}: {}", position, reason))
+                }
+                other => other,
+            })?);
+        }
+        Self::from_hashes(decoded)
+    }
+}
+
+impl<T: Hashable> MerkleTree<T, DomainSeparated> {
+    /// Creates an RFC 6962 (Certificate Transparency) tree: SHA-256 with domain separation
+    /// (leaves are `H(0x00 || leaf_bytes)`, internal nodes are `H(0x01 || left || right)`)
+    /// and `TreeStyle::Rfc6962`, so the root is exactly the RFC's Merkle Tree Hash.
+    /// The bottom layer holds the prefixed leaf hashes, so proofs must start from `layers[0]`, not `T::hash`.
+    pub fn new_ct(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_style(data, TreeStyle::Rfc6962)
+    }
+}
+
+#[cfg(feature = "keccak")]
+impl<T: Hashable> MerkleTree<T, OpenZeppelinHasher> {
+    /// Creates an Ethereum-style tree for Solidity's `MerkleProof.verify`: Keccak-256 throughout,
+    /// leaves hashed twice, and each pair sorted before hashing, so `hash_pair(a, b) == hash_pair(b, a)`.
+    /// The odd node out is carried up unchanged, like merkletreejs with `sortPairs: true`.
+    /// The hashes of `get_proof(i).siblings` are the `bytes32[]` proof to send on chain.
+    pub fn new_sorted(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_padding(data, PaddingStrategy::PromoteOdd)
+    }
+}
+
+impl<T: Hashable> MerkleTree<T, SortedPairs> {
+    /// Creates a SHA-256 tree that pairs nodes with `hash_pair_sorted`, so proofs don't need to say which side
+    /// a sibling is on: pass `get_proof(i).sibling_hashes()` to `verify_sorted_proof`.
+    /// Unlike `new_sorted_leaves`, the leaves keep their input order.
+    pub fn new_commutative(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_hasher(data)
+    }
+}
+
+impl<T: Hashable> MerkleTree<T, DoubleSha256Hasher> {
+    /// Creates a Bitcoin-style tree: leaves and internal nodes are both double SHA-256 over raw bytes,
+    /// and the last node of an odd layer is paired with itself, just like Bitcoin block headers.
+    pub fn new_btc(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_hasher(data)
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Creates a new Merkle Tree that combines hashes with `H`, e.g. `MerkleTree::<String, Blake3Hasher>::with_hasher(data)`.
+    /// Returns an Error if the data is empty.
+    pub fn with_hasher(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_padding(data, PaddingStrategy::default())
+    }
+
+    /// Creates a new Merkle Tree that combines hashes with `H` and narrows odd layers according to `style`.
+    /// Returns an Error if the data is empty.
+    pub fn with_style(data: Vec<T>, style: TreeStyle) -> Result<Self, MerkleError> {
+        Self::with_padding(data, style.into())
+    }
+
+    /// Creates a new Merkle Tree that combines hashes with `H` and pads odd layers according to `padding`.
+    /// Returns an Error if the data is empty.
+    pub fn with_padding(data: Vec<T>, padding: PaddingStrategy) -> Result<Self, MerkleError> {
+        // 1. Create the bottom layer (The Leaves/Wide part of the funnel)
+        let first_layer = hash_leaves::<T, H, _>(data);
+
+        // 2. Funnel the leaves up into the root
+        Self::from_leaf_layer(first_layer, padding)
+    }
+
+    /// Same as `with_hasher`, but the leaf hashes are sorted byte-wise before the tree is built.
+    /// Any permutation of `data` gives the same tree, so the prover doesn't need to know the insertion order.
+    /// Leaf positions follow the sorted order: look them up with `find_leaf_index_of(&item)` before `get_proof`.
+    pub fn with_sorted_leaves(data: Vec<T>) -> Result<Self, MerkleError> {
+        let mut first_layer = hash_leaves::<T, H, _>(data);
+        first_layer.sort_unstable();
+        Self::from_leaf_layer(first_layer, PaddingStrategy::default())
+    }
+
+    // Every constructor ends here: check there is something to build from, then funnel the leaves up.
+    fn from_leaf_layer(
+        first_layer: Vec<[u8; 32]>,
+        padding: PaddingStrategy,
+    ) -> Result<Self, MerkleError> {
+        // Guard Clause: Prevent mathematical errors with empty inputs
+        if first_layer.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        Ok(MerkleTree {
+            layers: build_layers::<H>(&first_layer, padding),
+            padding,
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Creates a tree whose bottom layer is exactly `hashes`, combining them with `H`.
+    /// The hashes are used as is (`H::wrap_leaf` is not applied), so they must already be leaf-layer values.
+    pub fn from_hashes_with_hasher(hashes: Vec<[u8; 32]>) -> Result<Self, MerkleError> {
+        Self::from_leaf_layer(hashes, PaddingStrategy::default())
+    }
+
+    /// Appends `new_data` as extra leaves on the right and refreshes the layers above them.
+    /// All new leaves are hashed first, then only the right-hand part of each layer that changed is recomputed,
+    /// so each affected ancestor is hashed once instead of once per leaf as with repeated `push`.
+    /// Returns an Error if `new_data` is empty.
+    pub fn extend(&mut self, new_data: impl IntoIterator<Item = T>) -> Result<(), MerkleError> {
+        self.extend_counting(new_data).map(|_| ())
+    }
+
+    // `extend`, also returning how many internal nodes were recomputed (the tests compare it with `push`).
+    fn extend_counting(
+        &mut self,
+        new_data: impl IntoIterator<Item = T>,
+    ) -> Result<usize, MerkleError> {
+        let new_leaves = hash_leaves::<T, H, _>(new_data);
+        if new_leaves.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        let first_new = self.layers[0].len();
+        self.layers.resize(first_new + new_leaves.len());
+        self.layers[0][first_new..].copy_from_slice(&new_leaves);
+        self.leaf_index.clear();
+        Ok(self.rebuild_right_edge(first_new))
+    }
+
+    /// Appends one leaf and recomputes only the nodes on the right edge, one per level (O(log n)).
+    /// A new level is added on top when the leaf count goes past a power of two.
+    /// The result is identical to building the tree from scratch with all the leaves.
+    pub fn push(&mut self, item: T) {
+        let index = self.layers[0].len();
+        self.layers.resize(index + 1);
+        self.layers[0][index] = item.hash_with::<H>();
+        self.leaf_index.clear();
+
+        // Only the new leaf is dirty, so this recomputes one node per level: its parent, grandparent and so on
+        self.rebuild_right_edge(index);
+    }
+
+    /// Creates an empty tree with room for `leaf_capacity` leaves, to be filled with `extend` or `push`.
+    /// Like `Vec::with_capacity` this is only a hint: the tree can end up with more or fewer leaves.
+    /// Until the first `extend` or `push` the tree has no leaves and its root is all zeros.
+    pub fn with_capacity(leaf_capacity: usize) -> Self {
+        MerkleTree {
+            layers: Layers::with_leaf_capacity(leaf_capacity),
+            padding: PaddingStrategy::default(),
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    // Recomputes the layers above the leaves from leaf `first_dirty` rightwards, in place.
+    // The layers must already have their new shape (`Layers::resize`), and everything left of the changed region
+    // keeps its hash. Returns how many nodes were recomputed.
+    fn rebuild_right_edge(&mut self, first_dirty: usize) -> usize {
+        let mut recomputed = 0;
+        let mut dirty = first_dirty;
+        for level in 0..self.layers.len() - 1 {
+            // The first stale parent is the one above the pair that holds the first changed node
+            let parent_start = dirty / 2;
+            let (below, above) = self.layers.split_level_mut(level);
+            let stale = &mut above[parent_start..];
+            fill_parent_layer::<H>(&below[parent_start * 2..], self.padding, stale);
+            recomputed += stale.len();
+
+            dirty = parent_start;
+        }
+
+        recomputed
+    }
+
+    /// Rebuilds a tree from previously computed layers of hex hashes (leaves first, root last).
+    /// Every parent is re-checked against its children, so a tampered or truncated layer set is rejected.
+    pub fn from_layers(layers: Vec<Vec<String>>) -> Result<Self, MerkleError> {
+        Self::from_layers_with_padding(layers, PaddingStrategy::default())
+    }
+
+    /// Same as `from_layers`, for layers that were built with a non-default `PaddingStrategy`.
+    pub fn from_layers_with_padding(
+        layers: Vec<Vec<String>>,
+        padding: PaddingStrategy,
+    ) -> Result<Self, MerkleError> {
+        if layers.first().is_none_or(|leaves| leaves.is_empty()) {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        // 1. Decode every hex string back into raw bytes
+        let mut decoded = Vec::with_capacity(layers.len());
+        for layer in &layers {
+            let bytes = layer
+                .iter()
+                .map(|hex| from_hex(hex))
+                .collect::<Result<Vec<_>, _>>()?;
+            decoded.push(bytes);
+        }
+
+        // 2. Make sure each layer really is the hash of the one below it, up to a single root
+        let layers = Layers::from_vecs(decoded);
+        check_layers::<H>(&layers, padding)?;
+
+        Ok(MerkleTree {
+            layers,
+            padding,
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Replaces the leaf at `index` and re-hashes only the path from that leaf up to the root (O(log n)).
+    pub fn update_leaf(&mut self, index: usize, new_data: &T) -> Result<(), MerkleError> {
+        let leaf_count = self.layers[0].len();
+        if index >= leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index,
+                len: leaf_count,
+            });
+        }
+
+        self.layers[0][index] = new_data.hash_with::<H>();
+        self.leaf_index.clear();
+
+        // Walk upward, recalculating only the parent of the node we just changed
+        let mut index = index;
+        for level in 0..self.layers.len() - 1 {
+            let layer = &self.layers[level];
+            let left_index = index - index % 2;
+            let left = layer[left_index];
+            // A missing right node means this one was the odd node out during construction
+            let parent = match layer.get(left_index + 1) {
+                Some(right) => hash_pair_with::<H>(&left, right),
+                None => lone_parent::<H>(&left, self.padding),
+            };
+
+            index /= 2;
+            self.layers[level + 1][index] = parent;
+        }
+
+        Ok(())
+    }
+
+    /// Same as `update_leaf`, but takes the new value by value and hands back the old leaf hash, e.g. for logging the change.
+    pub fn set_leaf(&mut self, index: usize, value: T) -> Result<[u8; 32], MerkleError> {
+        let old_hash = *self.get_node(0, index)?;
+        self.update_leaf(index, &value)?;
+        Ok(old_hash)
+    }
+
+    /// Removes the leaf at `index`, shifts the leaves after it one place left, and returns the removed leaf hash.
+    /// Every node right of the gap moves, so this is O(n). The layers are rebuilt from the gap rightwards,
+    /// which also re-decides padding: a last leaf that used to be the odd one out gets a real sibling again,
+    /// and vice versa. The result equals building a new tree from the remaining leaves.
+    /// Removing the only leaf leaves an empty tree, as from `with_capacity`.
+    pub fn remove_leaf(&mut self, index: usize) -> Result<[u8; 32], MerkleError> {
+        let removed = *self.get_node(0, index)?;
+        let leaf_count = self.layers[0].len();
+        self.leaf_index.clear();
+        self.layers[0].copy_within(index + 1.., index);
+        self.layers.resize(leaf_count - 1);
+        self.rebuild_right_edge(index);
+        Ok(removed)
+    }
+
+    /// Replaces several leaves at once, re-hashing every affected internal node exactly once.
+    /// Leaves that share ancestors only pay for those ancestors once, unlike repeated `update_leaf` calls.
+    /// If an index appears twice the later update wins. Nothing is changed if any index is out of bounds.
+    pub fn batch_update(&mut self, updates: Vec<(usize, T)>) -> Result<(), MerkleError> {
+        self.batch_update_counting(updates).map(|_| ())
+    }
+
+    // `batch_update`, also returning how many internal nodes were recomputed (the tests check the sharing).
+    fn batch_update_counting(
+        &mut self,
+        mut updates: Vec<(usize, T)>,
+    ) -> Result<usize, MerkleError> {
+        let leaf_count = self.layers[0].len();
+        if let Some((index, _)) = updates.iter().find(|(index, _)| *index >= leaf_count) {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: *index,
+                len: leaf_count,
+            });
+        }
+
+        // 1. Write the new leaf hashes in index order (the sort is stable, so the last duplicate wins)
+        updates.sort_by_key(|(index, _)| *index);
+        let mut dirty = BTreeSet::new();
+        for (index, value) in &updates {
+            self.layers[0][*index] = value.hash_with::<H>();
+            dirty.insert(*index);
+        }
+        self.leaf_index.clear();
+
+        // 2. Sweep upwards: each level's dirty parents come from the dirty nodes below, deduplicated by the set
+        let mut recomputed = 0;
+        for level in 0..self.layers.len() - 1 {
+            let parents: BTreeSet<usize> = dirty.iter().map(|index| index / 2).collect();
+            for &parent in &parents {
+                let layer = &self.layers[level];
+                let left = layer[parent * 2];
+                let hash = match layer.get(parent * 2 + 1) {
+                    Some(right) => hash_pair_with::<H>(&left, right),
+                    None => lone_parent::<H>(&left, self.padding),
+                };
+                self.layers[level + 1][parent] = hash;
+                recomputed += 1;
+            }
+            dirty = parents;
+        }
+
+        Ok(recomputed)
+    }
+
+    /// How odd layers were padded when this tree was built. Hand it to `MerkleProof::verify_padded`.
+    pub fn padding(&self) -> PaddingStrategy {
+        self.padding
+    }
+
+    /// How many levels sit above the leaves: the root is at depth 0 and the leaves at depth `depth()`.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    /// Number of leaves the tree covers.
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    /// Total number of hashes stored across all layers, root included.
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.layers.iter().map(|layer| layer.len()).sum()
+    }
+
+    /// The leaf layer (level 0): one hash per item the tree was built from, in input order.
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.layers[0]
+    }
+
+    /// True if some layer has a left and right child with the same hash (CVE-2012-2459).
+    /// With `DuplicateSelf` padding, a leaf list ending in `[.., X]` and one ending in `[.., X, X]` give the same root,
+    /// so such a tree could stand in for a shorter one. Bitcoin rejects blocks where this fires.
+    /// Identical leaves trigger it too. Other padding strategies can't be mutated this way and always return false.
+    pub fn is_mutated(&self) -> bool {
+        if self.padding != PaddingStrategy::DuplicateSelf {
+            return false;
+        }
+
+        self.layers
+            .iter()
+            .take(self.depth())
+            .any(|layer| layer.chunks_exact(2).any(|pair| pair[0] == pair[1]))
+    }
+
+    /// Recomputes every parent from its children and checks it against the stored hash, that each layer has
+    /// `ceil(len / 2)` nodes of the one below and that the top layer is a single root (O(n)).
+    /// Useful after loading a tree from storage you don't fully trust. An empty `with_capacity` tree counts as intact.
+    pub fn verify_integrity(&self) -> bool {
+        if self.layers[0].is_empty() {
+            return self.layers.len() == 1;
+        }
+        check_layers::<H>(&self.layers, self.padding).is_ok()
+    }
+
+    /// Builds a fresh tree from this tree's leaf layer alone, with the same hasher and padding.
+    /// None of the stored parents are read, so the result can be trusted even if they were tampered with;
+    /// `recomputed.layers() != tree.layers()` means the original was not intact.
+    /// Returns an Error for an empty `with_capacity` tree.
+    pub fn recompute_from_leaves(&self) -> Result<MerkleTree<(), H>, MerkleError> {
+        MerkleTree::from_leaf_layer(self.layers[0].to_vec(), self.padding)
+    }
+
+    /// True if this tree has more leaves than `expected_leaf_count` but the same root as the tree built
+    /// from only its first `expected_leaf_count` leaves, i.e. it is a duplicated-last-leaf variant of that tree.
+    pub fn is_mutation_of(&self, expected_leaf_count: usize) -> bool {
+        let leaf_count = self.layers[0].len();
+        if expected_leaf_count == 0 || expected_leaf_count >= leaf_count {
+            return false;
+        }
+
+        let shorter = build_layers::<H>(&self.layers[0][..expected_leaf_count], self.padding);
+        shorter.last().unwrap()[0] == *self.root_bytes()
+    }
+
+    /// Finds a leaf where `self` and `other` differ by walking down from the root, always into a child whose hashes
+    /// differ, so a single changed leaf is found after one comparison per level (O(log n)).
+    /// Returns `Ok(None)` if the roots match, and an Error if the trees have different sizes or padding.
+    /// If several leaves differ, the leftmost one under the first differing left subtree is returned.
+    pub fn diff_with(&self, other: &MerkleTree<T, H>) -> Result<Option<usize>, MerkleError> {
+        self.diff_counting(other).map(|(index, _)| index)
+    }
+
+    // diff_with, also reporting how many hash comparisons it took.
+    fn diff_counting(
+        &self,
+        other: &MerkleTree<T, H>,
+    ) -> Result<(Option<usize>, usize), MerkleError> {
+        if self.leaf_count() != other.leaf_count() {
+            return Err(MerkleError::LeafCountMismatch(
+                self.leaf_count(),
+                other.leaf_count(),
+            ));
+        }
+        if self.padding != other.padding {
+            return Err(MerkleError::InvalidLayers(
+                "the trees use different padding strategies".to_string(),
+            ));
+        }
+
+        let mut comparisons = 1;
+        if self.root_bytes() == other.root_bytes() {
+            return Ok((None, comparisons));
+        }
+
+        // Same size means same shape, so both trees can be walked with the same (level, index)
+        let mut index = 0;
+        for level in (0..self.depth()).rev() {
+            let left = 2 * index;
+            // A lone node only has a left child, so that's where the difference is
+            if left + 1 >= self.layers[level].len() {
+                index = left;
+                continue;
+            }
+
+            comparisons += 1;
+            index = if self.layers[level][left] != other.layers[level][left] {
+                left
+            } else {
+                left + 1
+            };
+        }
+
+        Ok((Some(index), comparisons))
+    }
+
+    // Every position holding `hash`, smallest first.
+    fn positions_of(&self, hash: &[u8; 32]) -> impl Iterator<Item = usize> + '_ {
+        self.leaf_index.positions(&self.layers[0], hash)
+    }
+
+    /// The position of the first leaf equal to `hash`, ready to pass to `get_proof`.
+    pub fn find_leaf_index(&self, hash: &[u8; 32]) -> Option<usize> {
+        self.positions_of(hash).next()
+    }
+
+    /// Same as `index_of`.
+    pub fn find_leaf_index_of(&self, item: &T) -> Option<usize> {
+        self.index_of(item)
+    }
+
+    /// The position of the first leaf holding `value`, hashed the way the tree hashes its leaves.
+    /// The first lookup after building or changing the tree indexes the leaf hashes (O(n)); lookups after that
+    /// are O(1) with `std` and O(log n) without it. Equal values hash to the same leaf; `indices_of` returns all of them.
+    pub fn index_of(&self, value: &T) -> Option<usize> {
+        self.find_leaf_index(&value.hash_with::<H>())
+    }
+
+    /// Every position holding `value`, smallest first. Empty if it isn't in the tree.
+    pub fn indices_of(&self, value: &T) -> Vec<usize> {
+        self.positions_of(&value.hash_with::<H>()).collect()
+    }
+
+    /// Whether any leaf holds `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        self.index_of(value).is_some()
+    }
+
+    /// Iterates over the leaf hashes in insertion order.
+    pub fn iter(&self) -> core::slice::Iter<'_, [u8; 32]> {
+        self.layers[0].iter()
+    }
+
+    /// Same as `iter`, under the name that says what it walks. It knows its length and runs backwards too.
+    /// The tree keeps only hashes, so there is no iterator over the original values.
+    pub fn iter_leaves(&self) -> core::slice::Iter<'_, [u8; 32]> {
+        self.iter()
+    }
+
+    /// Iterates over every node as `(level, index, hash)`, leaves (level 0) first and the root last.
+    pub fn iter_all_nodes(&self) -> impl Iterator<Item = (usize, usize, &[u8; 32])> {
+        self.layers.iter().enumerate().flat_map(|(level, layer)| {
+            layer
+                .iter()
+                .enumerate()
+                .map(move |(index, hash)| (level, index, hash))
+        })
+    }
+
+    /// Read-only view of every layer, leaves first and root last.
+    /// Each layer is a slice into the tree's one flat buffer of hashes, so no hash is copied.
+    pub fn layers(&self) -> Vec<&[[u8; 32]]> {
+        self.layers.iter().collect()
+    }
+
+    /// Walks the layers from the leaves up, each with its level and position; `.rev()` walks from the root down.
+    pub fn iter_layers(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = LayerView<'_>> + ExactSizeIterator + '_ {
+        let depth = self.depth();
+        self.layers
+            .iter()
+            .enumerate()
+            .map(move |(level, nodes)| LayerView {
+                level,
+                nodes,
+                is_leaf_layer: level == 0,
+                is_root_layer: level == depth,
+            })
+    }
+
+    /// Takes the tree apart into its raw layers (leaves first, root last). `from_layers` puts it back together.
+    pub fn into_layers(self) -> Vec<Vec<[u8; 32]>> {
+        self.layers.to_vecs()
+    }
+
+    /// The hashes at `level` (0 = leaves), or None if the tree has no such level.
+    pub fn layer(&self, level: usize) -> Option<&[[u8; 32]]> {
+        self.layers.get(level)
+    }
+
+    /// The hash at position `index` of `level` (0 = leaves), or None if there is no such node.
+    pub fn node(&self, level: usize, index: usize) -> Option<&[u8; 32]> {
+        self.layers.get(level)?.get(index)
+    }
+
+    /// Like `node`, but returns an Error naming the `level` or `index` that was out of range.
+    pub fn get_node(&self, level: usize, index: usize) -> Result<&[u8; 32], MerkleError> {
+        let layer = self.internal_nodes_at_level(level)?;
+        layer.get(index).ok_or(MerkleError::IndexOutOfBounds {
+            index,
+            len: layer.len(),
+        })
+    }
+
+    /// The node the one at (`level`, `index`) is paired with on the way up, i.e. position `index ^ 1`.
+    /// A lone last node gets whatever the padding pairs it with (itself for `DuplicateSelf`).
+    /// Returns an Error for the root and for a node `PaddingStrategy::PromoteOdd` carries up, since neither has a sibling.
+    pub fn sibling_of(&self, level: usize, index: usize) -> Result<&[u8; 32], MerkleError> {
+        let node = self.get_node(level, index)?;
+        let layer = &self.layers[level];
+        if let Some(sibling) = layer.get(index ^ 1) {
+            return Ok(sibling);
+        }
+
+        // No real neighbour: only a lone node below the root has a padding sibling
+        let is_root = level + 1 == self.layers.len();
+        match self.padding {
+            PaddingStrategy::DuplicateSelf if !is_root => Ok(node),
+            PaddingStrategy::FixedZeroHash if !is_root => Ok(&[0u8; 32]),
+            PaddingStrategy::Custom(ref sibling) if !is_root => Ok(sibling),
+            _ => Err(MerkleError::IndexOutOfBounds {
+                index: index ^ 1,
+                len: layer.len(),
+            }),
+        }
+    }
+
+    /// All hashes at `level`, where level 0 is the leaves and the last level is the root.
+    /// Returns an Error if the tree has no such level.
+    pub fn internal_nodes_at_level(&self, level: usize) -> Result<&[[u8; 32]], MerkleError> {
+        self.layers.get(level).ok_or(MerkleError::IndexOutOfBounds {
+            index: level,
+            len: self.layers.len(),
+        })
+    }
+
+    /// The raw 32-byte Merkle Root.
+    /// An empty tree (from `with_capacity` or an empty `collect`) has no root and returns all zeros instead.
+    /// That is a sentinel, not a digest: check `leaf_count() > 0` before publishing, signing or comparing a root.
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        // The root is the last layers first (and only) element
+        self.layers
+            .last()
+            .and_then(|layer| layer.first())
+            .unwrap_or(&[0u8; 32])
+    }
+
+    /// An owned copy of `root_bytes`, e.g. to sign or to embed in a message header.
+    /// The root is stored as raw bytes, so there is nothing to decode and nothing that can fail.
+    pub fn root_as_bytes(&self) -> [u8; 32] {
+        *self.root_bytes()
+    }
+
+    /// The Merkle Root as a 64-character hex string; 64 zeros for an empty tree, like `root_bytes`.
+    pub fn root_hex(&self) -> String {
+        to_hex(self.root_bytes())
+    }
+
+    /// Builds a `MerkleProof` for the leaf at `leaf_index`.
+    /// Each sibling records whether it sits on the left or the right, so the verifier never has to guess.
+    /// Steps run from the leaves up to (but not including) the root.
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        let leaf_count = self.layers[0].len();
+        // Read the nodes the path goes through; a lone node pointing at itself stands in for its padding sibling
+        let siblings = proof_node_coordinates(leaf_index, leaf_count, self.padding)?
+            .into_iter()
+            .map(|(level, index, direction)| {
+                let node = self.layers[level][index];
+                let padded = direction == ProofDirection::Right && index.is_multiple_of(2);
+                ProofNode {
+                    direction,
+                    hash: match padding_sibling(&node, self.padding) {
+                        Some(sibling) if padded => sibling,
+                        _ => node,
+                    },
+                }
+            })
+            .collect();
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count,
+            leaf_hash: self.layers[0][leaf_index],
+            siblings,
+            root: *self.root_bytes(),
+        })
+    }
+}
+
+impl MerkleProof {
+    /// Recomputes the root from `leaf_hash` and the siblings and checks it against the proof's own `root`.
+    /// This only shows the proof is consistent: compare `root` with a root you trust, or use `verify_against`.
+    pub fn verify(&self) -> bool {
+        self.verify_with::<Sha256Hasher>()
+    }
+
+    /// Same as `verify`, for trees built with a hasher other than SHA-256.
+    pub fn verify_with<H: MerkleHasher>(&self) -> bool {
+        verify_proof_with::<H>(&self.root, &self.leaf_hash, &self.siblings)
+    }
+
+    /// The sibling hashes without their directions, leaf level first: the whole proof for `verify_sorted_proof`.
+    pub fn sibling_hashes(&self) -> Vec<[u8; 32]> {
+        self.siblings.iter().map(|node| node.hash).collect()
+    }
+
+    /// Checks that `leaf_hash` is part of the tree with the given `root`, without needing the tree itself.
+    pub fn verify_against(&self, leaf_hash: &[u8; 32], root: &[u8; 32]) -> bool {
+        self.verify_against_with::<Sha256Hasher>(leaf_hash, root)
+    }
+
+    /// Same as `verify_against`, for trees built with a hasher other than SHA-256.
+    pub fn verify_against_with<H: MerkleHasher>(
+        &self,
+        leaf_hash: &[u8; 32],
+        root: &[u8; 32],
+    ) -> bool {
+        // The proof must be about the leaf and the root we were handed, not some other ones
+        *leaf_hash == self.leaf_hash && *root == self.root && self.verify_with::<H>()
+    }
+
+    /// Like `verify`, but also checks the proof has the shape `padding` gives a tree of `leaf_count` leaves:
+    /// every padded step must hold the padding sibling, and promoted nodes must have no step at all.
+    pub fn verify_padded(
+        &self,
+        leaf_hash: &[u8; 32],
+        root: &[u8; 32],
+        padding: PaddingStrategy,
+    ) -> bool {
+        self.verify_padded_with::<Sha256Hasher>(leaf_hash, root, padding)
+    }
+
+    /// Same as `verify_padded`, for trees built with a hasher other than SHA-256.
+    pub fn verify_padded_with<H: MerkleHasher>(
+        &self,
+        leaf_hash: &[u8; 32],
+        root: &[u8; 32],
+        padding: PaddingStrategy,
+    ) -> bool {
+        self.verify_for_size_with::<H>(leaf_hash, root, self.leaf_count, padding)
+    }
+
+    /// Like `verify_padded`, but for a tree of `claimed_leaf_count` leaves instead of the size the proof says.
+    /// Verifiers that know the real size should use this: it rejects proofs for the extra leaves of a
+    /// duplicated-last-leaf variant of the tree (see `MerkleTree::is_mutated`), which share its root.
+    pub fn verify_for_size(
+        &self,
+        leaf_hash: &[u8; 32],
+        root: &[u8; 32],
+        claimed_leaf_count: usize,
+        padding: PaddingStrategy,
+    ) -> bool {
+        self.verify_for_size_with::<Sha256Hasher>(leaf_hash, root, claimed_leaf_count, padding)
+    }
+
+    /// Same as `verify_for_size`, for trees built with a hasher other than SHA-256.
+    pub fn verify_for_size_with<H: MerkleHasher>(
+        &self,
+        leaf_hash: &[u8; 32],
+        root: &[u8; 32],
+        claimed_leaf_count: usize,
+        padding: PaddingStrategy,
+    ) -> bool {
+        if *leaf_hash != self.leaf_hash || self.leaf_index >= claimed_leaf_count {
+            return false;
+        }
+
+        let mut current = *leaf_hash;
+        let mut index = self.leaf_index;
+        let mut layer_len = claimed_leaf_count;
+        let mut siblings = self.siblings.iter();
+
+        // Walk the layers the way get_proof did, working out on our own where the odd nodes are
+        while layer_len > 1 {
+            if index.is_multiple_of(2) && index + 1 == layer_len {
+                // We are the odd node out: the strategy, not the proof, decides what we are paired with.
+                // A promoted node has no step at all.
+                if let Some(padding_hash) = padding_sibling(&current, padding) {
+                    match siblings.next() {
+                        Some(node)
+                            if node.direction == ProofDirection::Right
+                                && node.hash == padding_hash =>
+                        {
+                            current = hash_pair_with::<H>(&current, &padding_hash);
+                        }
+                        _ => return false,
+                    }
+                }
+            } else {
+                let Some(node) = siblings.next() else {
+                    return false;
+                };
+                current = match (index.is_multiple_of(2), node.direction) {
+                    (true, ProofDirection::Right) => hash_pair_with::<H>(&current, &node.hash),
+                    (false, ProofDirection::Left) => hash_pair_with::<H>(&node.hash, &current),
+                    // The side has to match our position in the layer
+                    _ => return false,
+                };
+            }
+
+            index /= 2;
+            layer_len = layer_len.div_ceil(2);
+        }
+
+        // Leftover siblings mean the proof was made for a different shape
+        siblings.next().is_none() && current == *root
+    }
+}
+
+/// Recomputes the root from a leaf hash and its proof steps, then compares it to the expected root.
+pub fn verify_proof(root: &[u8; 32], leaf_hash: &[u8; 32], proof: &[ProofNode]) -> bool {
+    verify_proof_with::<Sha256Hasher>(root, leaf_hash, proof)
+}
+
+/// Same as `verify_proof`, for trees built with a hasher other than SHA-256.
+pub fn verify_proof_with<H: MerkleHasher>(
+    root: &[u8; 32],
+    leaf_hash: &[u8; 32],
+    proof: &[ProofNode],
+) -> bool {
+    let mut current = *leaf_hash;
+
+    for sibling in proof {
+        // A node that was paired with itself carries its own hash as a right sibling, so this covers odd layers too
+        current = match sibling.direction {
+            ProofDirection::Left => hash_pair_with::<H>(&sibling.hash, &current),
+            ProofDirection::Right => hash_pair_with::<H>(&current, &sibling.hash),
+        };
+    }
+
+    current == *root
+}
+
+/// `verify_proof` for a light client that only has hex: the trusted root, the leaf hash and `(sibling, side)` steps,
+/// as a full node might send them. Any string that isn't 64 hex characters makes the proof fail.
+pub fn verify_inclusion(root: &str, leaf_hash: &str, proof: &[(String, ProofDirection)]) -> bool {
+    verify_inclusion_with::<Sha256Hasher>(root, leaf_hash, proof)
+}
+
+/// Same as `verify_inclusion`, for trees built with a hasher other than SHA-256.
+pub fn verify_inclusion_with<H: MerkleHasher>(
+    root: &str,
+    leaf_hash: &str,
+    proof: &[(String, ProofDirection)],
+) -> bool {
+    let (Ok(root), Ok(mut current)) = (from_hex(root), from_hex(leaf_hash)) else {
+        return false;
+    };
+
+    for (sibling, direction) in proof {
+        let Ok(sibling) = from_hex(sibling) else {
+            return false;
+        };
+        current = match direction {
+            ProofDirection::Left => hash_pair_with::<H>(&sibling, &current),
+            ProofDirection::Right => hash_pair_with::<H>(&current, &sibling),
+        };
+    }
+
+    current == root
+}
+
+/// Verifies a proof from a `SortedPairs<Sha256Hasher>` tree given only the sibling hashes.
+/// Each pair is sorted before hashing, so the verifier doesn't need to know which side a sibling was on.
+pub fn verify_sorted_proof(root: &[u8; 32], leaf_hash: &[u8; 32], siblings: &[[u8; 32]]) -> bool {
+    verify_sorted_proof_with::<Sha256Hasher>(root, leaf_hash, siblings)
+}
+
+/// Same as `verify_sorted_proof`, for trees built with `SortedPairs<H>`.
+pub fn verify_sorted_proof_with<H: MerkleHasher>(
+    root: &[u8; 32],
+    leaf_hash: &[u8; 32],
+    siblings: &[[u8; 32]],
+) -> bool {
+    let mut current = *leaf_hash;
+
+    for sibling in siblings {
+        current = hash_pair_with::<SortedPairs<H>>(&current, sibling);
+    }
+
+    current == *root
+}
+
+/// The outcome of `verify_batch`: how many proofs were looked at and which ones failed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BatchResult {
+    /// Number of entries actually checked (fewer than the input if the batch stopped early).
+    pub checked: usize,
+    /// Positions in the input slice whose proofs did not verify.
+    pub failures: Vec<usize>,
+}
+
+impl BatchResult {
+    /// True when every checked proof verified.
+    pub fn all_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Verifies many `(leaf_hash, proof)` pairs against the same root in one call.
+/// With `stop_on_first_failure` the batch ends at the first bad proof, otherwise every failure is collected.
+pub fn verify_batch(
+    root: &[u8; 32],
+    items: &[([u8; 32], MerkleProof)],
+    stop_on_first_failure: bool,
+) -> BatchResult {
+    verify_batch_with::<Sha256Hasher>(root, items, stop_on_first_failure)
+}
+
+/// Same as `verify_batch`, for trees built with a hasher other than SHA-256.
+pub fn verify_batch_with<H: MerkleHasher>(
+    root: &[u8; 32],
+    items: &[([u8; 32], MerkleProof)],
+    stop_on_first_failure: bool,
+) -> BatchResult {
+    let mut result = BatchResult::default();
+
+    for (position, (leaf_hash, proof)) in items.iter().enumerate() {
+        result.checked += 1;
+        if !proof.verify_against_with::<H>(leaf_hash, root) {
+            result.failures.push(position);
+            if stop_on_first_failure {
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+// --- TESTS ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_root_consistency() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let tree1 = MerkleTree::new(data.clone()).unwrap();
+        let tree2 = MerkleTree::new(data).unwrap();
+        assert_eq!(tree1.root_bytes(), tree2.root_bytes());
+    }
+
+    #[test]
+    fn test_odd_leaves() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let tree = MerkleTree::new(data).unwrap();
+        // 3 leaves should result in 3 levels:
+        // Level 0: [H(A), H(B), H(C)]
+        // Level 1: [H(AB), H(CC)]
+        // Level 2: [H(ABCC)]
+        assert_eq!(tree.layers.len(), 3);
+    }
+
+    #[test]
+    fn test_empty_data_fails() {
+        let data: Vec<String> = vec![];
+        let result = MerkleTree::new(data);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err, MerkleError::EmptyInput);
+        assert_eq!(err.to_string(), "Cannot create a Merkle Tree with no data.");
+    }
+
+    #[test]
+    fn test_errors_work_with_question_mark() {
+        fn first_proof_len(data: Vec<String>) -> Result<usize, Box<dyn std::error::Error>> {
+            let tree = MerkleTree::new(data)?;
+            Ok(tree.get_proof(3)?.siblings.len())
+        }
+
+        let err = first_proof_len(vec!["A".to_string()]).unwrap_err();
+        assert_eq!(err.to_string(), "Index 3 is out of bounds for 1 elements.");
+        let err = first_proof_len(vec![]).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MerkleError>(),
+            Some(&MerkleError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_proof_verifies_for_every_leaf() {
+        let data = vec![
+            "A".to_string(),
+            "B".to_string(),
+            "C".to_string(),
+            "D".to_string(),
+            "E".to_string(),
+        ];
+        let tree = MerkleTree::new(data).unwrap();
+        // 5 leaves means the last leaf gets duplicated on the way up, so this covers the odd case too
+        for i in 0..5 {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(verify_proof(
+                tree.root_bytes(),
+                &tree.layers[0][i],
+                &proof.siblings
+            ));
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_fails() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let tree = MerkleTree::new(data).unwrap();
+        let mut proof = tree.get_proof(1).unwrap();
+        proof.siblings[0].hash = hash_data(b"not B's sibling");
+        assert!(!verify_proof(
+            tree.root_bytes(),
+            &tree.layers[0][1],
+            &proof.siblings
+        ));
+    }
+
+    #[test]
+    fn test_proof_out_of_bounds() {
+        let data = vec!["A".to_string(), "B".to_string()];
+        let tree = MerkleTree::new(data).unwrap();
+        assert_eq!(
+            tree.get_proof(2),
+            Err(MerkleError::IndexOutOfBounds { index: 2, len: 2 })
+        );
+    }
+
+    #[test]
+    fn test_get_proof_length() {
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let proof = tree.get_proof(5).unwrap();
+        // ceil(log2(7)) = 3 levels between the leaves and the root
+        assert_eq!(proof.siblings.len(), 3);
+        assert_eq!(proof.leaf_index, 5);
+        assert_eq!(proof.leaf_hash, tree.layers[0][5]);
+        assert!(tree.get_proof(7).is_err());
+    }
+
+    // A second hasher so we can check that the tree really uses H.
+    struct Sha512TruncHasher;
+
+    impl MerkleHasher for Sha512TruncHasher {
+        fn hash(data: &[u8]) -> [u8; 32] {
+            use sha2::{Digest, Sha512_256};
+            Sha512_256::digest(data).into()
+        }
+    }
+
+    #[test]
+    fn test_swapping_hasher_changes_root() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let sha_tree = MerkleTree::new(data.clone()).unwrap();
+        let other_tree = MerkleTree::<String, Sha512TruncHasher>::with_hasher(data).unwrap();
+        assert_ne!(sha_tree.root_bytes(), other_tree.root_bytes());
+
+        // Proofs still verify as long as the verifier uses the same hasher
+        let proof = other_tree.get_proof(2).unwrap();
+        assert!(verify_proof_with::<Sha512TruncHasher>(
+            other_tree.root_bytes(),
+            &other_tree.layers[0][2],
+            &proof.siblings
+        ));
+        assert!(!verify_proof(
+            other_tree.root_bytes(),
+            &other_tree.layers[0][2],
+            &proof.siblings
+        ));
+    }
+
+    #[test]
+    fn test_digest_hasher_hashes_leaves_and_nodes() {
+        use sha2::{Digest, Sha512_256};
+
+        let data = vec!["A".to_string(), "B".to_string()];
+        let sha_tree = MerkleTree::new(data.clone()).unwrap();
+        let other = MerkleTree::<String, DigestHasher<Sha512_256>>::with_hasher(data).unwrap();
+        assert_ne!(sha_tree.root_bytes(), other.root_bytes());
+
+        // Leaves go through the chosen digest too, not SHA-256
+        let expected_leaf: [u8; 32] = Sha512_256::digest(b"A").into();
+        assert_eq!(other.layers[0][0], expected_leaf);
+
+        // The default is still SHA-256 through the generic adapter
+        let explicit = MerkleTree::<String, DigestHasher<sha2::Sha256>>::with_hasher(vec![
+            "A".to_string(),
+            "B".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(explicit.root_bytes(), sha_tree.root_bytes());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_root_differs_from_sha256() {
+        let data = vec!["A".to_string(), "B".to_string()];
+        let sha_tree = MerkleTree::new(data.clone()).unwrap();
+        let blake_tree = MerkleTree::<String, Blake3Hasher>::with_hasher(data).unwrap();
+        assert_ne!(sha_tree.root_bytes(), blake_tree.root_bytes());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_vectors() {
+        // BLAKE3("abc") from the reference implementation (b3sum)
+        const ABC: &str = "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+        let single =
+            MerkleTree::<String, Blake3Hasher>::with_hasher(vec!["abc".to_string()]).unwrap();
+        assert_eq!(single.root_hex(), ABC);
+
+        // Three leaves: the odd one is paired with itself, every node is BLAKE3(left || right)
+        let data = vec!["abc".to_string(), "def".to_string(), "ghi".to_string()];
+        let tree = MerkleTree::<String, Blake3Hasher>::with_hasher(data.clone()).unwrap();
+        let [a, d, g] = [b"abc", b"def", b"ghi"].map(|bytes| *blake3::hash(bytes).as_bytes());
+        let left = *blake3::hash(&[a, d].concat()).as_bytes();
+        let right = *blake3::hash(&[g, g].concat()).as_bytes();
+        assert_eq!(
+            tree.root_bytes(),
+            blake3::hash(&[left, right].concat()).as_bytes()
+        );
+        assert_eq!(
+            tree.root_hex(),
+            "f7c9a6f85283d18f275252f53dbd3a5799ce4c245b325d88371171f57dde1956"
+        );
+
+        // Proofs are generated and checked with the same backend
+        for (i, item) in data.iter().enumerate() {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(proof.verify_with::<Blake3Hasher>());
+            assert!(proof.verify_against_with::<Blake3Hasher>(
+                &item.hash_with::<Blake3Hasher>(),
+                tree.root_bytes()
+            ));
+            assert!(!proof.verify());
+        }
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    fn test_keccak256_vectors() {
+        // keccak256("abc"), as printed by ethers.js / cast keccak
+        const ABC: &str = "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45";
+
+        let single =
+            MerkleTree::<String, Keccak256Hasher>::with_hasher(vec!["abc".to_string()]).unwrap();
+        assert_eq!(single.root_hex(), ABC);
+        assert_eq!(to_hex(single.root_bytes()), ABC);
+
+        // Leaves and internal nodes both use Keccak-256
+        let data = vec!["abc".to_string(), "def".to_string()];
+        let tree = MerkleTree::<String, Keccak256Hasher>::with_hasher(data).unwrap();
+        assert_eq!(to_hex(&tree.layers[0][0]), ABC);
+        assert_eq!(
+            *tree.root_bytes(),
+            Keccak256Hasher::hash_nodes(&tree.layers[0][0], &tree.layers[0][1])
+        );
+    }
+
+    #[test]
+    fn test_merkle_proof_verify() {
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        // Index 6 is the odd one out at the bottom layer, so it gets paired with itself
+        for i in [0, 3, 6] {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(proof.verify_against(&tree.layers[0][i], tree.root_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_merkle_proof_tampered_sibling_fails() {
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let mut proof = tree.get_proof(5).unwrap();
+        proof.siblings[1].hash = hash_data(b"tampered");
+        assert!(!proof.verify_against(&tree.layers[0][5], tree.root_bytes()));
+    }
+
+    #[test]
+    fn test_merkle_proof_carries_its_root() {
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let proof = tree.get_proof(4).unwrap();
+        assert_eq!(proof.root, *tree.root_bytes());
+        assert!(proof.verify());
+
+        // Changing any one sibling breaks the proof
+        for step in 0..proof.siblings.len() {
+            let mut tampered = proof.clone();
+            tampered.siblings[step].hash[0] ^= 1;
+            assert!(!tampered.verify(), "step {} was tampered", step);
+        }
+    }
+
+    #[test]
+    fn test_merkle_proof_wrong_root_fails() {
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let other = MerkleTree::new(vec!["X".to_string()]).unwrap();
+        let proof = tree.get_proof(2).unwrap();
+        assert!(!proof.verify_against(&tree.layers[0][2], other.root_bytes()));
+    }
+
+    #[test]
+    fn test_proof_directions_for_edge_and_middle_leaves() {
+        let data: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        // Far left: every sibling is on the right
+        let left = tree.get_proof(0).unwrap();
+        assert!(
+            left.siblings
+                .iter()
+                .all(|n| n.direction == ProofDirection::Right)
+        );
+        // Far right: every sibling is on the left
+        let right = tree.get_proof(7).unwrap();
+        assert!(
+            right
+                .siblings
+                .iter()
+                .all(|n| n.direction == ProofDirection::Left)
+        );
+        // Middle: index 5 = 0b101 -> Left, Right, Left
+        let middle = tree.get_proof(5).unwrap();
+        let directions: Vec<_> = middle.siblings.iter().map(|n| n.direction).collect();
+        assert_eq!(
+            directions,
+            vec![
+                ProofDirection::Left,
+                ProofDirection::Right,
+                ProofDirection::Left
+            ]
+        );
+
+        for (i, proof) in [(0, left), (7, right), (5, middle)] {
+            assert!(proof.verify_against(&tree.layers[0][i], tree.root_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_proof_node_coordinates() {
+        use ProofDirection::{Left, Right};
+
+        // Leaf 4 of 5: padded on the two lower levels, then the left half of the tree
+        let coordinates = |padding| proof_node_coordinates(4, 5, padding).unwrap();
+        assert_eq!(
+            coordinates(PaddingStrategy::DuplicateSelf),
+            vec![(0, 4, Right), (1, 2, Right), (2, 0, Left)]
+        );
+        assert_eq!(coordinates(PaddingStrategy::PromoteOdd), vec![(2, 0, Left)]);
+        assert_eq!(
+            proof_node_coordinates(5, 5, PaddingStrategy::DuplicateSelf),
+            Err(MerkleError::IndexOutOfBounds { index: 5, len: 5 })
+        );
+        assert_eq!(
+            proof_node_coordinates(0, 1, PaddingStrategy::DuplicateSelf),
+            Ok(vec![])
+        );
+
+        let paddings = [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+            PaddingStrategy::Custom(hash_data(b"pad")),
+        ];
+        for leaf_count in 1..=19 {
+            for padding in paddings {
+                let data: Vec<String> = (0..leaf_count).map(|i| i.to_string()).collect();
+                let tree = MerkleTree::<String>::with_padding(data, padding).unwrap();
+                for leaf in 0..leaf_count {
+                    let coordinates = proof_node_coordinates(leaf, leaf_count, padding).unwrap();
+                    let proof = tree.get_proof(leaf).unwrap();
+                    assert_eq!(coordinates.len(), proof.siblings.len());
+
+                    // Each stored coordinate holds the proof's sibling, unless it is a lone node standing in for its padding
+                    for (&(level, index, direction), step) in
+                        coordinates.iter().zip(&proof.siblings)
+                    {
+                        assert_eq!(step.direction, direction);
+                        let padded = direction == Right && index.is_multiple_of(2);
+                        let expected = if padded {
+                            padding_sibling(&tree.layers[level][index], padding).unwrap()
+                        } else {
+                            tree.layers[level][index]
+                        };
+                        assert_eq!(step.hash, expected, "{} leaves, leaf {}", leaf_count, leaf);
+                    }
+
+                    // And apart from the levels the node was promoted on, there is one step per level
+                    let levels: Vec<usize> = coordinates.iter().map(|c| c.0).collect();
+                    assert!(levels.windows(2).all(|pair| pair[0] < pair[1]));
+                    if padding != PaddingStrategy::PromoteOdd {
+                        assert_eq!(levels, (0..tree.depth()).collect::<Vec<_>>());
+                    }
+                    assert!(proof.verify_padded(&tree.layers[0][leaf], tree.root_bytes(), padding));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_root_over_raw_bytes() {
+        // root = SHA256(SHA256("alice->bob:10") || SHA256("bob->charlie:5")), hashed over the raw 32-byte digests
+        let data = vec!["alice->bob:10".to_string(), "bob->charlie:5".to_string()];
+        let tree = MerkleTree::new(data).unwrap();
+        assert_eq!(
+            tree.root_hex(),
+            "d671f77193be1efb4a489af9b6377b305c40081a52ec5f67c3159b53351e31f1"
+        );
+    }
+
+    #[test]
+    fn test_known_root_odd_layer() {
+        // Computed independently with Python's hashlib over raw bytes, C paired with itself:
+        // SHA256(SHA256(H(A) || H(B)) || SHA256(H(C) || H(C)))
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let tree = MerkleTree::new(data).unwrap();
+        assert_eq!(
+            tree.root_hex(),
+            "420940ee1c7a73de80cfa2554efb4e6cec7ea745fed73108ccb06886054df8c6"
+        );
+    }
+
+    #[test]
+    fn test_legacy_hex_concat_matches_old_roots() {
+        // The root the demo binary printed before hashes were combined as raw bytes
+        let data = vec!["alice->bob:10".to_string(), "bob->charlie:5".to_string()];
+        let legacy = MerkleTree::<String, LegacyHexConcat>::with_hasher(data.clone()).unwrap();
+        assert_eq!(
+            legacy.root_hex(),
+            "10cfd5c0ab59728057d0ff40fc8f03fce8a67e06d94dad6c9407f63a5c4a0c05"
+        );
+        assert_ne!(
+            legacy.root_bytes(),
+            MerkleTree::new(data).unwrap().root_bytes()
+        );
+    }
+
+    #[test]
+    fn test_streamed_hashing_matches_concatenation() {
+        let (a, b, c) = (hash_data(b"a"), hash_data(b"b"), hash_data(b"c"));
+
+        // Feeding the hasher piece by piece gives the same digest as hashing one buffer
+        assert_eq!(
+            Sha256Hasher::hash_parts(&[b"ab", b"", b"c"]),
+            hash_data(b"abc")
+        );
+        assert_eq!(
+            Sha256Hasher::hash_nodes(&a, &b),
+            hash_data(&[a, b].concat())
+        );
+        assert_eq!(
+            Sha256Hasher::hash_children(&[a, b, c]),
+            hash_data(&[a, b, c].concat())
+        );
+        assert_eq!(
+            DomainSeparated::<Sha256Hasher>::hash_leaf(b"a"),
+            hash_data(&[0x00, b'a'])
+        );
+        assert_eq!(
+            DomainSeparated::<Sha256Hasher>::hash_children(&[a, b]),
+            hash_data(&[&[0x01][..], &a, &b].concat())
+        );
+        assert_eq!(
+            LegacyHexConcat::<Sha256Hasher>::hash_nodes(&a, &b),
+            hash_data(format!("{}{}", to_hex(&a), to_hex(&b)).as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_from_layers_round_trip() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let hex_layers: Vec<Vec<String>> = tree
+            .layers
+            .iter()
+            .map(|layer| layer.iter().map(|hash| to_hex(hash)).collect())
+            .collect();
+
+        let rebuilt = MerkleTree::<String>::from_layers(hex_layers.clone()).unwrap();
+        assert_eq!(rebuilt.layers, tree.layers);
+
+        // Swapping a leaf without fixing its parents must be caught
+        let mut tampered = hex_layers;
+        tampered[0][1] = to_hex(&hash_data(b"evil"));
+        assert!(matches!(
+            MerkleTree::<String>::from_layers(tampered),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_hex_rejects_bad_input() {
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex(&"zz".repeat(32)).is_err());
+        assert_eq!(
+            from_hex(&"+f".repeat(32)),
+            Err(MerkleError::HashDecodingError(
+                "invalid hex pair: +f".to_string()
+            ))
+        );
+        assert!(MerkleTree::<()>::from_hex_hashes(vec!["+f".repeat(32)]).is_err());
+        assert_eq!(from_hex(&"ff".repeat(32)).unwrap(), [0xff; 32]);
+    }
+
+    #[test]
+    fn test_update_leaf_matches_rebuild() {
+        let mut data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(data.clone()).unwrap();
+
+        // Index 6 is the duplicated odd leaf, index 3 is a normal right child
+        for index in [6, 3, 0] {
+            let new_value = format!("changed-{}", index);
+            tree.update_leaf(index, &new_value).unwrap();
+            data[index] = new_value;
+
+            let rebuilt = MerkleTree::new(data.clone()).unwrap();
+            assert_eq!(tree.root_bytes(), rebuilt.root_bytes());
+            assert_eq!(tree.layers, rebuilt.layers);
+        }
+
+        assert_eq!(
+            tree.update_leaf(7, &"x".to_string()),
+            Err(MerkleError::IndexOutOfBounds { index: 7, len: 7 })
+        );
+    }
+
+    #[test]
+    fn test_set_leaf_returns_old_hash() {
+        // 5 leaves: leaf 4 is paired with its own copy.
+        // 6 leaves: leaves 4 and 5 sit under the node that gets duplicated one level up.
+        for (size, indices) in [(5, vec![0, 4, 3]), (6, vec![0, 5, 4])] {
+            let mut data: Vec<String> = (0..size).map(|i| i.to_string()).collect();
+            let mut tree = MerkleTree::new(data.clone()).unwrap();
+
+            for index in indices {
+                let new_value = format!("balance-{}", index);
+                let old_hash = tree.set_leaf(index, new_value.clone()).unwrap();
+                assert_eq!(old_hash, data[index].hash());
+                data[index] = new_value;
+
+                let rebuilt = MerkleTree::new(data.clone()).unwrap();
+                assert_eq!(
+                    tree.layers, rebuilt.layers,
+                    "size {}, index {}",
+                    size, index
+                );
+            }
+
+            assert_eq!(
+                tree.set_leaf(size, "x".to_string()),
+                Err(MerkleError::IndexOutOfBounds {
+                    index: size,
+                    len: size
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_integrity_catches_corrupted_node() {
+        let mut tree = MerkleTree::new((0..6).map(|i| i.to_string()).collect()).unwrap();
+        assert!(tree.verify_integrity());
+        assert!(MerkleTree::<String>::with_capacity(4).verify_integrity());
+
+        // Flip one bit of an internal node
+        let mut corrupted = tree.clone();
+        corrupted.layers[1][2][0] ^= 1;
+        assert!(!corrupted.verify_integrity());
+
+        // A layer with the wrong number of nodes is caught even if the root still matches
+        let mut wrong_count = tree.clone();
+        let mut layers = tree.clone().into_layers();
+        layers[1].push([0u8; 32]);
+        wrong_count.layers = Layers::from_vecs(layers);
+        assert!(!wrong_count.verify_integrity());
+
+        // So is a second "root"
+        let mut layers = tree.clone().into_layers();
+        layers.last_mut().unwrap().push([0u8; 32]);
+        tree.layers = Layers::from_vecs(layers);
+        assert!(!tree.verify_integrity());
+    }
+
+    #[test]
+    fn test_recompute_from_leaves_ignores_stored_parents() {
+        let data: Vec<String> = (0..9).map(|i| i.to_string()).collect();
+        let tree =
+            MerkleTree::<String>::with_padding(data.clone(), PaddingStrategy::PromoteOdd).unwrap();
+        let recomputed = tree.recompute_from_leaves().unwrap();
+        assert_eq!(recomputed.layers(), tree.layers());
+        assert_eq!(recomputed.padding(), PaddingStrategy::PromoteOdd);
+
+        // Tamper with a parent and the root: the fresh tree still has the honest root
+        let mut tampered = tree.clone();
+        tampered.layers[2][1][0] ^= 1;
+        tampered.layers[4][0] = [0u8; 32];
+        let fresh = tampered.recompute_from_leaves().unwrap();
+        assert_ne!(fresh.layers(), tampered.layers());
+        assert_eq!(fresh.root_bytes(), tree.root_bytes());
+        assert!(fresh.get_proof(8).unwrap().verify());
+
+        assert_eq!(
+            MerkleTree::<String>::with_capacity(2)
+                .recompute_from_leaves()
+                .err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_verify_batch_reports_failure_indices() {
+        let data: Vec<String> = (0..6).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let mut items: Vec<_> = (0..6)
+            .map(|i| (tree.layers[0][i], tree.get_proof(i).unwrap()))
+            .collect();
+
+        // Corrupt entries 1 and 4 in two different ways
+        items[1].1.siblings[0].hash = hash_data(b"corrupt");
+        items[4].0 = hash_data(b"wrong leaf");
+
+        let all = verify_batch(tree.root_bytes(), &items, false);
+        assert_eq!(all.checked, 6);
+        assert_eq!(all.failures, vec![1, 4]);
+        assert!(!all.all_valid());
+
+        let early = verify_batch(tree.root_bytes(), &items, true);
+        assert_eq!(early.checked, 2);
+        assert_eq!(early.failures, vec![1]);
+    }
+
+    #[test]
+    fn test_domain_separation_blocks_internal_node_as_leaf() {
+        let data: Vec<String> = (0..4).map(|i| i.to_string()).collect();
+
+        // Without separation, the 64 bytes of two children make a fake "leaf" that hashes to their parent
+        let plain = MerkleTree::new(data.clone()).unwrap();
+        let crafted = [plain.layers[0][0], plain.layers[0][1]].concat();
+        let forged_leaf = hash_data(&crafted);
+        assert_eq!(forged_leaf, plain.layers[1][0]);
+        let forged_path = [ProofNode {
+            direction: ProofDirection::Right,
+            hash: plain.layers[1][1],
+        }];
+        assert!(verify_proof(plain.root_bytes(), &forged_leaf, &forged_path));
+
+        // With separation, the crafted leaf becomes H(0x00 || ...) and can't match the H(0x01 || ...) node
+        let ct = MerkleTree::new_ct(data).unwrap();
+        let crafted = [ct.layers[0][0], ct.layers[0][1]].concat();
+        let as_leaf = DomainSeparated::<Sha256Hasher>::hash_leaf(&crafted);
+        let path = [ProofNode {
+            direction: ProofDirection::Right,
+            hash: ct.layers[1][1],
+        }];
+        assert!(!verify_proof_with::<DomainSeparated>(
+            ct.root_bytes(),
+            &as_leaf,
+            &path
+        ));
+
+        // RFC 6962: the hash of an empty leaf is SHA256(0x00)
+        let empty_leaf = MerkleTree::new_ct(vec![String::new()]).unwrap();
+        assert_eq!(
+            empty_leaf.root_hex(),
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"
+        );
+
+        // Real proofs still work and the roots differ from the plain tree
+        let proof = ct.get_proof(2).unwrap();
+        assert!(proof.verify_against_with::<DomainSeparated>(&ct.layers[0][2], ct.root_bytes()));
+        assert_ne!(ct.root_bytes(), plain.root_bytes());
+    }
+
+    #[test]
+    fn test_extend_matches_rebuild() {
+        let mut data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(data.clone()).unwrap();
+
+        tree.extend(vec!["x".to_string()]).unwrap();
+        data.push("x".to_string());
+        assert_eq!(
+            tree.root_bytes(),
+            MerkleTree::new(data.clone()).unwrap().root_bytes()
+        );
+
+        // 6 + 3 = 9 leaves -> ceil(log2(9)) + 1 = 5 layers
+        tree.extend(vec!["y".into(), "z".into(), "w".into()])
+            .unwrap();
+        assert_eq!(tree.layers.len(), 5);
+
+        assert_eq!(tree.extend(vec![]), Err(MerkleError::EmptyInput));
+    }
+
+    #[test]
+    fn test_bulk_extend_touches_fewer_nodes_than_push() {
+        let data: Vec<String> = (0..4000).map(|i| i.to_string()).collect();
+        let expected = MerkleTree::new(data.clone()).unwrap();
+
+        let mut tree = MerkleTree::new(data[..1000].to_vec()).unwrap();
+        let recomputed = tree.extend_counting(data[1000..].to_vec()).unwrap();
+        assert_eq!(tree.layers, expected.layers);
+
+        // push re-hashes one node per level above the leaves, so n leaves cost depth(n) hashes each
+        let naive: usize = (1001..=4000)
+            .map(|n: usize| n.next_power_of_two().trailing_zeros() as usize)
+            .sum();
+        assert!(recomputed < naive / 4, "{} vs {}", recomputed, naive);
+
+        // The std Extend trait takes any iterator and treats an empty one as a no-op
+        let mut by_trait = MerkleTree::new(data[..1000].to_vec()).unwrap();
+        Extend::extend(&mut by_trait, data[1000..].iter().cloned());
+        Extend::extend(&mut by_trait, core::iter::empty());
+        assert_eq!(by_trait.layers, expected.layers);
+    }
+
+    #[test]
+    fn test_push_matches_rebuild() {
+        let paddings = [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+            PaddingStrategy::Custom([7u8; 32]),
+        ];
+
+        for padding in paddings {
+            let mut data = vec!["0".to_string()];
+            let mut tree: MerkleTree<String> =
+                MerkleTree::with_padding(data.clone(), padding).unwrap();
+
+            // Compare against a fresh tree after every single append, across all odd/even transitions
+            for i in 1..200 {
+                tree.push(i.to_string());
+                data.push(i.to_string());
+
+                let fresh: MerkleTree<String> =
+                    MerkleTree::with_padding(data.clone(), padding).unwrap();
+                assert_eq!(
+                    tree.layers,
+                    fresh.layers,
+                    "{:?} after {} leaves",
+                    padding,
+                    i + 1
+                );
+            }
+        }
+
+        // Pushing into an empty tree works too
+        let mut empty = MerkleTree::<String>::with_capacity(2);
+        empty.push("A".to_string());
+        empty.push("B".to_string());
+        assert_eq!(
+            empty,
+            MerkleTree::new(vec!["A".into(), "B".into()]).unwrap()
+        );
+    }
+
+    // A txid is already the double SHA-256 of its transaction, so it is used as the leaf as is.
+    // Block explorers print txids and roots byte-reversed, hence the reversal.
+    struct Txid([u8; 32]);
+
+    impl Txid {
+        fn from_display_hex(hex: &str) -> Self {
+            let mut bytes = from_hex(hex).unwrap();
+            bytes.reverse();
+            Txid(bytes)
+        }
+    }
+
+    impl Hashable for Txid {
+        fn hash(&self) -> [u8; 32] {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_bitcoin_block_100000_root() {
+        let txids = vec![
+            Txid::from_display_hex(
+                "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            ),
+            Txid::from_display_hex(
+                "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            ),
+            Txid::from_display_hex(
+                "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            ),
+            Txid::from_display_hex(
+                "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+            ),
+        ];
+        let tree = MerkleTree::new_btc(txids).unwrap();
+
+        let mut root = *tree.root_bytes();
+        root.reverse();
+        assert_eq!(
+            to_hex(&root),
+            "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766"
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_block_170_root() {
+        // The block with the first person-to-person transaction (Satoshi to Hal Finney)
+        let txids = vec![
+            Txid::from_display_hex(
+                "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082",
+            ),
+            Txid::from_display_hex(
+                "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+            ),
+        ];
+        let tree = MerkleTree::new_btc(txids).unwrap();
+
+        let mut root = *tree.root_bytes();
+        root.reverse();
+        assert_eq!(
+            to_hex(&root),
+            "7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff"
+        );
+        let proof = tree.get_proof(1).unwrap();
+        assert!(proof.verify_with::<DoubleSha256Hasher>());
+
+        // Single SHA-256 gives a different root for the same txids
+        let single = MerkleTree::from_hashes(tree.leaves().to_vec()).unwrap();
+        assert_ne!(single.root_bytes(), tree.root_bytes());
+    }
+
+    #[test]
+    fn test_leaves_and_levels() {
+        let data: Vec<String> = (0..3).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        assert_eq!(tree.leaves().len(), 3);
+        assert_eq!(tree.leaves()[1], hash_data(b"1"));
+        assert_eq!(tree.internal_nodes_at_level(0).unwrap(), tree.leaves());
+        assert_eq!(tree.internal_nodes_at_level(1).unwrap().len(), 2);
+        assert_eq!(
+            tree.internal_nodes_at_level(2).unwrap(),
+            [*tree.root_bytes()]
+        );
+        assert_eq!(
+            tree.internal_nodes_at_level(3),
+            Err(MerkleError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+    }
+
+    #[test]
+    fn test_iterators() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        let leaves: Vec<_> = tree.into_iter().collect();
+        assert_eq!(leaves, tree.leaves().iter().collect::<Vec<_>>());
+        let mut count = 0;
+        for hash in &tree {
+            assert_eq!(hash, &tree.layers[0][count]);
+            count += 1;
+        }
+        assert_eq!(count, 5);
+
+        // Insertion order, exact length and both ends
+        let expected: Vec<[u8; 32]> = (0..5).map(|i| i.to_string().hash()).collect();
+        assert!(tree.iter_leaves().eq(expected.iter()));
+        assert_eq!(tree.iter_leaves().len(), tree.leaf_count());
+        assert!(tree.iter_leaves().rev().eq(expected.iter().rev()));
+        let mut both_ends = tree.iter_leaves();
+        assert_eq!(both_ends.next_back(), Some(&expected[4]));
+        assert_eq!(both_ends.next(), Some(&expected[0]));
+        assert_eq!(both_ends.len(), 3);
+        assert_eq!((&tree).into_iter().skip(2).step_by(2).count(), 2);
+
+        let nodes: Vec<_> = tree.iter_all_nodes().collect();
+        assert_eq!(nodes.len(), tree.node_count());
+        assert_eq!(nodes[0], (0, 0, &tree.layers[0][0]));
+        assert_eq!(nodes[5], (1, 0, &tree.layers[1][0]));
+        assert_eq!(*nodes.last().unwrap(), (3, 0, tree.root_bytes()));
+    }
+
+    #[test]
+    fn test_iter_layers_folds_into_the_next() {
+        let tree = MerkleTree::new((0..11).map(|i| i.to_string()).collect()).unwrap();
+        let views: Vec<LayerView> = tree.iter_layers().collect();
+        assert_eq!(views.len(), tree.depth() + 1);
+        assert!(views[0].is_leaf_layer && !views[0].is_root_layer);
+        assert_eq!(views[0].nodes, tree.leaves());
+
+        // Pairing up each layer with hash_pair (the odd one out with itself) gives the layer above
+        for pair in views.windows(2) {
+            let folded: Vec<[u8; 32]> = pair[0]
+                .nodes
+                .chunks(2)
+                .map(|chunk| hash_pair(&chunk[0], chunk.last().unwrap()))
+                .collect();
+            assert_eq!(folded, pair[1].nodes);
+            assert_eq!(pair[1].level, pair[0].level + 1);
+        }
+
+        // Top-down, the root comes first
+        let top = tree.iter_layers().next_back().unwrap();
+        assert!(top.is_root_layer && !top.is_leaf_layer);
+        assert_eq!(top.nodes, [*tree.root_bytes()]);
+        let levels: Vec<usize> = tree.iter_layers().rev().map(|view| view.level).collect();
+        assert_eq!(levels, [4, 3, 2, 1, 0]);
+
+        let single = MerkleTree::new(vec!["x".to_string()]).unwrap();
+        let only = single.iter_layers().next().unwrap();
+        assert!(only.is_leaf_layer && only.is_root_layer);
+    }
+
+    #[test]
+    fn test_find_leaf_index() {
+        let data: Vec<String> = (0..6).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        assert_eq!(tree.find_leaf_index(&tree.leaves()[3]), Some(3));
+        assert_eq!(tree.find_leaf_index(&hash_data(b"bogus")), None);
+        assert_eq!(tree.find_leaf_index_of(&"4".to_string()), Some(4));
+
+        // The item is hashed with the tree's own leaf hashing
+        let ct = MerkleTree::new_ct(vec!["a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(ct.find_leaf_index_of(&"b".to_string()), Some(1));
+        assert_eq!(ct.find_leaf_index(&hash_data(b"b")), None);
+    }
+
+    #[test]
+    fn test_contains_and_index_of() {
+        let data: Vec<String> = ["a", "b", "a", "c", "a"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut tree = MerkleTree::new(data).unwrap();
+        let (a, b, c, z) = (
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "z".to_string(),
+        );
+
+        // Present, absent, and a value held by three leaves
+        assert!(tree.contains(&b));
+        assert_eq!(tree.index_of(&c), Some(3));
+        assert!(!tree.contains(&z));
+        assert_eq!(tree.index_of(&z), None);
+        assert!(tree.indices_of(&z).is_empty());
+        assert_eq!(tree.index_of(&a), Some(0));
+        assert_eq!(tree.indices_of(&a), vec![0, 2, 4]);
+
+        // The index follows every kind of change to the leaves
+        tree.update_leaf(0, &z).unwrap();
+        assert_eq!(tree.indices_of(&a), vec![2, 4]);
+        assert_eq!(tree.index_of(&z), Some(0));
+        tree.push(b.clone());
+        tree.extend(vec![a.clone()]).unwrap();
+        assert_eq!(tree.indices_of(&b), vec![1, 5]);
+        assert_eq!(tree.indices_of(&a), vec![2, 4, 6]);
+        assert_eq!(tree.remove_leaf(1).unwrap(), b.hash());
+        assert_eq!(tree.indices_of(&a), vec![1, 3, 5]);
+        assert_eq!(tree.indices_of(&b), vec![4]);
+        tree.batch_update(vec![(1, c.clone()), (5, z.clone())])
+            .unwrap();
+        assert_eq!(tree.indices_of(&a), vec![3]);
+        assert_eq!(tree.indices_of(&c), vec![1, 2]);
+        assert_eq!(tree.indices_of(&z), vec![0, 5]);
+
+        // Same answers as a scan, including for a tree loaded from bytes
+        let reloaded = MerkleTree::<String>::from_bytes(&tree.to_bytes()).unwrap();
+        for value in [&a, &b, &c, &z] {
+            let scanned: Vec<usize> = (0..tree.leaf_count())
+                .filter(|&i| tree.leaves()[i] == value.hash())
+                .collect();
+            assert_eq!(tree.indices_of(value), scanned);
+            assert_eq!(reloaded.indices_of(value), scanned);
+        }
+        assert!(!MerkleTree::<String>::with_capacity(2).contains(&a));
+
+        // A clone of a searched tree builds its own index and answers the same
+        let cloned = tree.clone();
+        tree.push(c.clone());
+        assert_eq!(cloned.indices_of(&c), vec![1, 2]);
+        assert_eq!(tree.indices_of(&c), vec![1, 2, 6]);
+    }
+
+    // The lazy index must not cost `MerkleTree` its Sync in std builds
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_tree_is_sync() {
+        fn assert_sync<S: Sync>() {}
+        assert_sync::<MerkleTree<String>>();
+    }
+
+    #[test]
+    fn test_duplicated_last_leaf_is_detected() {
+        let honest_data: Vec<String> = ["A", "B", "C"].iter().map(|s| s.to_string()).collect();
+        let mut mutated_data = honest_data.clone();
+        mutated_data.push("C".to_string());
+
+        let honest = MerkleTree::new(honest_data).unwrap();
+        let mutated = MerkleTree::new(mutated_data).unwrap();
+        // Same root from different leaf lists
+        assert_eq!(honest.root_bytes(), mutated.root_bytes());
+
+        assert!(!honest.is_mutated());
+        assert!(mutated.is_mutated());
+        assert!(mutated.is_mutation_of(3));
+        assert!(!honest.is_mutation_of(3));
+        assert!(!honest.is_mutation_of(2));
+
+        // The extra leaf proves fine on its own, but not against the real tree size
+        let padding = PaddingStrategy::DuplicateSelf;
+        let extra = mutated.get_proof(3).unwrap();
+        assert!(extra.verify_against(&mutated.layers[0][3], honest.root_bytes()));
+        assert!(!extra.verify_for_size(&mutated.layers[0][3], honest.root_bytes(), 3, padding));
+
+        // Leaves that are in the real tree still verify
+        let real = mutated.get_proof(2).unwrap();
+        assert!(real.verify_for_size(&mutated.layers[0][2], honest.root_bytes(), 3, padding));
+
+        // Carrying the odd node up instead of duplicating it leaves nothing to mutate
+        let data: Vec<String> = ["A", "B", "C", "C"].iter().map(|s| s.to_string()).collect();
+        let promoted =
+            MerkleTree::<String>::with_padding(data, PaddingStrategy::PromoteOdd).unwrap();
+        assert!(!promoted.is_mutated());
+    }
+
+    #[test]
+    fn test_diff_with_finds_changed_leaf() {
+        let data: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        let ours = MerkleTree::new(data.clone()).unwrap();
+        let mut changed = data.clone();
+        changed[5] = "five".to_string();
+        let theirs = MerkleTree::new(changed).unwrap();
+
+        // The root, then one left child per level below it
+        assert_eq!(ours.diff_counting(&theirs), Ok((Some(5), 4)));
+        assert_eq!(ours.diff_with(&theirs), Ok(Some(5)));
+        assert_eq!(ours.diff_with(&ours.clone()), Ok(None));
+
+        // The lone last leaf of an odd tree is reached too
+        let odd: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let mut odd_changed = odd.clone();
+        odd_changed[6] = "six".to_string();
+        let odd_tree = MerkleTree::new(odd).unwrap();
+        assert_eq!(
+            odd_tree.diff_with(&MerkleTree::new(odd_changed).unwrap()),
+            Ok(Some(6))
+        );
+
+        let smaller = MerkleTree::new(data[..4].to_vec()).unwrap();
+        assert_eq!(
+            ours.diff_with(&smaller),
+            Err(MerkleError::LeafCountMismatch(8, 4))
+        );
+    }
+
+    #[test]
+    fn test_with_capacity_then_extend() {
+        let data: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+
+        let mut tree = MerkleTree::<String>::with_capacity(8);
+        assert_eq!(tree.leaf_count(), 0);
+        assert!(tree.layers.leaf_capacity() >= 8);
+        assert_eq!(*tree.root_bytes(), [0u8; 32]);
+        assert!(tree.get_proof(0).is_err());
+
+        // Only a hint: going past the capacity is fine
+        tree.extend(data[..3].to_vec()).unwrap();
+        tree.extend(data[3..].to_vec()).unwrap();
+        let expected = MerkleTree::new(data).unwrap();
+        assert_eq!(tree.layers, expected.layers);
+        assert_eq!(tree.root_bytes(), expected.root_bytes());
+    }
+
+    #[test]
+    fn test_layer_lengths_match_capacity_math() {
+        // Odd at several levels, so every rounding in the size math gets exercised
+        let leaf_count: usize = 100_003;
+        let tree: MerkleTree<u64> = MerkleTree::new((0..leaf_count as u64).collect()).unwrap();
+
+        // ceil(log2(n)) levels above the leaves
+        let levels = leaf_count.next_power_of_two().trailing_zeros() as usize;
+        assert_eq!(tree.layers.len(), levels + 1);
+        let mut expected = leaf_count;
+        for layer in tree.layers.iter() {
+            assert_eq!(layer.len(), expected);
+            expected = expected.div_ceil(2);
+        }
+        assert_eq!(tree.layers.last().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_layer_accessors() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data.clone()).unwrap();
+
+        assert_eq!(tree.layers().len(), tree.depth() + 1);
+        assert_eq!(tree.layer(0), Some(tree.leaves()));
+        assert_eq!(tree.layer(0).unwrap()[4], hash_data(b"4"));
+        assert_eq!(tree.layer(1).map(|layer| layer.len()), Some(3));
+        assert_eq!(tree.layer(4), None);
+        assert_eq!(tree.node(0, 2), Some(&hash_data(b"2")));
+        assert_eq!(tree.node(tree.depth(), 0), Some(tree.root_bytes()));
+        assert_eq!(tree.node(1, 3), None);
+        assert_eq!(tree.node(9, 0), None);
+
+        // into_layers hands back exactly what was built
+        let layers = tree.clone().into_layers();
+        assert_eq!(layers, tree.layers());
+        assert_eq!(layers[1][0], hash_pair(&hash_data(b"0"), &hash_data(b"1")));
+    }
+
+    #[test]
+    fn test_get_node_and_sibling_of() {
+        let leaves: Vec<[u8; 32]> = (0..8)
+            .map(|i| hash_data(i.to_string().as_bytes()))
+            .collect();
+        let tree = MerkleTree::new((0..8).map(|i| i.to_string()).collect()).unwrap();
+
+        // Work every internal node out by hand, level by level
+        let level1: Vec<[u8; 32]> = (0..4)
+            .map(|i| hash_pair(&leaves[2 * i], &leaves[2 * i + 1]))
+            .collect();
+        let level2: Vec<[u8; 32]> = (0..2)
+            .map(|i| hash_pair(&level1[2 * i], &level1[2 * i + 1]))
+            .collect();
+        let root = hash_pair(&level2[0], &level2[1]);
+
+        for (level, expected) in [&leaves, &level1, &level2, &vec![root]].iter().enumerate() {
+            for (index, hash) in expected.iter().enumerate() {
+                assert_eq!(tree.get_node(level, index), Ok(hash));
+                if level < 3 {
+                    assert_eq!(tree.sibling_of(level, index), Ok(&expected[index ^ 1]));
+                }
+            }
+        }
+
+        assert_eq!(
+            tree.get_node(4, 0),
+            Err(MerkleError::IndexOutOfBounds { index: 4, len: 4 })
+        );
+        assert_eq!(
+            tree.get_node(1, 4),
+            Err(MerkleError::IndexOutOfBounds { index: 4, len: 4 })
+        );
+        assert_eq!(
+            tree.sibling_of(3, 0),
+            Err(MerkleError::IndexOutOfBounds { index: 1, len: 1 })
+        );
+
+        // The lone third leaf is paired according to the padding
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let duplicated = MerkleTree::new(data.clone()).unwrap();
+        assert_eq!(duplicated.sibling_of(0, 2), Ok(&hash_data(b"C")));
+        let zero: MerkleTree<String> =
+            MerkleTree::with_padding(data.clone(), PaddingStrategy::FixedZeroHash).unwrap();
+        assert_eq!(zero.sibling_of(0, 2), Ok(&[0u8; 32]));
+        let promoted: MerkleTree<String> =
+            MerkleTree::with_padding(data, PaddingStrategy::PromoteOdd).unwrap();
+        assert!(promoted.sibling_of(0, 2).is_err());
+    }
+
+    #[test]
+    fn test_sorted_leaves_ignore_input_order() {
+        let ab = MerkleTree::new_sorted_leaves(vec!["A".to_string(), "B".to_string()]).unwrap();
+        let ba = MerkleTree::new_sorted_leaves(vec!["B".to_string(), "A".to_string()]).unwrap();
+        assert_eq!(ab.root_bytes(), ba.root_bytes());
+
+        let data: Vec<String> = (0..7).rev().map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new_sorted_leaves(data.clone()).unwrap();
+        assert!(tree.leaves().is_sorted());
+
+        // Proofs still work, once the item's sorted position is looked up
+        for item in &data {
+            let index = tree.find_leaf_index_of(item).unwrap();
+            let proof = tree.get_proof(index).unwrap();
+            assert!(proof.verify_against(&item.hash(), tree.root_bytes()));
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_try_from_layers_round_trip(data in proptest::collection::vec(".*", 1..40)) {
+            let tree = MerkleTree::new(data).unwrap();
+            let hex: Vec<Vec<String>> = tree
+                .layers()
+                .iter()
+                .map(|layer| layer.iter().map(|hash| to_hex(hash)).collect())
+                .collect();
+
+            let restored = MerkleTree::<String>::try_from(hex).unwrap();
+            proptest::prop_assert_eq!(restored.root_bytes(), tree.root_bytes());
+            proptest::prop_assert_eq!(restored.layers(), tree.layers());
+        }
+    }
+
+    #[test]
+    fn test_try_from_rejects_bad_layers() {
+        let tree =
+            MerkleTree::new(vec!["A".to_string(), "B".to_string(), "C".to_string()]).unwrap();
+        let mut hex: Vec<Vec<String>> = tree
+            .layers()
+            .iter()
+            .map(|layer| layer.iter().map(|hash| to_hex(hash)).collect())
+            .collect();
+
+        // An empty middle layer, a tampered parent and a missing root are all refused
+        let mut empty_middle = hex.clone();
+        empty_middle[1].clear();
+        assert!(matches!(
+            MerkleTree::<String>::try_from(empty_middle),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+
+        hex[1][0] = to_hex(&[0u8; 32]);
+        assert!(matches!(
+            MerkleTree::<String>::try_from(hex.clone()),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+
+        hex.truncate(2);
+        assert!(MerkleTree::<String>::try_from(hex).is_err());
+    }
+
+    #[test]
+    fn test_remove_leaf_matches_rebuild() {
+        let abc = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let mut tree = MerkleTree::new(abc).unwrap();
+        assert_eq!(tree.remove_leaf(1).unwrap(), "B".to_string().hash());
+
+        // C no longer needs to be duplicated: it is A's real sibling now
+        let ac = MerkleTree::new(vec!["A".to_string(), "C".to_string()]).unwrap();
+        assert_eq!(tree.layers, ac.layers);
+
+        // Every position and every padding, down to an empty tree
+        let paddings = [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+        ];
+        for padding in paddings {
+            let mut data: Vec<String> = (0..9).map(|i| i.to_string()).collect();
+            let mut tree: MerkleTree<String> =
+                MerkleTree::with_padding(data.clone(), padding).unwrap();
+            for index in [4, 0, 6, 2, 1, 3, 0, 1] {
+                tree.remove_leaf(index).unwrap();
+                data.remove(index);
+                let rebuilt: MerkleTree<String> =
+                    MerkleTree::with_padding(data.clone(), padding).unwrap();
+                assert_eq!(tree.layers, rebuilt.layers, "{:?}", padding);
+            }
+
+            tree.remove_leaf(0).unwrap();
+            assert_eq!(tree.leaf_count(), 0);
+            assert_eq!(tree.root_bytes(), &[0u8; 32]);
+            assert!(tree.remove_leaf(0).is_err());
+        }
+    }
+
+    #[test]
+    fn test_batch_update_matches_rebuild() {
+        let mut data: Vec<String> = (0..16).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(data.clone()).unwrap();
+
+        // Leaves 4 and 5 share a parent, and all four share the upper levels
+        let updates: Vec<(usize, String)> = [5, 4, 7, 12]
+            .iter()
+            .map(|&i| (i, format!("new-{}", i)))
+            .collect();
+        for (index, value) in &updates {
+            data[*index] = value.clone();
+        }
+
+        let recomputed = tree.batch_update_counting(updates).unwrap();
+        let rebuilt = MerkleTree::new(data.clone()).unwrap();
+        assert_eq!(tree.layers, rebuilt.layers);
+        // 3 + 2 + 2 + 1 parents instead of 4 * 4 with update_leaf
+        assert_eq!(recomputed, 8);
+
+        // Later duplicates win, and a bad index leaves the tree alone
+        tree.batch_update(vec![(0, "first".to_string()), (0, "second".to_string())])
+            .unwrap();
+        data[0] = "second".to_string();
+        assert_eq!(
+            tree.root_bytes(),
+            MerkleTree::new(data).unwrap().root_bytes()
+        );
+
+        let before = *tree.root_bytes();
+        assert_eq!(
+            tree.batch_update(vec![(1, "x".to_string()), (16, "y".to_string())]),
+            Err(MerkleError::IndexOutOfBounds { index: 16, len: 16 })
+        );
+        assert_eq!(tree.root_bytes(), &before);
+    }
+
+    #[test]
+    fn test_iterator_and_slice_constructors() {
+        let data: Vec<String> = (0..6).map(|i| i.to_string()).collect();
+        let expected = MerkleTree::new(data.clone()).unwrap();
+
+        let collected: MerkleTree<String> = (0..6).map(|i| i.to_string()).collect();
+        assert_eq!(collected.layers(), expected.layers());
+        let tried = MerkleTree::try_from_iter((0..6).map(|i| i.to_string())).unwrap();
+        assert_eq!(tried.root_bytes(), expected.root_bytes());
+        let borrowed = MerkleTree::from_slice(&data).unwrap();
+        assert_eq!(borrowed.layers(), expected.layers());
+        // data is still ours
+        assert_eq!(data.len(), 6);
+
+        assert_eq!(
+            MerkleTree::try_from_iter(Vec::<String>::new()),
+            Err(MerkleError::EmptyInput)
+        );
+        assert_eq!(
+            MerkleTree::<String>::from_slice(&[]),
+            Err(MerkleError::EmptyInput)
+        );
+        let mut empty: MerkleTree<String> = std::iter::empty().collect();
+        assert_eq!(empty.leaf_count(), 0);
+        assert_eq!(empty.root_bytes(), &[0u8; 32]);
+        assert_eq!(empty.root_hex(), "0".repeat(64));
+
+        // The sentinel root is replaced by a real one as soon as leaves arrive
+        empty.extend(data.clone()).unwrap();
+        assert_eq!(empty, expected);
+        assert_eq!(empty.layers(), expected.layers());
+        let mut via_trait: MerkleTree<String> = std::iter::empty().collect();
+        Extend::extend(&mut via_trait, data.clone());
+        assert_eq!(via_trait.layers(), expected.layers());
+    }
+
+    #[test]
+    fn test_try_from_lazy_iterator() {
+        // Nothing is collected up front: the leaves are hashed as the iterator yields them
+        let lazy = MerkleTree::try_from_iter(std::iter::repeat_n("x", 8)).unwrap();
+        let eager = MerkleTree::new(vec!["x".to_string(); 8]).unwrap();
+        assert_eq!(lazy.leaf_count(), 8);
+        assert_eq!(lazy.layers(), eager.layers());
+    }
+
+    #[test]
+    fn test_byte_leaves() {
+        let tree = MerkleTree::new(vec![b"hello".to_vec(), b"world".to_vec()]).unwrap();
+        let expected = hash_pair(
+            // SHA-256("hello") and SHA-256("world")
+            &from_hex("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824").unwrap(),
+            &from_hex("486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7").unwrap(),
+        );
+        assert_eq!(tree.root_bytes(), &expected);
+
+        // Slices, arrays and the equivalent strings all give the same leaves
+        let slices = MerkleTree::new(vec![&b"hello"[..], &b"world"[..]]).unwrap();
+        let arrays = MerkleTree::new(vec![*b"hello", *b"world"]).unwrap();
+        let strings = MerkleTree::new(vec!["hello".to_string(), "world".to_string()]).unwrap();
+        assert_eq!(slices.layers(), tree.layers());
+        assert_eq!(arrays.layers(), tree.layers());
+        assert_eq!(strings.layers(), tree.layers());
+    }
+
+    #[test]
+    fn test_integer_leaves_are_big_endian() {
+        assert_eq!(256u64.hash(), hash_data(&[0, 0, 0, 0, 0, 0, 1, 0]));
+        assert_eq!(256u32.hash(), hash_data(&[0, 0, 1, 0]));
+        assert_eq!(1u128.hash(), hash_data(&[&[0u8; 15][..], &[1]].concat()));
+        assert_eq!((-1i64).hash(), hash_data(&[0xff; 8]));
+
+        // Block heights straight into a tree
+        let tree = MerkleTree::new((0u64..5).collect()).unwrap();
+        assert_eq!(tree.leaves()[3], 3u64.to_be_bytes().hash());
+    }
+
+    #[test]
+    fn test_root_as_bytes_matches_decoded_hex() {
+        let tree =
+            MerkleTree::new(vec!["A".to_string(), "B".to_string(), "C".to_string()]).unwrap();
+        let root = tree.root_as_bytes();
+        assert_eq!(root, from_hex(&tree.root_hex()).unwrap());
+        assert_eq!(&root, tree.root_bytes());
+        assert_eq!(root[..4], [0x42, 0x09, 0x40, 0xee]);
+        assert_eq!(
+            MerkleTree::<String>::with_capacity(1).root_as_bytes(),
+            [0u8; 32]
+        );
+    }
+
+    #[test]
+    fn test_verify_inclusion_from_hex_alone() {
+        // A full tree, so no step is a node paired with itself (which would hash the same from either side)
+        let tree = MerkleTree::new((0..8).map(|i| i.to_string()).collect()).unwrap();
+        let root = tree.root_hex();
+
+        for i in 0..8 {
+            let proof = tree.get_proof(i).unwrap();
+            let leaf = to_hex(&proof.leaf_hash);
+            let steps: Vec<(String, ProofDirection)> = proof
+                .siblings
+                .iter()
+                .map(|node| (to_hex(&node.hash), node.direction))
+                .collect();
+            assert!(verify_inclusion(&root, &leaf, &steps));
+
+            // Changing any one step, its side, the leaf or the root makes it fail
+            for step in 0..steps.len() {
+                let mut changed = steps.clone();
+                let digit = if steps[step].0.starts_with('0') {
+                    "1"
+                } else {
+                    "0"
+                };
+                changed[step].0.replace_range(..1, digit);
+                assert!(!verify_inclusion(&root, &leaf, &changed));
+
+                let mut flipped = steps.clone();
+                flipped[step].1 = match flipped[step].1 {
+                    ProofDirection::Left => ProofDirection::Right,
+                    ProofDirection::Right => ProofDirection::Left,
+                };
+                assert!(!verify_inclusion(&root, &leaf, &flipped));
+            }
+            assert!(!verify_inclusion(&root, &to_hex(&[0u8; 32]), &steps));
+            assert!(!verify_inclusion(&to_hex(&[0u8; 32]), &leaf, &steps));
+        }
+
+        // Malformed hex fails instead of panicking
+        assert!(!verify_inclusion("zz", &root, &[]));
+        assert!(!verify_inclusion(
+            &root,
+            &root,
+            &[("12".to_string(), ProofDirection::Left)]
+        ));
+        assert!(verify_inclusion(&root, &root, &[]));
+    }
+
+    #[test]
+    fn test_clone_and_equality() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let tree = MerkleTree::new(data.clone()).unwrap();
+        let same = MerkleTree::new(data).unwrap();
+        let different = MerkleTree::new(vec!["A".to_string(), "B".to_string()]).unwrap();
+
+        assert_eq!(tree, same);
+        assert_ne!(tree, different);
+        assert_eq!(tree.clone(), tree);
+    }
+
+    // The leaf index is interior mutability to clippy, but keys only compare by root
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_trees_as_set_and_map_keys() {
+        let trees: Vec<MerkleTree<String>> = (1..6)
+            .map(|n| MerkleTree::new((0..n).map(|i| i.to_string()).collect()).unwrap())
+            .collect();
+
+        // Sorted by root, duplicates collapse, and lookups go by root
+        let set: BTreeSet<MerkleTree<String>> = trees.iter().chain(&trees).cloned().collect();
+        assert_eq!(set.len(), 5);
+        let roots: Vec<String> = set.iter().map(|tree| tree.root_hex()).collect();
+        assert!(roots.is_sorted());
+        assert!(set.contains(&MerkleTree::new(vec!["0".to_string()]).unwrap()));
+
+        let (low, high) = (set.first().unwrap(), set.last().unwrap());
+        assert!(low < high && high >= low);
+        assert_eq!(low.partial_cmp(high), Some(Ordering::Less));
+        assert_eq!(low.cmp(low), Ordering::Equal);
+
+        let mut versions = std::collections::HashMap::new();
+        for (version, tree) in trees.iter().enumerate() {
+            versions.insert(tree.clone(), version);
+        }
+        assert_eq!(versions[&trees[3]], 3);
+        assert_eq!(versions.len(), 5);
+    }
+
+    #[test]
+    fn test_from_hashes_matches_new() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        let from_bytes = MerkleTree::from_hashes(tree.leaves().to_vec()).unwrap();
+        assert_eq!(from_bytes.layers, tree.layers);
+
+        let hex: Vec<String> = tree.leaves().iter().map(|h| to_hex(h)).collect();
+        let from_hex_strings = MerkleTree::from_hex_hashes(hex).unwrap();
+        assert_eq!(from_hex_strings.root_bytes(), tree.root_bytes());
+
+        assert_eq!(
+            MerkleTree::from_hashes(vec![]),
+            Err(MerkleError::EmptyInput)
+        );
+        assert!(matches!(
+            MerkleTree::from_hex_hashes(vec!["abcd".to_string()]),
+            Err(MerkleError::HashDecodingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_hex_hashes_of_abc() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let hex: Vec<String> = data.iter().map(|item| to_hex(&item.hash())).collect();
+
+        let tree = MerkleTree::new(data).unwrap();
+        let from_hex_strings = MerkleTree::from_hex_hashes(hex.clone()).unwrap();
+        assert_eq!(from_hex_strings.layers, tree.layers);
+
+        // The error names the entry that failed to decode
+        let mut bad = hex;
+        bad[2].replace_range(0..2, "zz");
+        assert_eq!(
+            MerkleTree::from_hex_hashes(bad),
+            Err(MerkleError::HashDecodingError(
+                "leaf 2: invalid hex pair: zz".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_depth_and_counts() {
+        let four = MerkleTree::new((0..4).map(|i| i.to_string()).collect()).unwrap();
+        assert_eq!(four.depth(), 2);
+        assert_eq!(four.leaf_count(), 4);
+        assert_eq!(four.node_count(), 7);
+
+        // 3 leaves -> [3, 2, 1]
+        let three = MerkleTree::new((0..3).map(|i| i.to_string()).collect()).unwrap();
+        assert_eq!(three.depth(), 2);
+        assert_eq!(three.leaf_count(), 3);
+        assert_eq!(three.node_count(), 6);
+    }
+
+    // Raw byte leaves for the RFC 6962 vectors, hashed with H's leaf hashing
+    struct RawLeaf(Vec<u8>);
+
+    impl Hashable for RawLeaf {
+        fn hash(&self) -> [u8; 32] {
+            self.hash_with::<Sha256Hasher>()
+        }
+
+        fn hash_with<H: MerkleHasher>(&self) -> [u8; 32] {
+            H::hash_leaf(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_rfc6962_reference_vectors() {
+        // Inputs and roots from the Certificate Transparency reference implementation's test suite
+        let inputs = [
+            "",
+            "00",
+            "10",
+            "2021",
+            "3031",
+            "40414243",
+            "5051525354555657",
+            "606162636465666768696a6b6c6d6e6f",
+        ];
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+
+        for size in 1..=8 {
+            let leaves: Vec<RawLeaf> = inputs[..size]
+                .iter()
+                .map(|hex| {
+                    // Decode the short hex inputs two characters at a time
+                    let bytes = (0..hex.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                        .collect();
+                    RawLeaf(bytes)
+                })
+                .collect();
+            let tree = MerkleTree::new_ct(leaves).unwrap();
+            assert_eq!(tree.root_hex(), roots[size - 1], "tree of size {}", size);
+            assert_eq!(tree.padding(), PaddingStrategy::PromoteOdd);
+
+            // Promoted nodes contribute no proof step, and every proof must still verify
+            for i in 0..size {
+                let proof = tree.get_proof(i).unwrap();
+                assert!(
+                    proof.verify_against_with::<DomainSeparated>(
+                        &tree.layers[0][i],
+                        tree.root_bytes()
+                    )
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rfc6962_style_updates_and_multiproofs() {
+        let mut data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::<String>::with_style(data.clone(), TreeStyle::Rfc6962).unwrap();
+        assert_ne!(
+            tree.root_bytes(),
+            MerkleTree::new(data.clone()).unwrap().root_bytes()
+        );
+
+        // Leaf 6 is carried up past the bottom layer without a sibling, so its proof is one step shorter
+        assert_eq!(tree.get_proof(6).unwrap().siblings.len(), 2);
+        assert_eq!(tree.get_proof(0).unwrap().siblings.len(), 3);
+
+        tree.update_leaf(6, &"changed".to_string()).unwrap();
+        data[6] = "changed".to_string();
+        let rebuilt = MerkleTree::<String>::with_style(data, TreeStyle::Rfc6962).unwrap();
+        assert_eq!(tree.layers, rebuilt.layers);
+
+        let proof = tree.generate_multiproof(&[1, 6]).unwrap();
+        let leaves = [tree.layers[0][1], tree.layers[0][6]];
+        assert!(verify_multiproof(tree.root_bytes(), &leaves, &proof));
+    }
+
+    #[test]
+    fn test_padding_strategies_give_distinct_roots_and_own_proofs() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let strategies = [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+            PaddingStrategy::Custom(hash_data(b"padding")),
+        ];
+        let trees: Vec<_> = strategies
+            .iter()
+            .map(|&padding| MerkleTree::<String>::with_padding(data.clone(), padding).unwrap())
+            .collect();
+
+        // Every pair of strategies gives a different root, and each tree remembers its strategy
+        for (i, tree) in trees.iter().enumerate() {
+            assert_eq!(tree.padding(), strategies[i]);
+            for other in &trees[i + 1..] {
+                assert_ne!(tree.root_bytes(), other.root_bytes());
+            }
+        }
+        assert_eq!(trees[0], MerkleTree::new(data).unwrap());
+
+        for (tree, &own) in trees.iter().zip(&strategies) {
+            for i in 0..5 {
+                let proof = tree.get_proof(i).unwrap();
+                assert!(proof.verify_padded(&tree.layers[0][i], tree.root_bytes(), own));
+            }
+
+            // Leaf 4 is the odd node out on every layer, so its proof only fits its own strategy
+            let proof = tree.get_proof(4).unwrap();
+            for &padding in strategies.iter().filter(|&&padding| padding != own) {
+                assert!(!proof.verify_padded(&tree.layers[0][4], tree.root_bytes(), padding));
+            }
+        }
+    }
+
+    #[test]
+    fn test_odd_leaf_strategies_on_three_leaves() {
+        let data = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let strategies = [
+            OddLeafStrategy::Duplicate,
+            OddLeafStrategy::Promote,
+            OddLeafStrategy::ZeroPad,
+        ];
+        let trees: Vec<_> = strategies
+            .iter()
+            .map(|&strategy| MerkleTree::new_with_options(data.clone(), strategy).unwrap())
+            .collect();
+        assert_ne!(trees[0].root_bytes(), trees[1].root_bytes());
+        assert_ne!(trees[1].root_bytes(), trees[2].root_bytes());
+        assert_ne!(trees[0].root_bytes(), trees[2].root_bytes());
+        assert_eq!(trees[0], MerkleTree::new(data).unwrap());
+
+        // The right half of the root is C paired with itself, C on its own, or C paired with zeros
+        let c = trees[0].layers[0][2];
+        assert_eq!(trees[0].layers[1][1], hash_pair(&c, &c));
+        assert_eq!(trees[1].layers[1][1], c);
+        assert_eq!(trees[2].layers[1][1], hash_pair(&c, &[0u8; 32]));
+
+        for (tree, &own) in trees.iter().zip(&strategies) {
+            assert_eq!(tree.padding(), own.into());
+            let proof = tree.get_proof(2).unwrap();
+            assert!(proof.verify_padded(&c, tree.root_bytes(), own.into()));
+            for &other in strategies.iter().filter(|&&other| other != own) {
+                assert!(!proof.verify_padded(&c, tree.root_bytes(), other.into()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_and_debug_summaries() {
+        let tree =
+            MerkleTree::new(vec!["A".to_string(), "B".to_string(), "C".to_string()]).unwrap();
+        assert_eq!(
+            tree.to_string(),
+            "MerkleTree: 3 leaves, depth 2, 6 nodes, SHA-256, root 420940ee…054df8c6"
+        );
+        let expected = "\
+MerkleTree: 3 leaves, depth 2, 6 nodes, SHA-256, root 420940ee…054df8c6
+Level 2:           420940ee…
+Level 1:      63956f0c…      98f95730…
+Level 0: 559aead0… df7e70e5… 6b23c0d5…";
+        assert_eq!(format!("{:#}", tree), expected);
+        assert_eq!(
+            format!("{:?}", tree),
+            "MerkleTree { leaf_count: 3, depth: 2, node_count: 6, hasher: \"SHA-256\", \
+             padding: DuplicateSelf, root: \"420940ee1c7a73de80cfa2554efb4e6cec7ea745fed73108ccb06886054df8c6\" }"
+        );
+
+        // Past 16 leaves `{:#}` keeps to the summary line
+        let big = MerkleTree::new_ct((0..17).map(|i| i.to_string()).collect()).unwrap();
+        let summary = format!("{:#}", big);
+        assert!(
+            summary
+                .starts_with("MerkleTree: 17 leaves, depth 5, 37 nodes, SHA-256 (RFC 6962), root ")
+        );
+        assert!(!summary.contains('\n'));
+    }
+
+    #[test]
+    fn test_display_draws_layers_root_first() {
+        let data = vec![
+            "A".to_string(),
+            "B".to_string(),
+            "C".to_string(),
+            "D".to_string(),
+        ];
+        let tree = MerkleTree::new(data).unwrap();
+        let expected = "\
+MerkleTree: 4 leaves, depth 2, 7 nodes, SHA-256, root 1b3faa3f…f9b13617
+Level 2:                1b3faa3f…
+Level 1:      63956f0c…           98a2fbfd…
+Level 0: 559aead0… df7e70e5… 6b23c0d5… 3f39d5c3…";
+        assert_eq!(format!("{:#}", tree), expected);
+        assert!(tree.root_hex().starts_with("1b3faa3f"));
+    }
+
+    #[test]
+    fn test_sorted_pairs_ignore_sibling_sides() {
+        let data: Vec<String> = (0..6).map(|i| i.to_string()).collect();
+        let sorted = MerkleTree::<String, SortedPairs>::with_hasher(data.clone()).unwrap();
+        let plain = MerkleTree::new(data).unwrap();
+        assert_eq!(sorted.leaves(), plain.leaves());
+        assert_ne!(sorted.root_bytes(), plain.root_bytes());
+
+        // Flip every sibling to the other side of the pair
+        let flip = |proof: &MerkleProof| {
+            let mut flipped = proof.clone();
+            for node in &mut flipped.siblings {
+                node.direction = match node.direction {
+                    ProofDirection::Left => ProofDirection::Right,
+                    ProofDirection::Right => ProofDirection::Left,
+                };
+            }
+            flipped
+        };
+
+        for i in 0..6 {
+            let proof = sorted.get_proof(i).unwrap();
+            assert!(flip(&proof).verify_with::<SortedPairs>());
+
+            // A bare list of hashes is all a sorted verifier needs
+            assert!(verify_sorted_proof(
+                sorted.root_bytes(),
+                &sorted.layers[0][i],
+                &proof.sibling_hashes()
+            ));
+
+            // The default pairing cares about sides, so the flipped proof no longer fits
+            let proof = plain.get_proof(i).unwrap();
+            assert!(proof.verify());
+            assert!(!flip(&proof).verify());
+        }
+    }
+
+    #[test]
+    fn test_commutative_tree_ignores_order_within_pairs() {
+        let (a, b) = (hash_data(b"a"), hash_data(b"b"));
+        assert_eq!(hash_pair_sorted(&a, &b), hash_pair_sorted(&b, &a));
+        assert_ne!(hash_pair(&a, &b), hash_pair(&b, &a));
+
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new_commutative(data.clone()).unwrap();
+        assert_eq!(
+            tree.root_bytes(),
+            MerkleTree::<String, SortedPairs>::with_hasher(data.clone())
+                .unwrap()
+                .root_bytes()
+        );
+
+        // Swapping the two leaves of any pair leaves every parent, and so the root, unchanged
+        for pair in [0, 2, 4] {
+            let mut swapped = data.clone();
+            swapped.swap(pair, pair + 1);
+            let other = MerkleTree::new_commutative(swapped).unwrap();
+            assert_eq!(other.root_bytes(), tree.root_bytes());
+            assert_ne!(other.leaves(), tree.leaves());
+        }
+
+        for i in 0..7 {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(verify_sorted_proof(
+                tree.root_bytes(),
+                &tree.leaves()[i],
+                &proof.sibling_hashes()
+            ));
+        }
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    fn test_new_sorted_matches_openzeppelin_hashing() {
+        use sha3::{Digest, Keccak256};
+
+        let data: Vec<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+        let tree = MerkleTree::new_sorted(data.clone()).unwrap();
+
+        // Recompute by hand: keccak256(keccak256(leaf)), then keccak256(min || max) for every pair
+        let leaf =
+            |s: &str| -> [u8; 32] { Keccak256::digest(Keccak256::digest(s.as_bytes())).into() };
+        let pair = |a: [u8; 32], b: [u8; 32]| -> [u8; 32] {
+            let (low, high) = if a <= b { (a, b) } else { (b, a) };
+            Keccak256::digest([low, high].concat()).into()
+        };
+        let expected = pair(pair(leaf("a"), leaf("b")), pair(leaf("c"), leaf("d")));
+        assert_eq!(*tree.root_bytes(), expected);
+
+        // StandardMerkleTree sorts its leaves by hash first. With four leaves in that order,
+        // its array layout groups the same pairs, so the roots agree
+        let encoded = data
+            .iter()
+            .map(|s| (vec![s.clone()], s.as_bytes().to_vec()))
+            .collect();
+        let standard =
+            StandardMerkleTree::from_encoded(encoded, vec!["bytes".to_string()]).unwrap();
+        let mut by_hash = data.clone();
+        by_hash.sort_by_key(|s| leaf(s));
+        let in_hash_order = MerkleTree::new_sorted(by_hash).unwrap();
+        assert_eq!(in_hash_order.root_bytes(), standard.root_bytes());
+
+        // The bare sibling hashes are what Solidity's MerkleProof.verify takes
+        for i in 0..4 {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(proof.verify_with::<OpenZeppelinHasher>());
+            let hashes: Vec<[u8; 32]> = proof.siblings.iter().map(|node| node.hash).collect();
+            assert!(verify_sorted_proof_with::<Keccak256Hasher>(
+                tree.root_bytes(),
+                &tree.layers[0][i],
+                &hashes
+            ));
+            assert_eq!(standard.get_proof(i).unwrap().len(), hashes.len());
+        }
+    }
+}
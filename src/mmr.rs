@@ -0,0 +1,421 @@
+// Merkle Mountain Ranges: an append-only forest of perfect trees stored as one flat list of nodes.
+// Positions follow the usual MMR numbering (post-order, 0-based), so they never move as the range grows.
+//
+//        6
+//      /   \
+//     2     5     9
+//    / \   / \   / \
+//   0   1 3   4 7   8  10
+
+use crate::{Hashable, MerkleError, MerkleHasher, Sha256Hasher, hash_pair_with, to_hex};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A Merkle Mountain Range. `append` never re-hashes a node that is already stored: it pushes the leaf
+/// and then one parent for every perfect tree it completes (O(1) amortized).
+///
+/// The root "bags" the peaks from right to left: `bag = H(peak, bag)`, starting from the rightmost peak.
+/// Unlike `MerkleAccumulator` this is not the RFC 6962 tree hash; it is the layout used by MMR-based chains.
+#[derive(Debug)]
+pub struct MmrAccumulator<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    // Every node ever created, indexed by MMR position.
+    nodes: Vec<[u8; 32]>,
+    // Positions of the peaks, leftmost (highest) first.
+    peaks: Vec<usize>,
+    leaf_count: usize,
+    // Marker to link the range to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+/// Proof that a leaf is part of an MMR of a given size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmrProof {
+    /// The leaf's MMR position (not its leaf index; see `MmrAccumulator::leaf_position`).
+    pub position: usize,
+    /// Siblings on the way from the leaf up to the peak of its mountain, bottom first.
+    pub siblings: Vec<[u8; 32]>,
+    /// The other peaks of the range, left to right, without the one the leaf is under.
+    pub peaks: Vec<[u8; 32]>,
+}
+
+impl<T: Hashable> MmrAccumulator<T> {
+    /// Creates an empty SHA-256 range.
+    pub fn new() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Default for MmrAccumulator<T, H> {
+    fn default() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MmrAccumulator<T, H> {
+    /// Creates an empty range that combines hashes with `H`.
+    pub fn with_hasher() -> Self {
+        MmrAccumulator {
+            nodes: Vec::new(),
+            peaks: Vec::new(),
+            leaf_count: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Number of leaves appended so far.
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// True until the first `append`.
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Number of nodes stored, which is what proofs are verified against.
+    pub fn mmr_size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Adds a leaf at the end and returns its MMR position.
+    pub fn append(&mut self, item: T) -> usize {
+        let position = self.nodes.len();
+        self.nodes.push(item.hash_with::<H>());
+        self.peaks.push(position);
+        self.leaf_count += 1;
+
+        // One merge per trailing zero of the new leaf count: each completes a mountain of the next height
+        for _ in 0..self.leaf_count.trailing_zeros() {
+            let right = self.peaks.pop().unwrap();
+            let left = self.peaks.pop().unwrap();
+            let parent = hash_pair_with::<H>(&self.nodes[left], &self.nodes[right]);
+            self.peaks.push(self.nodes.len());
+            self.nodes.push(parent);
+        }
+
+        position
+    }
+
+    /// The peak hashes, leftmost (highest mountain) first.
+    pub fn peaks(&self) -> Vec<[u8; 32]> {
+        self.peaks
+            .iter()
+            .map(|&position| self.nodes[position])
+            .collect()
+    }
+
+    /// The bagged peaks (all zeros while the range is empty).
+    pub fn root(&self) -> [u8; 32] {
+        bag_peaks::<H>(&self.peaks()).unwrap_or([0u8; 32])
+    }
+
+    /// Returns the root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(&self.root())
+    }
+
+    /// The MMR position of the leaf with index `leaf_index` (the n-th appended leaf is at 2n - popcount(n)).
+    /// None if that position doesn't fit in a usize, i.e. for leaf indices past roughly `usize::MAX / 2`.
+    pub fn leaf_position(leaf_index: usize) -> Option<usize> {
+        // n + (n - popcount(n)), so the subtraction can't underflow and only the addition can overflow
+        leaf_index.checked_add(leaf_index - leaf_index.count_ones() as usize)
+    }
+
+    /// Proves that the leaf with index `leaf_index` is in the range at its current size.
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MmrProof, MerkleError> {
+        if leaf_index >= self.leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: self.leaf_count,
+            });
+        }
+
+        // 1. Climb from the leaf to the peak of its mountain, collecting siblings
+        let position =
+            Self::leaf_position(leaf_index).expect("positions of stored leaves can't overflow");
+        let mut siblings = Vec::new();
+        let mut current = position;
+        let mut height = 0;
+        while !self.peaks.contains(&current) {
+            let (sibling, parent) =
+                family(current, height).expect("positions of stored nodes can't overflow");
+            siblings.push(self.nodes[sibling]);
+            current = parent;
+            height += 1;
+        }
+
+        // 2. Every other peak is needed to bag the root
+        let peaks = self
+            .peaks
+            .iter()
+            .filter(|&&peak| peak != current)
+            .map(|&peak| self.nodes[peak])
+            .collect();
+
+        Ok(MmrProof {
+            position,
+            siblings,
+            peaks,
+        })
+    }
+}
+
+impl MmrProof {
+    /// True if the proof shows `leaf_hash` is at `position` in the SHA-256 MMR of `mmr_size` nodes with `root`.
+    pub fn verify(&self, leaf_hash: &[u8; 32], mmr_size: usize, root: &[u8; 32]) -> bool {
+        self.verify_with::<Sha256Hasher>(leaf_hash, mmr_size, root)
+    }
+
+    /// Same as `verify`, for ranges built with a hasher other than SHA-256.
+    pub fn verify_with<H: MerkleHasher>(
+        &self,
+        leaf_hash: &[u8; 32],
+        mmr_size: usize,
+        root: &[u8; 32],
+    ) -> bool {
+        let Some(peak_positions) = peak_positions(mmr_size) else {
+            return false;
+        };
+        // No mountain in a range of this size can be taller than the whole range
+        if self.position >= mmr_size
+            || node_height(self.position) != Some(0)
+            || self.siblings.len() >= usize::BITS as usize - 1
+        {
+            return false;
+        }
+
+        // 1. Rebuild the leaf's mountain peak; the position decides which side each sibling is on
+        let mut current = *leaf_hash;
+        let mut position = self.position;
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            // Positions near usize::MAX have no room for their family, so no range holds them
+            let Some((sibling_position, parent)) = family(position, height as u32) else {
+                return false;
+            };
+            current = if sibling_position < position {
+                hash_pair_with::<H>(sibling, &current)
+            } else {
+                hash_pair_with::<H>(&current, sibling)
+            };
+            position = parent;
+        }
+
+        // 2. The climb has to end exactly on a peak, with every other peak supplied
+        let Some(mountain) = peak_positions.iter().position(|&peak| peak == position) else {
+            return false;
+        };
+        if self.peaks.len() + 1 != peak_positions.len() {
+            return false;
+        }
+
+        let mut peaks = self.peaks.clone();
+        peaks.insert(mountain, current);
+        bag_peaks::<H>(&peaks).is_some_and(|bagged| bagged == *root)
+    }
+}
+
+// Folds the peaks from the right: bag = H(peak, bag). None if there are no peaks.
+fn bag_peaks<H: MerkleHasher>(peaks: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let (last, rest) = peaks.split_last()?;
+    Some(
+        rest.iter()
+            .rev()
+            .fold(*last, |bag, peak| hash_pair_with::<H>(peak, &bag)),
+    )
+}
+
+// Height of the node at `position` (0 for leaves), or None for usize::MAX. Positions of a perfect tree's peak
+// are all-ones when counted from 1; jumping left past the biggest perfect tree keeps the height the same.
+fn node_height(position: usize) -> Option<u32> {
+    let mut one_based = position.checked_add(1)?;
+    // Written without `+ 1` so an all-ones usize doesn't overflow
+    while one_based & one_based.wrapping_add(1) != 0 {
+        one_based -= (1 << one_based.ilog2()) - 1;
+    }
+    Some(one_based.ilog2())
+}
+
+// (sibling, parent) of the node at `position` with the given height, or None if either would be past usize::MAX.
+// A node is a right child exactly when the next position is higher than it.
+fn family(position: usize, height: u32) -> Option<(usize, usize)> {
+    let mountain = (2usize << height) - 1;
+    if node_height(position.checked_add(1)?)? > height {
+        Some((position.checked_sub(mountain)?, position + 1))
+    } else {
+        let sibling = position.checked_add(mountain)?;
+        Some((sibling, sibling.checked_add(1)?))
+    }
+}
+
+// Peak positions of an MMR with `mmr_size` nodes, left to right, or None if no MMR has that many nodes.
+fn peak_positions(mmr_size: usize) -> Option<Vec<usize>> {
+    let mut peaks = Vec::new();
+    let mut start = 0;
+    let mut last_height = u32::MAX;
+    while start < mmr_size {
+        // The biggest perfect tree (2^(h+1) - 1 nodes) that still fits. usize::MAX nodes would be a single
+        // mountain of 2^63 leaves, more than any range can hold, and would overflow here
+        let height = (mmr_size - start).checked_add(1)?.ilog2() - 1;
+        // Mountains have to shrink strictly, otherwise the two would have been merged
+        if height >= last_height {
+            return None;
+        }
+        start += (2usize << height) - 1;
+        peaks.push(start - 1);
+        last_height = height;
+    }
+    Some(peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainSeparated, hash_data, hash_pair};
+    use alloc::format;
+
+    // Reference root: one perfect tree per set bit of the leaf count, biggest first, then the peaks bagged
+    fn reference_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        fn perfect(leaves: &[[u8; 32]]) -> [u8; 32] {
+            match leaves {
+                [leaf] => *leaf,
+                _ => {
+                    let (left, right) = leaves.split_at(leaves.len() / 2);
+                    hash_pair(&perfect(left), &perfect(right))
+                }
+            }
+        }
+
+        let mut peaks = Vec::new();
+        let mut start = 0;
+        for bit in (0..usize::BITS).rev() {
+            let size = 1usize << bit;
+            if leaves.len() & size != 0 {
+                peaks.push(perfect(&leaves[start..start + size]));
+                start += size;
+            }
+        }
+        bag_peaks::<Sha256Hasher>(&peaks).unwrap()
+    }
+
+    #[test]
+    fn test_small_sizes_match_reference() {
+        let sizes = [1, 3, 4, 7, 8, 10, 11, 15, 16, 18, 19];
+        let mut mmr = MmrAccumulator::new();
+        let mut leaves = Vec::new();
+
+        for (i, &expected_size) in sizes.iter().enumerate() {
+            let item = format!("leaf-{}", i);
+            leaves.push(hash_data(item.as_bytes()));
+            assert_eq!(
+                mmr.append(item),
+                MmrAccumulator::<String>::leaf_position(i).unwrap()
+            );
+            assert_eq!(mmr.mmr_size(), expected_size);
+            assert_eq!(mmr.root(), reference_root(&leaves), "{} leaves", i + 1);
+        }
+
+        // By hand for three leaves: peaks H(l0, l1) and l2, bagged right to left
+        let mut three = MmrAccumulator::new();
+        for item in ["a", "b", "c"] {
+            three.append(item.to_string());
+        }
+        let [a, b, c] = [b"a", b"b", b"c"].map(|bytes| hash_data(bytes));
+        assert_eq!(three.peaks(), vec![hash_pair(&a, &b), c]);
+        assert_eq!(three.root(), hash_pair(&hash_pair(&a, &b), &c));
+
+        assert_eq!(MmrAccumulator::<String>::new().root(), [0u8; 32]);
+    }
+
+    #[test]
+    fn test_proofs_over_hundreds_of_leaves() {
+        let mut mmr = MmrAccumulator::<String, DomainSeparated>::with_hasher();
+        for i in 0..300 {
+            mmr.append(format!("entry-{}", i));
+        }
+        let root = mmr.root();
+        let size = mmr.mmr_size();
+
+        // A spread of positions, including the first and last leaf
+        for leaf_index in (0..300).map(|i| (i * 37) % 300).chain([0, 299]) {
+            let leaf = format!("entry-{}", leaf_index).hash_with::<DomainSeparated>();
+            let proof = mmr.get_proof(leaf_index).unwrap();
+            assert!(proof.verify_with::<DomainSeparated>(&leaf, size, &root));
+
+            // Wrong leaf, wrong size, wrong hasher
+            assert!(!proof.verify_with::<DomainSeparated>(&[0u8; 32], size, &root));
+            assert!(!proof.verify_with::<DomainSeparated>(&leaf, size - 1, &root));
+            assert!(!proof.verify(&leaf, size, &root));
+        }
+
+        assert!(mmr.get_proof(300).is_err());
+    }
+
+    #[test]
+    fn test_tampered_proofs_fail() {
+        let mut mmr = MmrAccumulator::new();
+        for i in 0..11 {
+            mmr.append(i.to_string());
+        }
+        let root = mmr.root();
+        let leaf = hash_data(b"5");
+        let proof = mmr.get_proof(5).unwrap();
+        assert!(proof.verify(&leaf, mmr.mmr_size(), &root));
+
+        let mut moved = proof.clone();
+        moved.position = MmrAccumulator::<String>::leaf_position(4).unwrap();
+        assert!(!moved.verify(&leaf, mmr.mmr_size(), &root));
+
+        let mut short = proof.clone();
+        short.siblings.pop();
+        assert!(!short.verify(&leaf, mmr.mmr_size(), &root));
+
+        let mut extra_peak = proof.clone();
+        extra_peak.peaks.push([0u8; 32]);
+        assert!(!extra_peak.verify(&leaf, mmr.mmr_size(), &root));
+
+        // A node count no MMR can have (two leaves without their parent)
+        assert_eq!(peak_positions(2), None);
+        assert_eq!(peak_positions(19), Some(vec![14, 17, 18]));
+    }
+
+    #[test]
+    fn test_sizes_and_positions_near_usize_max() {
+        let mut mmr = MmrAccumulator::new();
+        for i in 0..7 {
+            mmr.append(i.to_string());
+        }
+        let root = mmr.root();
+        let leaf = hash_data(b"3");
+        let proof = mmr.get_proof(3).unwrap();
+        assert!(proof.verify(&leaf, mmr.mmr_size(), &root));
+
+        // The size comes from the verifier, so a huge one has to fail instead of overflowing
+        assert_eq!(peak_positions(usize::MAX), None);
+        assert!(!proof.verify(&leaf, usize::MAX, &root));
+        assert!(!proof.verify(&leaf, usize::MAX - 1, &root));
+
+        // So does a leaf position at the very end of the address space
+        for position in [usize::MAX - 2, usize::MAX - 1, usize::MAX] {
+            let mut far = proof.clone();
+            far.position = position;
+            assert!(!far.verify(&leaf, usize::MAX - 1, &root));
+            assert!(!far.verify(&leaf, usize::MAX, &root));
+        }
+        assert_eq!(node_height(usize::MAX), None);
+        assert_eq!(family(usize::MAX - 1, 0), None);
+
+        // Leaf positions are about twice the leaf index, so the upper half of the indices has none
+        let half = usize::MAX / 2;
+        assert_eq!(
+            MmrAccumulator::<String>::leaf_position(half),
+            Some(2 * half - half.count_ones() as usize)
+        );
+        assert_eq!(
+            MmrAccumulator::<String>::leaf_position(half + 1),
+            Some(usize::MAX)
+        );
+        assert_eq!(MmrAccumulator::<String>::leaf_position(half + 2), None);
+        assert_eq!(MmrAccumulator::<String>::leaf_position(usize::MAX), None);
+    }
+}
@@ -0,0 +1,204 @@
+// Multiproofs: prove many leaves at once while sharing the upper-level nodes between them.
+
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleTree, PaddingStrategy, Sha256Hasher, hash_pair_with,
+    lone_parent,
+};
+use alloc::vec::Vec;
+
+/// A single proof covering several leaves.
+/// Only the nodes a verifier cannot work out from the leaves themselves are included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof {
+    /// Sorted, deduplicated positions of the proven leaves.
+    pub leaf_indices: Vec<usize>,
+    /// Number of leaves in the whole tree, needed to know where the odd layers are.
+    pub leaf_count: usize,
+    /// How the tree padded its odd layers.
+    pub padding: PaddingStrategy,
+    /// The extra sibling hashes, in the order the verifier consumes them (bottom layer first, left to right).
+    pub hashes: Vec<[u8; 32]>,
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Builds one compact proof for every leaf in `indices`.
+    /// Indices are sorted and deduplicated first; an empty list is an error.
+    pub fn generate_multiproof(&self, indices: &[usize]) -> Result<MultiProof, MerkleError> {
+        if indices.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        let leaf_count = self.layers[0].len();
+        let mut known = indices.to_vec();
+        known.sort_unstable();
+        known.dedup();
+        if let Some(&last) = known.last()
+            && last >= leaf_count
+        {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: last,
+                len: leaf_count,
+            });
+        }
+
+        let leaf_indices = known.clone();
+        let mut hashes = Vec::new();
+
+        // Climb every layer except the root, keeping track of which nodes the verifier will already know
+        for layer in self.layers.iter().take(self.depth()) {
+            let mut parents = Vec::new();
+            let mut i = 0;
+
+            while i < known.len() {
+                let index = known[i];
+                if index.is_multiple_of(2) {
+                    if known.get(i + 1) == Some(&(index + 1)) {
+                        // Both children are known, nothing to send
+                        i += 1;
+                    } else if index + 1 < layer.len() {
+                        hashes.push(layer[index + 1]);
+                    }
+                    // Otherwise this node was the odd one out, which the verifier can redo on its own
+                } else {
+                    // The left sibling would have been handled above if it were known
+                    hashes.push(layer[index - 1]);
+                }
+
+                parents.push(index / 2);
+                i += 1;
+            }
+
+            known = parents;
+        }
+
+        Ok(MultiProof {
+            leaf_indices,
+            leaf_count,
+            padding: self.padding(),
+            hashes,
+        })
+    }
+}
+
+/// Rebuilds the root from `leaves` (one hash per entry in `proof.leaf_indices`) and the proof's shared nodes.
+pub fn verify_multiproof(root: &[u8; 32], leaves: &[[u8; 32]], proof: &MultiProof) -> bool {
+    verify_multiproof_with::<Sha256Hasher>(root, leaves, proof)
+}
+
+/// Same as `verify_multiproof`, for trees built with a hasher other than SHA-256.
+pub fn verify_multiproof_with<H: MerkleHasher>(
+    root: &[u8; 32],
+    leaves: &[[u8; 32]],
+    proof: &MultiProof,
+) -> bool {
+    if leaves.is_empty() || leaves.len() != proof.leaf_indices.len() {
+        return false;
+    }
+    // The indices must be in the same sorted, unique, in-range form generate_multiproof produces
+    if proof.leaf_indices.windows(2).any(|w| w[0] >= w[1])
+        || *proof.leaf_indices.last().unwrap() >= proof.leaf_count
+    {
+        return false;
+    }
+
+    let mut current: Vec<(usize, [u8; 32])> = proof
+        .leaf_indices
+        .iter()
+        .copied()
+        .zip(leaves.iter().copied())
+        .collect();
+    let mut extra = proof.hashes.iter();
+    let mut layer_len = proof.leaf_count;
+
+    // Walk the layers exactly the way generate_multiproof did
+    while layer_len > 1 {
+        let mut parents = Vec::new();
+        let mut i = 0;
+
+        while i < current.len() {
+            let (index, hash) = current[i];
+            let parent = if index.is_multiple_of(2) {
+                if current.get(i + 1).map(|(next, _)| *next) == Some(index + 1) {
+                    i += 1;
+                    hash_pair_with::<H>(&hash, &current[i].1)
+                } else if index + 1 < layer_len {
+                    match extra.next() {
+                        Some(sibling) => hash_pair_with::<H>(&hash, sibling),
+                        None => return false,
+                    }
+                } else {
+                    lone_parent::<H>(&hash, proof.padding)
+                }
+            } else {
+                match extra.next() {
+                    Some(sibling) => hash_pair_with::<H>(sibling, &hash),
+                    None => return false,
+                }
+            };
+
+            parents.push((index / 2, parent));
+            i += 1;
+        }
+
+        current = parents;
+        layer_len = layer_len.div_ceil(2);
+    }
+
+    // Every shared node must have been used, and we must land on the root
+    extra.next().is_none() && current.len() == 1 && current[0].1 == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_data;
+
+    fn numbered_tree(count: usize) -> MerkleTree<String> {
+        MerkleTree::new((0..count).map(|i| i.to_string()).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_multiproof_round_trip() {
+        let tree = numbered_tree(11);
+        // Unsorted with a duplicate, and index 10 sits on an odd layer
+        let indices = [7, 2, 10, 3, 2];
+        let proof = tree.generate_multiproof(&indices).unwrap();
+        assert_eq!(proof.leaf_indices, vec![2, 3, 7, 10]);
+
+        let leaves: Vec<_> = proof
+            .leaf_indices
+            .iter()
+            .map(|&i| tree.layers[0][i])
+            .collect();
+        assert!(verify_multiproof(tree.root_bytes(), &leaves, &proof));
+
+        let mut wrong = leaves.clone();
+        wrong[1] = hash_data(b"not a leaf");
+        assert!(!verify_multiproof(tree.root_bytes(), &wrong, &proof));
+    }
+
+    #[test]
+    fn test_multiproof_is_smaller_than_individual_proofs() {
+        let tree = numbered_tree(16);
+        let indices = [4, 5, 6, 7];
+        let multi = tree.generate_multiproof(&indices).unwrap();
+        let individual: usize = indices
+            .iter()
+            .map(|&i| tree.get_proof(i).unwrap().siblings.len())
+            .sum();
+
+        // 4 proofs x 4 levels = 16 hashes, but one aligned block of 4 only needs the 2 nodes above it
+        assert_eq!(individual, 16);
+        assert_eq!(multi.hashes.len(), 2);
+    }
+
+    #[test]
+    fn test_multiproof_rejects_bad_indices() {
+        let tree = numbered_tree(4);
+        assert_eq!(tree.generate_multiproof(&[]), Err(MerkleError::EmptyInput));
+        assert_eq!(
+            tree.generate_multiproof(&[1, 4]),
+            Err(MerkleError::IndexOutOfBounds { index: 4, len: 4 })
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// Parallel construction, enabled with the `rayon` feature (or its alias `parallel`).
+// Each layer only depends on the one below it, so every layer can be hashed across all cores.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use rayon::prelude::*;
+
+use crate::arena::Layers;
+use crate::leaf_index::LeafIndex;
+use crate::{Hashable, MerkleError, MerkleHasher, MerkleTree, PaddingStrategy, chunk_parent};
+
+impl<T: Hashable + Send + Sync> MerkleTree<T> {
+    /// Parallel version of `MerkleTree::new`. Produces exactly the same layers.
+    pub fn par_new(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::par_with_hasher(data)
+    }
+}
+
+impl<T: Hashable + Send + Sync, H: MerkleHasher> MerkleTree<T, H> {
+    /// Parallel version of `MerkleTree::with_hasher`. Produces exactly the same layers.
+    pub fn par_with_hasher(data: Vec<T>) -> Result<Self, MerkleError> {
+        Self::par_with_padding(data, PaddingStrategy::default())
+    }
+
+    /// Parallel version of `MerkleTree::with_padding`. Produces exactly the same layers.
+    pub fn par_with_padding(data: Vec<T>, padding: PaddingStrategy) -> Result<Self, MerkleError> {
+        if data.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        // 1. Hash every leaf in parallel, straight into the arena (par_iter keeps the original order)
+        let mut layers = Layers::for_leaves(data.len());
+        layers[0]
+            .par_iter_mut()
+            .zip(data.par_iter())
+            .for_each(|(slot, item)| *slot = item.hash_with::<H>());
+
+        // 2. Same funnel as the sequential version, but each layer's pairs are hashed in parallel
+        for level in 0..layers.len() - 1 {
+            let (below, above) = layers.split_level_mut(level);
+            above
+                .par_iter_mut()
+                .zip(below.par_chunks(2))
+                .for_each(|(slot, chunk)| *slot = chunk_parent::<H>(chunk, padding));
+        }
+
+        Ok(MerkleTree {
+            layers,
+            padding,
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MerkleTree;
+
+    #[test]
+    fn test_par_new_matches_sequential() {
+        for count in [1, 2, 7, 1000, 1025] {
+            let data: Vec<String> = (0..count).map(|i| i.to_string()).collect();
+            let sequential = MerkleTree::new(data.clone()).unwrap();
+            let parallel = MerkleTree::par_new(data).unwrap();
+            assert_eq!(parallel.layers, sequential.layers);
+        }
+    }
+
+    #[test]
+    fn test_large_tree_matches_for_every_padding() {
+        use crate::PaddingStrategy;
+
+        let data: Vec<String> = (0..100_000).map(|i| i.to_string()).collect();
+        for padding in [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+        ] {
+            let sequential: MerkleTree<String> =
+                MerkleTree::with_padding(data.clone(), padding).unwrap();
+            let parallel: MerkleTree<String> =
+                MerkleTree::par_with_padding(data.clone(), padding).unwrap();
+            assert_eq!(parallel.root_bytes(), sequential.root_bytes());
+            assert_eq!(parallel.padding(), padding);
+        }
+    }
+
+    #[test]
+    fn test_par_new_empty_fails() {
+        let data: Vec<String> = vec![];
+        assert!(MerkleTree::par_new(data).is_err());
+    }
+}
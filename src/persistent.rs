@@ -0,0 +1,353 @@
+// Persistent (copy-on-write) trees: an update returns a new version and leaves the old one untouched.
+// Versions share every subtree the update didn't reach, so each extra version only costs O(log n) new nodes.
+
+use crate::arena::Layers;
+use crate::leaf_index::LeafIndex;
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleProof, MerkleTree, PaddingStrategy, ProofDirection,
+    ProofNode, Sha256Hasher, hash_pair_with, lone_parent, padding_sibling, to_hex,
+};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+// One node of a persistent tree. A branch without a right child was the odd node out of its layer.
+#[derive(Debug)]
+enum Node {
+    Leaf([u8; 32]),
+    Branch {
+        hash: [u8; 32],
+        left: Arc<Node>,
+        right: Option<Arc<Node>>,
+    },
+}
+
+impl Node {
+    fn hash(&self) -> &[u8; 32] {
+        match self {
+            Node::Leaf(hash) => hash,
+            Node::Branch { hash, .. } => hash,
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Arc<Node>> {
+        let (left, right) = match self {
+            Node::Branch { left, right, .. } => (Some(left), right.as_ref()),
+            Node::Leaf(_) => (None, None),
+        };
+        left.into_iter().chain(right)
+    }
+}
+
+/// An immutable Merkle Tree made of `Arc`-linked nodes, for keeping many versions of a large tree in memory.
+/// `with_updated_leaf` returns a new version that shares all untouched subtrees with the old one,
+/// so only the path from the changed leaf to the root is allocated again.
+///
+/// It holds exactly the hashes a `MerkleTree` with the same leaves and padding would, and its roots and
+/// proofs are identical. Convert with `MerkleTree::to_persistent` and `to_tree`.
+#[derive(Debug)]
+pub struct PersistentMerkleTree<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    root: Arc<Node>,
+    leaf_count: usize,
+    // Levels above the leaves, same as `MerkleTree::depth`.
+    depth: usize,
+    padding: PaddingStrategy,
+    // Marker to link the tree to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+// Cloning a version only bumps the root's reference count.
+impl<T: Hashable, H: MerkleHasher> Clone for PersistentMerkleTree<T, H> {
+    fn clone(&self) -> Self {
+        PersistentMerkleTree {
+            root: Arc::clone(&self.root),
+            leaf_count: self.leaf_count,
+            depth: self.depth,
+            padding: self.padding,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Copies this tree into a `PersistentMerkleTree`, reusing the stored hashes (nothing is re-hashed).
+    /// This copy is the one O(n) step: keep the result and call `with_updated_leaf` on it for every new version.
+    /// Returns an Error for an empty `with_capacity` tree.
+    pub fn to_persistent(&self) -> Result<PersistentMerkleTree<T, H>, MerkleError> {
+        if self.layers[0].is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        // 1. Wrap every leaf, then link each layer's hashes to the pair of nodes below them
+        let mut nodes: Vec<Arc<Node>> = self.layers[0]
+            .iter()
+            .map(|hash| Arc::new(Node::Leaf(*hash)))
+            .collect();
+        for layer in self.layers.iter().skip(1) {
+            nodes = layer
+                .iter()
+                .zip(nodes.chunks(2))
+                .map(|(hash, children)| {
+                    Arc::new(Node::Branch {
+                        hash: *hash,
+                        left: Arc::clone(&children[0]),
+                        right: children.get(1).cloned(),
+                    })
+                })
+                .collect();
+        }
+
+        // 2. Only the root is left
+        Ok(PersistentMerkleTree {
+            root: Arc::clone(&nodes[0]),
+            leaf_count: self.layers[0].len(),
+            depth: self.layers.len() - 1,
+            padding: self.padding,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> PersistentMerkleTree<T, H> {
+    /// Returns a new version with the leaf at `index` replaced by `new_data`'s hash.
+    /// `self` is left as it was; the two versions share everything except the O(log n) nodes on the leaf's path.
+    pub fn with_updated_leaf(&self, index: usize, new_data: &T) -> Result<Self, MerkleError> {
+        if index >= self.leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index,
+                len: self.leaf_count,
+            });
+        }
+
+        let leaf = new_data.hash_with::<H>();
+        Ok(PersistentMerkleTree {
+            root: self.updated(&self.root, self.depth, index, leaf),
+            leaf_count: self.leaf_count,
+            depth: self.depth,
+            padding: self.padding,
+            _marker: PhantomData,
+        })
+    }
+
+    // Copies the path from `node` (at `level`) down to leaf `index`, pointing everything else at the old nodes.
+    fn updated(&self, node: &Arc<Node>, level: usize, index: usize, leaf: [u8; 32]) -> Arc<Node> {
+        let Node::Branch { left, right, .. } = &**node else {
+            return Arc::new(Node::Leaf(leaf));
+        };
+
+        // The leaf is under the right child if its bit for this level is set
+        let (left, right) = if (index >> (level - 1)) & 1 == 1 {
+            let right = right
+                .as_ref()
+                .map(|right| self.updated(right, level - 1, index, leaf));
+            (Arc::clone(left), right)
+        } else {
+            (self.updated(left, level - 1, index, leaf), right.clone())
+        };
+
+        let hash = match &right {
+            Some(right) => hash_pair_with::<H>(left.hash(), right.hash()),
+            None => lone_parent::<H>(left.hash(), self.padding),
+        };
+        Arc::new(Node::Branch { hash, left, right })
+    }
+
+    /// The raw 32-byte Merkle Root.
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        self.root.hash()
+    }
+
+    /// Returns the Merkle Root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(self.root_bytes())
+    }
+
+    /// How many leaves the tree was built from.
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// How many levels sit above the leaves.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// How odd layers were padded.
+    pub fn padding(&self) -> PaddingStrategy {
+        self.padding
+    }
+
+    /// The hash at position `index` of `level` (0 = leaves), or None if there is no such node.
+    /// Same coordinates as `MerkleTree::node`; this walks down from the root (O(log n)).
+    pub fn node(&self, level: usize, index: usize) -> Option<&[u8; 32]> {
+        if level > self.depth || index >> (self.depth - level) != 0 {
+            return None;
+        }
+
+        let mut node = &self.root;
+        for current in (level + 1..=self.depth).rev() {
+            let Node::Branch { left, right, .. } = &**node else {
+                return None;
+            };
+            node = if (index >> (current - 1 - level)) & 1 == 1 {
+                right.as_ref()?
+            } else {
+                left
+            };
+        }
+        Some(node.hash())
+    }
+
+    /// Same as `MerkleTree::get_proof`: the proofs of both trees are identical.
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        if leaf_index >= self.leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: self.leaf_count,
+            });
+        }
+
+        // Walk down from the root, collecting the sibling at each level; the proof wants them bottom-up
+        let mut siblings = Vec::new();
+        let mut node = &self.root;
+        for level in (1..=self.depth).rev() {
+            let Node::Branch { left, right, .. } = &**node else {
+                break;
+            };
+
+            if (leaf_index >> (level - 1)) & 1 == 1 {
+                siblings.push(ProofNode {
+                    direction: ProofDirection::Left,
+                    hash: *left.hash(),
+                });
+                // Only a branch with a right child can have the leaf on its right
+                node = right.as_ref().unwrap_or(left);
+            } else {
+                // No right node: the left one was padded, or carried up with no step at all
+                let sibling = match right {
+                    Some(right) => Some(*right.hash()),
+                    None => padding_sibling(left.hash(), self.padding),
+                };
+                if let Some(hash) = sibling {
+                    siblings.push(ProofNode {
+                        direction: ProofDirection::Right,
+                        hash,
+                    });
+                }
+                node = left;
+            }
+        }
+        siblings.reverse();
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count: self.leaf_count,
+            leaf_hash: *node.hash(),
+            siblings,
+            root: *self.root_bytes(),
+        })
+    }
+
+    /// Copies this version back into a plain `MerkleTree` (O(n)), e.g. to use `update_leaf` or serialize it.
+    pub fn to_tree(&self) -> MerkleTree<T, H> {
+        // Read the nodes level by level from the root down, then flip to leaves-first
+        let mut layers = Vec::with_capacity(self.depth + 1);
+        let mut level = vec![&self.root];
+        while !level.is_empty() {
+            layers.push(level.iter().map(|node| *node.hash()).collect());
+            // Leaves have no children, so this stops after the leaf layer
+            level = level.iter().flat_map(|node| node.children()).collect();
+        }
+        layers.reverse();
+
+        MerkleTree {
+            layers: Layers::from_vecs(layers),
+            padding: self.padding,
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_mutable_updates() {
+        let paddings = [
+            PaddingStrategy::DuplicateSelf,
+            PaddingStrategy::PromoteOdd,
+            PaddingStrategy::FixedZeroHash,
+        ];
+
+        for padding in paddings {
+            let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+            let mut mutable: MerkleTree<String> = MerkleTree::with_padding(data, padding).unwrap();
+            let mut version = mutable.to_persistent().unwrap();
+
+            for index in [6, 0, 3] {
+                let value = format!("v{}", index);
+                mutable.update_leaf(index, &value).unwrap();
+                version = version.with_updated_leaf(index, &value).unwrap();
+
+                assert_eq!(version.root_bytes(), mutable.root_bytes());
+                assert_eq!(version.to_tree().layers, mutable.layers, "{:?}", padding);
+                for leaf in 0..7 {
+                    assert_eq!(version.get_proof(leaf), mutable.get_proof(leaf));
+                }
+                for (level, layer) in mutable.layers.iter().enumerate() {
+                    for (i, hash) in layer.iter().enumerate() {
+                        assert_eq!(version.node(level, i), Some(hash));
+                    }
+                    assert_eq!(version.node(level, layer.len()), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_versions_share_untouched_subtrees() {
+        let tree = MerkleTree::new((0..8).map(|i| i.to_string()).collect()).unwrap();
+        let old = tree.to_persistent().unwrap();
+        let new = old.with_updated_leaf(0, &"changed".to_string()).unwrap();
+
+        // The old version is unchanged
+        assert_eq!(old.root_bytes(), tree.root_bytes());
+        assert_ne!(new.root_bytes(), old.root_bytes());
+
+        let children = |node: &Arc<Node>| match &**node {
+            Node::Branch { left, right, .. } => (Arc::clone(left), right.clone().unwrap()),
+            Node::Leaf(_) => panic!("expected a branch"),
+        };
+
+        // Leaf 0 lives under root.left.left.left, so every right child on that path is shared
+        let (old_left, old_right) = children(&old.root);
+        let (new_left, new_right) = children(&new.root);
+        assert!(Arc::ptr_eq(&old_right, &new_right));
+        assert!(!Arc::ptr_eq(&old_left, &new_left));
+
+        let (old_ll, old_lr) = children(&old_left);
+        let (new_ll, new_lr) = children(&new_left);
+        assert!(Arc::ptr_eq(&old_lr, &new_lr));
+        assert!(Arc::ptr_eq(&children(&old_ll).1, &children(&new_ll).1));
+    }
+
+    #[test]
+    fn test_persistent_errors() {
+        let tree =
+            MerkleTree::new(vec!["A".to_string(), "B".to_string(), "C".to_string()]).unwrap();
+        let version = tree.to_persistent().unwrap();
+        assert_eq!(
+            version.with_updated_leaf(3, &"x".to_string()).err(),
+            Some(MerkleError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+        assert!(version.get_proof(3).is_err());
+        assert_eq!(
+            MerkleTree::<String>::with_capacity(4).to_persistent().err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+}
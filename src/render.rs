@@ -0,0 +1,176 @@
+// ASCII drawings of whole trees, for teaching and debugging: every node with the children it was hashed from,
+// root at the top, like the output of `tree`. A padding sibling is marked with `*`: it is what the lone node was
+// paired with (itself, zeros or the custom hash) and is not stored in the tree. A promoted node has one child.
+
+use crate::{Hashable, MerkleHasher, MerkleTree, padding_sibling, to_hex};
+use alloc::format;
+use alloc::string::String;
+
+/// Settings for `MerkleTree::render_ascii`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Hex characters shown per hash, from 1 to 64. Defaults to 8.
+    pub hash_chars: usize,
+    /// Trees with more nodes than this are summarized instead of drawn. Defaults to 127,
+    /// a tree of 64 leaves.
+    pub max_nodes: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            hash_chars: 8,
+            max_nodes: 127,
+        }
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Draws the tree top-down, one node per line, e.g. for three leaves with the default padding:
+    ///
+    /// ```text
+    /// 420940ee
+    /// ├── 63956f0c
+    /// │   ├── 559aead0  leaf 0
+    /// │   └── df7e70e5  leaf 1
+    /// └── 98f95730
+    ///     ├── 6b23c0d5  leaf 2
+    ///     └── 6b23c0d5* padding
+    /// ```
+    ///
+    /// A tree with more than `opts.max_nodes` nodes gives its `Display` summary and a note instead.
+    pub fn render_ascii(&self, opts: RenderOptions) -> String {
+        if self.node_count() > opts.max_nodes {
+            return format!(
+                "{}\n({} nodes is over the limit of {}, not drawn)",
+                self,
+                self.node_count(),
+                opts.max_nodes
+            );
+        }
+        if self.leaf_count() == 0 {
+            return String::from("(empty tree)");
+        }
+
+        let chars = opts.hash_chars.clamp(1, 64);
+        let mut out = String::new();
+        out.push_str(&to_hex(self.root_bytes())[..chars]);
+        out.push('\n');
+        self.render_children(self.depth(), 0, "", chars, &mut out);
+        // Every line ends in '\n'; the last one doesn't need to
+        out.pop();
+        out
+    }
+
+    // Appends the children of node `index` on `level`, each under `prefix`, and their children in turn.
+    fn render_children(
+        &self,
+        level: usize,
+        index: usize,
+        prefix: &str,
+        chars: usize,
+        out: &mut String,
+    ) {
+        if level == 0 {
+            return;
+        }
+
+        // 1. The stored children, plus the padding sibling when the left child was the odd node out
+        let below = &self.layers[level - 1];
+        let left = 2 * index;
+        let stored = if left + 1 < below.len() { 2 } else { 1 };
+        let padding = if stored == 1 {
+            padding_sibling(&below[left], self.padding)
+        } else {
+            None
+        };
+        let total = stored + usize::from(padding.is_some());
+
+        // 2. One line per child, then its own subtree behind the vertical bar if more siblings follow
+        for child in 0..total {
+            let last = child + 1 == total;
+            out.push_str(prefix);
+            out.push_str(if last { "└── " } else { "├── " });
+            if child < stored {
+                let position = left + child;
+                out.push_str(&to_hex(&below[position])[..chars]);
+                if level == 1 {
+                    out.push_str(&format!("  leaf {}", position));
+                }
+                out.push('\n');
+                let deeper = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.render_children(level - 1, position, &deeper, chars, out);
+            } else if let Some(sibling) = padding {
+                out.push_str(&to_hex(&sibling)[..chars]);
+                out.push_str("* padding\n");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PaddingStrategy;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_render_three_leaves() {
+        let tree =
+            MerkleTree::new(vec!["A".to_string(), "B".to_string(), "C".to_string()]).unwrap();
+        let expected = "\
+420940ee
+├── 63956f0c
+│   ├── 559aead0  leaf 0
+│   └── df7e70e5  leaf 1
+└── 98f95730
+    ├── 6b23c0d5  leaf 2
+    └── 6b23c0d5* padding";
+        assert_eq!(tree.render_ascii(RenderOptions::default()), expected);
+
+        // Shorter hashes, and a promoted node that keeps its only child
+        let promoted = MerkleTree::<String>::with_padding(
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            PaddingStrategy::PromoteOdd,
+        )
+        .unwrap();
+        let opts = RenderOptions {
+            hash_chars: 4,
+            ..RenderOptions::default()
+        };
+        let drawing = promoted.render_ascii(opts);
+        assert!(
+            drawing.ends_with("└── 6b23\n    └── 6b23  leaf 2"),
+            "{}",
+            drawing
+        );
+        assert!(!drawing.contains('*'));
+    }
+
+    #[test]
+    fn test_render_limits_and_padding_nodes_deeper_up() {
+        let tree = MerkleTree::new((0..5).map(|i| i.to_string()).collect()).unwrap();
+        let drawing = tree.render_ascii(RenderOptions::default());
+        // One line per stored node plus one per padding sibling (5 and 3 nodes are odd)
+        assert_eq!(drawing.lines().count(), tree.node_count() + 2);
+        assert_eq!(drawing.matches("* padding").count(), 2);
+        let leaves: Vec<&str> = drawing
+            .lines()
+            .filter(|line| line.contains("leaf"))
+            .collect();
+        assert_eq!(leaves.len(), 5);
+
+        // Over the limit only the summary comes back
+        let big = MerkleTree::new((0..100).map(|i| i.to_string()).collect()).unwrap();
+        let summary = big.render_ascii(RenderOptions::default());
+        assert_eq!(summary.lines().count(), 2);
+        assert!(summary.ends_with("(202 nodes is over the limit of 127, not drawn)"));
+
+        assert_eq!(
+            MerkleTree::<String>::with_capacity(4).render_ascii(RenderOptions::default()),
+            "(empty tree)"
+        );
+    }
+}
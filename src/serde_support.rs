@@ -0,0 +1,563 @@
+// Serde support, enabled with the `serde` feature.
+// Only the computed hashes are persisted, so neither `T` nor `H` has to be serializable.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::wire::hasher_id;
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleProof, MerkleTree, PaddingStrategy, ProofDirection,
+    ProofNode, Sha256Hasher, from_hex, to_hex,
+};
+
+// The on-disk shape: {"layers": [["<hex>", ...], ...], "padding": "DuplicateSelf"}
+// `padding` is optional when loading so older checkpoints still work.
+#[derive(Serialize, Deserialize)]
+struct TreeRepr {
+    layers: Vec<Vec<String>>,
+    #[serde(default)]
+    padding: PaddingStrategy,
+}
+
+impl<T: Hashable, H: MerkleHasher> Serialize for MerkleTree<T, H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.iter().map(|hash| to_hex(hash)).collect())
+            .collect();
+        TreeRepr {
+            layers,
+            padding: self.padding(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Hashable, H: MerkleHasher> Deserialize<'de> for MerkleTree<T, H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Go through from_layers so a tampered checkpoint never turns into a tree
+        let repr = TreeRepr::deserialize(deserializer)?;
+        // An empty `with_capacity` tree serializes as a single empty leaf layer, so that is the one empty shape accepted
+        if let [leaves] = repr.layers.as_slice()
+            && leaves.is_empty()
+        {
+            let mut tree = MerkleTree::with_capacity(0);
+            tree.padding = repr.padding;
+            return Ok(tree);
+        }
+        MerkleTree::from_layers_with_padding(repr.layers, repr.padding).map_err(D::Error::custom)
+    }
+}
+
+// One step of a merkletreejs proof: {"position": "left" | "right", "data": "0x<hex>"}
+#[derive(Serialize, Deserialize)]
+struct JsProofStep {
+    position: JsPosition,
+    data: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JsPosition {
+    Left,
+    Right,
+}
+
+// A proof as the OpenZeppelin merkle-tree tooling passes it around: {"proof": ["0x<hex>", ...], "leaf": "0x<hex>"}
+#[derive(Serialize, Deserialize)]
+struct OzProof {
+    proof: Vec<String>,
+    leaf: String,
+}
+
+// The versioned proof document, for proofs that are stored or handed to other parties:
+// {"version": 1, "hasher": "<8 hex>", "leaf_index": 3, "leaf_count": 7, "leaf_hash": "<hex>",
+//  "siblings": [{"direction": "left" | "right", "hash": "<hex>"}, ...], "root": "<hex>"}
+// `hasher` is the same 4-byte fingerprint the binary tree format uses. `root` and `leaf_count` may be left out,
+// and fields a later version adds are skipped, so old readers keep working as long as `version` stays 1.
+const PROOF_DOC_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct DocVersion {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProofDoc {
+    version: u32,
+    hasher: String,
+    leaf_index: usize,
+    #[serde(default)]
+    leaf_count: usize,
+    leaf_hash: String,
+    siblings: Vec<DocStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root: Option<String>,
+}
+
+// Sides are written the merkletreejs way, "left" or "right".
+#[derive(Serialize, Deserialize)]
+struct DocStep {
+    direction: JsPosition,
+    hash: String,
+}
+
+// A hash written as hex, with or without a leading `0x`.
+fn from_prefixed_hex(hex: &str) -> Result<[u8; 32], MerkleError> {
+    from_hex(hex.strip_prefix("0x").unwrap_or(hex))
+}
+
+impl MerkleProof {
+    /// The siblings in the shape merkletreejs uses: `[{"position": "left", "data": "0x..."}, ...]`.
+    pub fn to_merkletreejs_json(&self) -> String {
+        let steps: Vec<JsProofStep> = self
+            .siblings
+            .iter()
+            .map(|node| JsProofStep {
+                position: match node.direction {
+                    ProofDirection::Left => JsPosition::Left,
+                    ProofDirection::Right => JsPosition::Right,
+                },
+                data: format!("0x{}", to_hex(&node.hash)),
+            })
+            .collect();
+        // A Vec of plain structs always serializes
+        serde_json::to_string(&steps).unwrap()
+    }
+
+    /// Reads a merkletreejs proof for `leaf_hash` in the tree with `root`. The `0x` prefix is optional.
+    /// merkletreejs doesn't send the tree size, so `leaf_count` is left at 0 and `verify_padded` won't accept the proof;
+    /// `leaf_index` is worked out from the positions, which is exact unless the tree carried odd nodes up.
+    pub fn from_merkletreejs_json(
+        json: &str,
+        leaf_hash: [u8; 32],
+        root: [u8; 32],
+    ) -> Result<Self, MerkleError> {
+        let steps: Vec<JsProofStep> =
+            serde_json::from_str(json).map_err(|e| MerkleError::InvalidProof(e.to_string()))?;
+
+        let mut siblings = Vec::with_capacity(steps.len());
+        let mut leaf_index = 0;
+        for (level, step) in steps.iter().enumerate() {
+            let direction = match step.position {
+                JsPosition::Left => ProofDirection::Left,
+                JsPosition::Right => ProofDirection::Right,
+            };
+            // A sibling on the left means we were the right child at this level
+            if direction == ProofDirection::Left && level < usize::BITS as usize {
+                leaf_index |= 1 << level;
+            }
+            siblings.push(ProofNode {
+                direction,
+                hash: from_prefixed_hex(&step.data)?,
+            });
+        }
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count: 0,
+            leaf_hash,
+            siblings,
+            root,
+        })
+    }
+
+    /// The proof as a version 1 proof document (see the top of this file) for a SHA-256 tree, root included.
+    /// Use this rather than the serde derive for anything stored or sent elsewhere: the layout is fixed per version.
+    pub fn to_json(&self) -> String {
+        self.to_json_with::<Sha256Hasher>()
+    }
+
+    /// Same as `to_json`, for trees built with a hasher other than SHA-256.
+    pub fn to_json_with<H: MerkleHasher>(&self) -> String {
+        let doc = ProofDoc {
+            version: PROOF_DOC_VERSION,
+            hasher: to_hex(&hasher_id::<H>()),
+            leaf_index: self.leaf_index,
+            leaf_count: self.leaf_count,
+            leaf_hash: to_hex(&self.leaf_hash),
+            siblings: self
+                .siblings
+                .iter()
+                .map(|node| DocStep {
+                    direction: match node.direction {
+                        ProofDirection::Left => JsPosition::Left,
+                        ProofDirection::Right => JsPosition::Right,
+                    },
+                    hash: to_hex(&node.hash),
+                })
+                .collect(),
+            root: Some(to_hex(&self.root)),
+        };
+        // Plain strings and numbers always serialize
+        serde_json::to_string(&doc).unwrap()
+    }
+
+    /// Reads a proof document written by `to_json`. Returns `MerkleError::UnsupportedVersion` for any version but 1,
+    /// and `InvalidProof` if the JSON is malformed or the proof was made with another hasher.
+    /// A document without a root comes back with `root` zeroed; set it before calling `verify`.
+    pub fn from_json(json: &str) -> Result<Self, MerkleError> {
+        Self::from_json_with::<Sha256Hasher>(json)
+    }
+
+    /// Same as `from_json`, for trees built with a hasher other than SHA-256.
+    pub fn from_json_with<H: MerkleHasher>(json: &str) -> Result<Self, MerkleError> {
+        // 1. Look at the version alone first, since a later version may change every other field
+        let version: DocVersion =
+            serde_json::from_str(json).map_err(|e| MerkleError::InvalidProof(e.to_string()))?;
+        if version.version != PROOF_DOC_VERSION {
+            return Err(MerkleError::UnsupportedVersion(version.version));
+        }
+
+        // 2. Then the version 1 fields, which must be for this hasher
+        let doc: ProofDoc =
+            serde_json::from_str(json).map_err(|e| MerkleError::InvalidProof(e.to_string()))?;
+        if doc.hasher != to_hex(&hasher_id::<H>()) {
+            return Err(MerkleError::InvalidProof(format!(
+                "proof was made with hasher {}, expected {}",
+                doc.hasher,
+                to_hex(&hasher_id::<H>())
+            )));
+        }
+
+        let siblings = doc
+            .siblings
+            .iter()
+            .map(|step| {
+                Ok(ProofNode {
+                    direction: match step.direction {
+                        JsPosition::Left => ProofDirection::Left,
+                        JsPosition::Right => ProofDirection::Right,
+                    },
+                    hash: from_hex(&step.hash)?,
+                })
+            })
+            .collect::<Result<Vec<_>, MerkleError>>()?;
+
+        Ok(MerkleProof {
+            leaf_index: doc.leaf_index,
+            leaf_count: doc.leaf_count,
+            leaf_hash: from_hex(&doc.leaf_hash)?,
+            siblings,
+            root: doc
+                .root
+                .as_deref()
+                .map(from_hex)
+                .transpose()?
+                .unwrap_or([0u8; 32]),
+        })
+    }
+
+    /// The proof in the shape of OpenZeppelin's `@openzeppelin/merkle-tree`: `{"proof": ["0x...", ...], "leaf": "0x..."}`.
+    /// Only the sibling hashes are written, so this is meant for trees built with `OpenZeppelinHasher`
+    /// (or any `SortedPairs` hasher), where the side of each sibling doesn't matter.
+    pub fn to_oz_json(&self) -> String {
+        let proof = OzProof {
+            proof: self
+                .siblings
+                .iter()
+                .map(|node| format!("0x{}", to_hex(&node.hash)))
+                .collect(),
+            leaf: format!("0x{}", to_hex(&self.leaf_hash)),
+        };
+        // Plain strings always serialize
+        serde_json::to_string(&proof).unwrap()
+    }
+
+    /// Reads a proof written by `to_oz_json` or the OpenZeppelin JS tooling. The `0x` prefix is optional.
+    /// The format carries no root, position or sides: `root` is left zeroed, `leaf_index` and `leaf_count` at 0,
+    /// and every sibling goes on the right. Set `root` to the root you expect, then check with `verify_with::<OpenZeppelinHasher>()`.
+    pub fn from_oz_json(json: &str) -> Result<Self, MerkleError> {
+        let parsed: OzProof =
+            serde_json::from_str(json).map_err(|e| MerkleError::InvalidProof(e.to_string()))?;
+
+        let siblings = parsed
+            .proof
+            .iter()
+            .map(|hex| {
+                Ok(ProofNode {
+                    direction: ProofDirection::Right,
+                    hash: from_prefixed_hex(hex)?,
+                })
+            })
+            .collect::<Result<Vec<_>, MerkleError>>()?;
+
+        Ok(MerkleProof {
+            leaf_index: 0,
+            leaf_count: 0,
+            leaf_hash: from_prefixed_hex(&parsed.leaf)?,
+            siblings,
+            root: [0u8; 32],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MerkleError, MerkleProof, MerkleTree};
+
+    #[test]
+    fn test_serde_json_round_trip() {
+        let data: Vec<String> = (0..6).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let restored: MerkleTree<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.layers, tree.layers);
+        assert_eq!(restored.root_hex(), tree.root_hex());
+    }
+
+    #[test]
+    fn test_serde_rejects_tampered_root() {
+        let data = vec!["A".to_string(), "B".to_string()];
+        let tree = MerkleTree::new(data).unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let tampered = json.replace(&tree.root_hex(), &"00".repeat(32));
+        assert!(serde_json::from_str::<MerkleTree<String>>(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_serde_rejects_corrupted_internal_node_and_bad_shapes() {
+        let data: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let json = serde_json::to_string(&tree).unwrap();
+
+        // One hex digit of a level-1 node is changed; the root is left alone
+        let node = crate::to_hex(&tree.layer(1).unwrap()[1]);
+        let mut flipped = node.clone();
+        flipped.replace_range(..1, if node.starts_with('0') { "1" } else { "0" });
+        let corrupted = json.replace(&node, &flipped);
+        assert_ne!(corrupted, json);
+        assert!(serde_json::from_str::<MerkleTree<String>>(&corrupted).is_err());
+
+        // Layers that don't halve on the way up, or no layers at all
+        let mut layers = tree.clone().into_layers();
+        layers[1].pop();
+        let hex: Vec<Vec<String>> = layers
+            .iter()
+            .map(|layer| layer.iter().map(|hash| crate::to_hex(hash)).collect())
+            .collect();
+        let short = serde_json::json!({ "layers": hex }).to_string();
+        assert!(serde_json::from_str::<MerkleTree<String>>(&short).is_err());
+        for bad in [
+            r#"{"layers": []}"#,
+            r#"{"layers": [["zz"]]}"#,
+            r#"{"padding": "PromoteOdd"}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<MerkleTree<String>>(bad).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde_empty_tree_round_trip() {
+        let empty = MerkleTree::<String>::with_capacity(4);
+        let json = serde_json::to_string(&empty).unwrap();
+        assert_eq!(json, r#"{"layers":[[]],"padding":"DuplicateSelf"}"#);
+
+        let mut restored: MerkleTree<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.leaf_count(), 0);
+        assert_eq!(restored.layers(), empty.layers());
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+        // It grows like the original did
+        restored.push("a".to_string());
+        assert_eq!(restored, MerkleTree::new(vec!["a".to_string()]).unwrap());
+
+        // Only the single empty leaf layer counts as empty
+        assert!(serde_json::from_str::<MerkleTree<String>>(r#"{"layers": [[], []]}"#).is_err());
+    }
+
+    #[test]
+    fn test_serde_keeps_padding() {
+        use crate::PaddingStrategy;
+
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree: MerkleTree<String> =
+            MerkleTree::with_padding(data, PaddingStrategy::Custom([3u8; 32])).unwrap();
+        let restored: MerkleTree<String> =
+            serde_json::from_str(&serde_json::to_string(&tree).unwrap()).unwrap();
+        assert_eq!(restored.padding(), tree.padding());
+        assert_eq!(restored.layers(), tree.layers());
+
+        // Read back under the default padding, the custom-padded nodes don't check out
+        let json = serde_json::to_string(&tree).unwrap();
+        let without_padding = json.split(",\"padding\"").next().unwrap().to_string() + "}";
+        assert!(serde_json::from_str::<MerkleTree<String>>(&without_padding).is_err());
+    }
+
+    #[test]
+    fn test_serde_proof_round_trip() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let proof = tree.get_proof(4).unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let restored: crate::MerkleProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, proof);
+        assert!(restored.verify());
+    }
+
+    #[test]
+    fn test_merkletreejs_proof_round_trip() {
+        let data: Vec<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        // The proof for "c" in merkletreejs' shape. With sha256 leaves and `new MerkleTree(leaves, sha256)`
+        // merkletreejs builds the same four-leaf tree, so these are the hashes it hands out
+        let fixture = r#"[
+            {"position": "right", "data": "0x18ac3e7343f016890c510e93f935261169d9e3f565436429830faf0934f4f8e4"},
+            {"position": "left", "data": "0xe5a01fee14e0ed5c48714f22180f25ad8365b53f9779f79dc4a3d7e93963f94a"}
+        ]"#;
+        let proof = tree.get_proof(2).unwrap();
+        let parsed =
+            MerkleProof::from_merkletreejs_json(fixture, proof.leaf_hash, proof.root).unwrap();
+        assert_eq!(parsed.siblings, proof.siblings);
+        assert_eq!(parsed.leaf_index, 2);
+        assert!(parsed.verify());
+
+        // Writing and reading back gives the same proof
+        let json = proof.to_merkletreejs_json();
+        assert!(json.starts_with(r#"[{"position":"right","data":"0x18ac3e73"#));
+        let back = MerkleProof::from_merkletreejs_json(&json, proof.leaf_hash, proof.root).unwrap();
+        assert_eq!(back.siblings, proof.siblings);
+    }
+
+    #[test]
+    fn test_merkletreejs_rejects_malformed_json() {
+        let leaf = [0u8; 32];
+        let bad = [
+            "not json",
+            r#"[{"position": "up", "data": "0x00"}]"#,
+            r#"[{"position": "left"}]"#,
+            r#"[{"position": "left", "data": "0x1234"}]"#,
+        ];
+        for json in bad {
+            let err = MerkleProof::from_merkletreejs_json(json, leaf, leaf).unwrap_err();
+            assert!(matches!(
+                err,
+                MerkleError::InvalidProof(_) | MerkleError::HashDecodingError(_)
+            ));
+        }
+    }
+
+    // Written by version 1 of `to_json` for leaf 3 of the SHA-256 tree over "0".."6". If this stops parsing
+    // or verifying, the document format changed and `PROOF_DOC_VERSION` has to go up.
+    const PROOF_V1_FIXTURE: &str = include_str!("../tests/fixtures/proof_v1.json");
+
+    #[test]
+    fn test_proof_document_fixture() {
+        let tree = MerkleTree::new((0..7).map(|i| i.to_string()).collect()).unwrap();
+        let proof = MerkleProof::from_json(PROOF_V1_FIXTURE).unwrap();
+        assert!(proof.verify());
+        assert_eq!(proof, tree.get_proof(3).unwrap());
+
+        let written: serde_json::Value = serde_json::from_str(&proof.to_json()).unwrap();
+        let stored: serde_json::Value = serde_json::from_str(PROOF_V1_FIXTURE).unwrap();
+        assert_eq!(written, stored);
+    }
+
+    #[test]
+    fn test_proof_document_versions_and_hashers() {
+        let mut doc: serde_json::Value = serde_json::from_str(PROOF_V1_FIXTURE).unwrap();
+
+        // Fields from a later minor addition are skipped, and the root may be left out
+        doc["comment"] = serde_json::json!("added by a newer writer");
+        doc.as_object_mut().unwrap().remove("root");
+        let proof = MerkleProof::from_json(&doc.to_string()).unwrap();
+        assert_eq!(proof.root, [0u8; 32]);
+        assert_eq!(proof.leaf_index, 3);
+
+        doc["version"] = serde_json::json!(2);
+        assert_eq!(
+            MerkleProof::from_json(&doc.to_string()).err(),
+            Some(MerkleError::UnsupportedVersion(2))
+        );
+
+        // A SHA-256 proof is not read as a proof for another hasher
+        assert!(matches!(
+            MerkleProof::from_json_with::<crate::DomainSeparated>(PROOF_V1_FIXTURE),
+            Err(MerkleError::InvalidProof(_))
+        ));
+        for bad in ["not json", r#"{"version": 1}"#, r#"{"leaf_index": 0}"#] {
+            assert!(MerkleProof::from_json(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    fn test_openzeppelin_proof_vector() {
+        use crate::OpenZeppelinHasher;
+        use crate::standard::StandardMerkleTree;
+
+        // The proof for the first value of the @openzeppelin/merkle-tree README example
+        // (["0x1111...1111", "5000000000000000000"] as address, uint256), whose root the README prints
+        let fixture = r#"{
+            "proof": ["0xb92c48e9d7abe27fd8dfd6b5dfdbfb1c9a463f80c712b66f3a5180a090cccafc"],
+            "leaf": "0xeb02c421cfa48976e66dfb29120745909ea3a0f843456c263cf8f1253483e283"
+        }"#;
+        let root =
+            crate::from_hex("d4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77")
+                .unwrap();
+        let mut proof = MerkleProof::from_oz_json(fixture).unwrap();
+        assert!(!proof.verify_with::<OpenZeppelinHasher>());
+        proof.root = root;
+        assert!(proof.verify_with::<OpenZeppelinHasher>());
+
+        // The same values through StandardMerkleTree give the same leaf and siblings
+        let values = vec![
+            vec![
+                "0x1111111111111111111111111111111111111111".to_string(),
+                "5000000000000000000".to_string(),
+            ],
+            vec![
+                "0x2222222222222222222222222222222222222222".to_string(),
+                "2500000000000000000".to_string(),
+            ],
+        ];
+        let tree =
+            StandardMerkleTree::of(values, vec!["address".to_string(), "uint256".to_string()])
+                .unwrap();
+        assert_eq!(proof.leaf_hash, tree.leaf_hash(0).unwrap());
+        assert_eq!(proof.siblings[0].hash, tree.get_proof(0).unwrap()[0]);
+
+        // Written back out, the 0x prefixes are there again
+        let json = proof.to_oz_json();
+        assert_eq!(
+            json,
+            r#"{"proof":["0xb92c48e9d7abe27fd8dfd6b5dfdbfb1c9a463f80c712b66f3a5180a090cccafc"],"leaf":"0xeb02c421cfa48976e66dfb29120745909ea3a0f843456c263cf8f1253483e283"}"#
+        );
+        assert_eq!(
+            MerkleProof::from_oz_json(&json).unwrap().siblings,
+            proof.siblings
+        );
+    }
+
+    #[test]
+    fn test_oz_json_round_trip_and_errors() {
+        let data: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+        let proof = tree.get_proof(3).unwrap();
+
+        let parsed = MerkleProof::from_oz_json(&proof.to_oz_json()).unwrap();
+        assert_eq!(parsed.leaf_hash, proof.leaf_hash);
+        let hashes = |p: &MerkleProof| p.siblings.iter().map(|n| n.hash).collect::<Vec<_>>();
+        assert_eq!(hashes(&parsed), hashes(&proof));
+
+        for json in [
+            "[]",
+            r#"{"proof": []}"#,
+            r#"{"proof": ["0x1234"], "leaf": "0x00"}"#,
+        ] {
+            assert!(MerkleProof::from_oz_json(json).is_err());
+        }
+    }
+}
@@ -0,0 +1,344 @@
+// Merkle sets: a tree over sorted element hashes, so both membership and absence can be proven.
+// `MerkleTree::prove_absence` gives the same absence proofs for trees built with sorted leaves.
+
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleProof, MerkleTree, PaddingStrategy, Sha256Hasher,
+};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A set of elements committed to by one Merkle root.
+/// Elements are stored as their leaf hashes in sorted order, so an element that is missing can be proven absent
+/// by showing the two adjacent leaves whose hashes bracket its hash.
+#[derive(Debug)]
+pub struct MerkleSet<T: Hashable, H: MerkleHasher = Sha256Hasher> {
+    // Sorted and deduplicated leaf hashes; the tree is rebuilt from them after every insert.
+    hashes: Vec<[u8; 32]>,
+    tree: Option<MerkleTree<(), H>>,
+    // Marker to link the set to type T without storing any elements.
+    _marker: PhantomData<T>,
+}
+
+/// Proof that an element is or isn't in a `MerkleSet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MembershipProof {
+    /// The element's hash is a leaf of the set.
+    Present(MerkleProof),
+    /// The element's hash falls strictly between two adjacent leaves.
+    /// A query below the smallest or above the largest hash only has the one neighbour on the inside.
+    Absent {
+        left_neighbor: Option<MerkleProof>,
+        right_neighbor: Option<MerkleProof>,
+    },
+}
+
+impl<T: Hashable> MerkleSet<T> {
+    /// Creates an empty SHA-256 set.
+    pub fn new() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> Default for MerkleSet<T, H> {
+    fn default() -> Self {
+        Self::with_hasher()
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleSet<T, H> {
+    /// Creates an empty set whose tree combines hashes with `H`.
+    pub fn with_hasher() -> Self {
+        MerkleSet {
+            hashes: Vec::new(),
+            tree: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds `item` and rebuilds the tree. Returns false if it was already in the set.
+    pub fn insert(&mut self, item: T) -> bool {
+        let hash = item.hash_with::<H>();
+        let Err(position) = self.hashes.binary_search(&hash) else {
+            return false;
+        };
+
+        self.hashes.insert(position, hash);
+        self.tree = MerkleTree::<(), H>::from_hashes_with_hasher(self.hashes.clone()).ok();
+        true
+    }
+
+    /// True if `item` is in the set (binary search over the sorted hashes).
+    pub fn contains(&self, item: &T) -> bool {
+        self.hashes.binary_search(&item.hash_with::<H>()).is_ok()
+    }
+
+    /// Number of distinct elements.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// True until the first `insert`.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// The root committing to the whole set (all zeros while it is empty).
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        self.tree
+            .as_ref()
+            .map_or(&[0u8; 32], |tree| tree.root_bytes())
+    }
+
+    /// Proves that `item` is in the set, or that it isn't. Returns an Error if the set is empty.
+    pub fn membership_proof(&self, item: &T) -> Result<MembershipProof, MerkleError> {
+        let tree = self.tree.as_ref().ok_or(MerkleError::EmptyInput)?;
+
+        match self.hashes.binary_search(&item.hash_with::<H>()) {
+            Ok(index) => Ok(MembershipProof::Present(tree.get_proof(index)?)),
+            Err(position) => bracketing_proof(tree, position),
+        }
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Proves that `item` is not a leaf of a tree built with `new_sorted_leaves` / `with_sorted_leaves`:
+    /// returns `MembershipProof::Absent` with the proofs (and so the indices) of the two leaves that bracket its hash.
+    /// Check it with `proves_absent`. Returns an Error if `item` is in the tree (use `get_proof` for that)
+    /// or if the leaves are not sorted, since the bracketing leaves would then prove nothing.
+    pub fn prove_absence(&self, item: &T) -> Result<MembershipProof, MerkleError> {
+        let leaves = &self.layers[0];
+        if leaves.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+        if !leaves.is_sorted() {
+            return Err(MerkleError::InvalidLayers(
+                "leaves are not sorted, build the tree with with_sorted_leaves".to_string(),
+            ));
+        }
+
+        match leaves.binary_search(&item.hash_with::<H>()) {
+            Ok(index) => Err(MerkleError::InvalidLeafValue(format!(
+                "the value is leaf {} of the tree",
+                index
+            ))),
+            Err(position) => bracketing_proof(self, position),
+        }
+    }
+}
+
+// `position` is where the missing hash would go in the sorted leaves: the leaves on either side bracket it.
+fn bracketing_proof<T: Hashable, H: MerkleHasher>(
+    tree: &MerkleTree<T, H>,
+    position: usize,
+) -> Result<MembershipProof, MerkleError> {
+    Ok(MembershipProof::Absent {
+        left_neighbor: position
+            .checked_sub(1)
+            .map(|i| tree.get_proof(i))
+            .transpose()?,
+        right_neighbor: tree.get_proof(position).ok(),
+    })
+}
+
+impl MembershipProof {
+    /// True if the proof shows the element with hash `item_hash` is in the SHA-256 set with `root`.
+    pub fn proves_present(&self, item_hash: &[u8; 32], root: &[u8; 32]) -> bool {
+        self.proves_present_with::<Sha256Hasher>(item_hash, root)
+    }
+
+    /// Same as `proves_present`, for sets built with a hasher other than SHA-256.
+    pub fn proves_present_with<H: MerkleHasher>(
+        &self,
+        item_hash: &[u8; 32],
+        root: &[u8; 32],
+    ) -> bool {
+        match self {
+            MembershipProof::Present(proof) => verify_leaf::<H>(proof, item_hash, root),
+            MembershipProof::Absent { .. } => false,
+        }
+    }
+
+    /// True if the proof shows the element with hash `item_hash` is not in the SHA-256 set with `root`.
+    pub fn proves_absent(&self, item_hash: &[u8; 32], root: &[u8; 32]) -> bool {
+        self.proves_absent_with::<Sha256Hasher>(item_hash, root)
+    }
+
+    /// Same as `proves_absent`, for sets built with a hasher other than SHA-256.
+    /// Both neighbours must verify, sit next to each other, and have hashes strictly around `item_hash`;
+    /// a missing neighbour is only accepted when the other one is the first or last leaf.
+    pub fn proves_absent_with<H: MerkleHasher>(
+        &self,
+        item_hash: &[u8; 32],
+        root: &[u8; 32],
+    ) -> bool {
+        let MembershipProof::Absent {
+            left_neighbor,
+            right_neighbor,
+        } = self
+        else {
+            return false;
+        };
+
+        match (left_neighbor, right_neighbor) {
+            (Some(left), Some(right)) => {
+                left.leaf_count == right.leaf_count
+                    && left.leaf_index + 1 == right.leaf_index
+                    && left.leaf_hash < *item_hash
+                    && *item_hash < right.leaf_hash
+                    && verify_leaf::<H>(left, &left.leaf_hash, root)
+                    && verify_leaf::<H>(right, &right.leaf_hash, root)
+            }
+            // Smaller than every element
+            (None, Some(right)) => {
+                right.leaf_index == 0
+                    && *item_hash < right.leaf_hash
+                    && verify_leaf::<H>(right, &right.leaf_hash, root)
+            }
+            // Larger than every element
+            (Some(left), None) => {
+                left.leaf_index + 1 == left.leaf_count
+                    && left.leaf_hash < *item_hash
+                    && verify_leaf::<H>(left, &left.leaf_hash, root)
+            }
+            (None, None) => false,
+        }
+    }
+}
+
+// Sets and sorted trees always use the default padding; the strict check also ties leaf_index to the path the proof takes.
+fn verify_leaf<H: MerkleHasher>(
+    proof: &MerkleProof,
+    leaf_hash: &[u8; 32],
+    root: &[u8; 32],
+) -> bool {
+    proof.verify_padded_with::<H>(leaf_hash, root, PaddingStrategy::DuplicateSelf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_of(items: &[&str]) -> MerkleSet<String> {
+        let mut set = MerkleSet::new();
+        for item in items {
+            set.insert(item.to_string());
+        }
+        set
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut set = set_of(&["A", "B", "C"]);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&"B".to_string()));
+        assert!(!set.contains(&"D".to_string()));
+        assert!(!set.insert("B".to_string()));
+
+        // The root only depends on the contents, not the insertion order
+        let reversed = set_of(&["C", "B", "A"]);
+        assert_eq!(set.root_bytes(), reversed.root_bytes());
+        assert!(set.insert("D".to_string()));
+        assert_ne!(set.root_bytes(), reversed.root_bytes());
+    }
+
+    #[test]
+    fn test_membership_and_non_membership_proofs() {
+        let items: Vec<String> = (0..9).map(|i| format!("member-{}", i)).collect();
+        let mut set = MerkleSet::new();
+        for item in &items {
+            set.insert(item.clone());
+        }
+        let root = *set.root_bytes();
+
+        for item in &items {
+            let proof = set.membership_proof(item).unwrap();
+            assert!(proof.proves_present(&item.hash(), &root));
+            assert!(!proof.proves_absent(&item.hash(), &root));
+        }
+
+        // Enough outsiders to land between neighbours and past both ends
+        let mut seen_ends = (false, false);
+        for i in 0..64 {
+            let outsider = format!("outsider-{}", i);
+            let proof = set.membership_proof(&outsider).unwrap();
+            assert!(proof.proves_absent(&outsider.hash(), &root), "{}", outsider);
+            assert!(!proof.proves_present(&outsider.hash(), &root));
+
+            if let MembershipProof::Absent {
+                left_neighbor,
+                right_neighbor,
+            } = &proof
+            {
+                seen_ends.0 |= left_neighbor.is_none();
+                seen_ends.1 |= right_neighbor.is_none();
+            }
+        }
+        assert_eq!(seen_ends, (true, true));
+    }
+
+    #[test]
+    fn test_absence_proof_cannot_hide_a_member() {
+        let set = set_of(&["A", "B", "C", "D", "E"]);
+        let root = *set.root_bytes();
+
+        // Two neighbours that are not adjacent leave a gap where a real member could hide
+        let tree = set.tree.as_ref().unwrap();
+        let gap = MembershipProof::Absent {
+            left_neighbor: Some(tree.get_proof(0).unwrap()),
+            right_neighbor: Some(tree.get_proof(2).unwrap()),
+        };
+        let hidden = set.hashes[1];
+        assert!(!gap.proves_absent(&hidden, &root));
+
+        // A proof for a member is no absence proof, and nothing verifies against another root
+        let present = set.membership_proof(&"C".to_string()).unwrap();
+        assert!(!present.proves_absent(&"C".to_string().hash(), &root));
+        assert!(!present.proves_present(&"C".to_string().hash(), &[0u8; 32]));
+
+        assert_eq!(
+            MerkleSet::<String>::new().membership_proof(&"A".to_string()),
+            Err(MerkleError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_sorted_tree_absence_proofs() {
+        let emails: Vec<String> = (0..10).map(|i| format!("user{}@example.com", i)).collect();
+        let tree = MerkleTree::new_sorted_leaves(emails.clone()).unwrap();
+        let root = *tree.root_bytes();
+
+        // Enough outsiders to land between neighbours and before the first and after the last leaf
+        let mut seen_ends = (false, false);
+        for i in 0..64 {
+            let outsider = format!("breached{}@example.com", i);
+            let proof = tree.prove_absence(&outsider).unwrap();
+            assert!(proof.proves_absent(&outsider.hash(), &root), "{}", outsider);
+
+            if let MembershipProof::Absent {
+                left_neighbor,
+                right_neighbor,
+            } = &proof
+            {
+                seen_ends.0 |= left_neighbor.is_none();
+                seen_ends.1 |= right_neighbor.is_none();
+                // The same proof can't clear one of its own bracketing leaves
+                let left = left_neighbor.as_ref().or(right_neighbor.as_ref()).unwrap();
+                assert!(!proof.proves_absent(&left.leaf_hash, &root));
+            }
+        }
+        assert_eq!(seen_ends, (true, true));
+
+        // A member can't be proven absent, and an unsorted tree refuses to try
+        assert!(matches!(
+            tree.prove_absence(&emails[3]),
+            Err(MerkleError::InvalidLeafValue(_))
+        ));
+        let unsorted = MerkleTree::new(emails).unwrap();
+        assert!(matches!(
+            unsorted.prove_absence(&"nobody@example.com".to_string()),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+    }
+}
@@ -0,0 +1,400 @@
+// Sparse Merkle trees: a fixed-depth tree over the whole keyspace where almost every leaf is empty.
+// Only non-empty nodes are stored; an empty subtree of any height is always the same precomputed hash.
+
+use crate::{Hashable, MerkleError, MerkleHasher, Sha256Hasher, hash_pair_with, to_hex};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A key→value commitment over a 2^depth keyspace.
+/// A key's slot is the first `depth` bits of its hash (most significant bit first, 0 = left),
+/// an empty slot is the all-zero hash, and each empty subtree hashes the pair of empty subtrees below it.
+///
+/// Keys whose hashes share their first `depth` bits land in the same slot, so keep `depth` at 160 or more.
+#[derive(Debug)]
+pub struct SparseMerkleTree<K: Hashable, V: Hashable, H: MerkleHasher = Sha256Hasher> {
+    depth: usize,
+    // empty[h] = root of an empty subtree of height h (empty[0] is an empty leaf).
+    empty: Vec<[u8; 32]>,
+    // Non-empty nodes keyed by (height, path with every bit below the node cleared).
+    nodes: BTreeMap<(usize, [u8; 32]), [u8; 32]>,
+    values: BTreeMap<[u8; 32], V>,
+    // Marker to link the tree to key type K and hasher H without storing either.
+    _marker: PhantomData<(K, H)>,
+}
+
+/// Proof of what a `SparseMerkleTree` holds for one key: its value's hash, or nothing at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseProof {
+    /// The key's slot: the first `depth` bits of its hash, the rest zeroed.
+    pub path: [u8; 32],
+    /// The hash of the value stored under the key, or None if the slot is empty.
+    pub leaf_hash: Option<[u8; 32]>,
+    /// One sibling per level, from the leaves up (`depth` entries).
+    pub siblings: Vec<[u8; 32]>,
+    /// The root of the tree the proof was taken from.
+    pub root: [u8; 32],
+}
+
+impl<K: Hashable, V: Hashable> SparseMerkleTree<K, V> {
+    /// Creates an empty SHA-256 tree over `depth`-bit key paths.
+    /// Returns an Error unless `depth` is between 1 and 256.
+    pub fn new(depth: usize) -> Result<Self, MerkleError> {
+        Self::with_hasher(depth)
+    }
+}
+
+impl<K: Hashable, V: Hashable, H: MerkleHasher> SparseMerkleTree<K, V, H> {
+    /// Same as `new`, hashing keys, values and nodes with `H`.
+    pub fn with_hasher(depth: usize) -> Result<Self, MerkleError> {
+        if depth == 0 || depth > 256 {
+            return Err(MerkleError::InvalidDepth(depth));
+        }
+
+        // The empty-subtree chain is computed once and never changes
+        let mut empty = Vec::with_capacity(depth + 1);
+        empty.push([0u8; 32]);
+        for height in 0..depth {
+            empty.push(hash_pair_with::<H>(&empty[height], &empty[height]));
+        }
+
+        Ok(SparseMerkleTree {
+            depth,
+            empty,
+            nodes: BTreeMap::new(),
+            values: BTreeMap::new(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// How many bits of the key hash pick the slot.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The cached root of an empty subtree of `height` (0 = an empty leaf, `depth()` = an empty tree),
+    /// or None above the root. A proof whose sibling at height h is `empty_hash(h)` passes through an empty subtree there.
+    pub fn empty_hash(&self, height: usize) -> Option<&[u8; 32]> {
+        self.empty.get(height)
+    }
+
+    /// Number of keys with a value.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// True until the first `insert`.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The raw 32-byte Merkle Root. An empty tree has the root of an all-empty subtree of full height.
+    pub fn root(&self) -> &[u8; 32] {
+        self.node(self.depth, &[0u8; 32])
+    }
+
+    /// Returns the Merkle Root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(self.root())
+    }
+
+    /// The value stored under `key`, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(&self.path_of(key))
+    }
+
+    /// Stores `value` under `key` and rehashes the `depth` nodes above its slot.
+    /// Returns the value that was there before, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let path = self.path_of(&key);
+        self.set_leaf_hash(path, value.hash_with::<H>());
+        self.values.insert(path, value)
+    }
+
+    // Writes `leaf_hash` into the slot at `path` and rehashes the `depth` nodes above it.
+    fn set_leaf_hash(&mut self, path: [u8; 32], leaf_hash: [u8; 32]) {
+        let mut current = leaf_hash;
+        self.nodes.insert((0, path), current);
+
+        // Walk up: at height h the bit that picks our side is bit depth - h - 1 of the path
+        for height in 0..self.depth {
+            let bit = self.depth - height - 1;
+            let sibling = self.node(height, &flip_bit(&prefix(&path, bit + 1), bit));
+            current = if get_bit(&path, bit) {
+                hash_pair_with::<H>(sibling, &current)
+            } else {
+                hash_pair_with::<H>(&current, sibling)
+            };
+            self.nodes.insert((height + 1, prefix(&path, bit)), current);
+        }
+    }
+
+    /// Proves what the tree holds for `key`: an inclusion proof if it has a value,
+    /// otherwise a proof that its slot is still empty.
+    pub fn get_proof(&self, key: &K) -> SparseProof {
+        self.proof_for_path(self.path_of(key))
+    }
+
+    // The proof for the slot at `path`, however the path was derived.
+    fn proof_for_path(&self, path: [u8; 32]) -> SparseProof {
+        let siblings = (0..self.depth)
+            .map(|height| {
+                let bit = self.depth - height - 1;
+                *self.node(height, &flip_bit(&prefix(&path, bit + 1), bit))
+            })
+            .collect();
+
+        SparseProof {
+            path,
+            leaf_hash: self.nodes.get(&(0, path)).copied(),
+            siblings,
+            root: *self.root(),
+        }
+    }
+
+    // The key's slot: the first `depth` bits of its hash.
+    fn path_of(&self, key: &K) -> [u8; 32] {
+        prefix(&key.hash_with::<H>(), self.depth)
+    }
+
+    // The stored node, or the empty subtree of that height.
+    fn node(&self, height: usize, path: &[u8; 32]) -> &[u8; 32] {
+        self.nodes
+            .get(&(height, *path))
+            .unwrap_or(&self.empty[height])
+    }
+}
+
+// For keys that already are 32-byte paths (e.g. derived addresses) and values that are already hashed:
+// `set`, `leaf_at` and `proof_at` use both as given, while `insert`, `get` and `get_proof` hash them,
+// so stick to one family per tree.
+impl<H: MerkleHasher> SparseMerkleTree<[u8; 32], [u8; 32], H> {
+    /// Stores `value_hash` as the leaf in slot `key`, exactly as given, and rehashes the nodes above it.
+    /// The slot is the first `depth` bits of `key`; returns an Error if any bit after them is set,
+    /// so two different keys can never share a slot.
+    pub fn set(&mut self, key: [u8; 32], value_hash: [u8; 32]) -> Result<(), MerkleError> {
+        let path = self.raw_path(&key)?;
+        self.set_leaf_hash(path, value_hash);
+        self.values.insert(path, value_hash);
+        Ok(())
+    }
+
+    /// The leaf stored by `set` in slot `key`, if any.
+    pub fn leaf_at(&self, key: &[u8; 32]) -> Option<&[u8; 32]> {
+        self.values.get(key)
+    }
+
+    /// Proves what slot `key` holds, like `get_proof` but without hashing the key.
+    /// Returns an Error for the same keys as `set`.
+    pub fn proof_at(&self, key: &[u8; 32]) -> Result<SparseProof, MerkleError> {
+        Ok(self.proof_for_path(self.raw_path(key)?))
+    }
+
+    fn raw_path(&self, key: &[u8; 32]) -> Result<[u8; 32], MerkleError> {
+        if prefix(key, self.depth) != *key {
+            return Err(MerkleError::InvalidLeafValue(format!(
+                "key {} has bits set past the first {} that pick its slot",
+                to_hex(key),
+                self.depth
+            )));
+        }
+        Ok(*key)
+    }
+}
+
+impl SparseProof {
+    /// Recomputes the root from `leaf_hash` (an empty leaf if None) along `path` and checks it against `root`.
+    /// Compare `path` and `leaf_hash` with your own key and value before trusting the result.
+    pub fn verify(&self) -> bool {
+        self.verify_with::<Sha256Hasher>()
+    }
+
+    /// Same as `verify`, for trees built with a hasher other than SHA-256.
+    pub fn verify_with<H: MerkleHasher>(&self) -> bool {
+        let depth = self.siblings.len();
+        // Bits below the slot must be zero, or two paths would verify for the same leaf
+        if depth == 0 || depth > 256 || prefix(&self.path, depth) != self.path {
+            return false;
+        }
+
+        let mut current = self.leaf_hash.unwrap_or([0u8; 32]);
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            current = if get_bit(&self.path, depth - height - 1) {
+                hash_pair_with::<H>(sibling, &current)
+            } else {
+                hash_pair_with::<H>(&current, sibling)
+            };
+        }
+        current == self.root
+    }
+}
+
+// Bit `index` of `path`, counting from the most significant bit of the first byte.
+fn get_bit(path: &[u8; 32], index: usize) -> bool {
+    (path[index / 8] >> (7 - index % 8)) & 1 == 1
+}
+
+fn flip_bit(path: &[u8; 32], index: usize) -> [u8; 32] {
+    let mut flipped = *path;
+    flipped[index / 8] ^= 1 << (7 - index % 8);
+    flipped
+}
+
+// Keeps the first `bits` bits of `path` and zeroes the rest.
+fn prefix(path: &[u8; 32], bits: usize) -> [u8; 32] {
+    let mut masked = [0u8; 32];
+    masked[..bits / 8].copy_from_slice(&path[..bits / 8]);
+    if !bits.is_multiple_of(8) {
+        masked[bits / 8] = path[bits / 8] & (0xff << (8 - bits % 8));
+    }
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainSeparated, hash_data, hash_pair};
+
+    #[test]
+    fn test_insertion_order_does_not_matter() {
+        let mut forward: SparseMerkleTree<String, String> = SparseMerkleTree::new(256).unwrap();
+        let empty_root = *forward.root();
+        forward.insert("alice".to_string(), "10".to_string());
+        forward.insert("bob".to_string(), "20".to_string());
+
+        let mut backward: SparseMerkleTree<String, String> = SparseMerkleTree::new(256).unwrap();
+        backward.insert("bob".to_string(), "20".to_string());
+        backward.insert("alice".to_string(), "10".to_string());
+
+        assert_eq!(forward.root(), backward.root());
+        assert_ne!(forward.root(), &empty_root);
+        assert_eq!(forward.get(&"alice".to_string()), Some(&"10".to_string()));
+        assert_eq!(forward.get(&"carol".to_string()), None);
+
+        // Overwriting hands back the old value and moves the root
+        let before = *forward.root();
+        assert_eq!(
+            forward.insert("alice".to_string(), "11".to_string()),
+            Some("10".to_string())
+        );
+        assert_ne!(forward.root(), &before);
+        assert_eq!(forward.len(), 2);
+    }
+
+    #[test]
+    fn test_empty_hashes_are_cached_per_height() {
+        let mut tree: SparseMerkleTree<String, String> = SparseMerkleTree::new(16).unwrap();
+        assert_eq!(tree.empty_hash(0), Some(&[0u8; 32]));
+        for height in 0..16 {
+            let below = tree.empty_hash(height).unwrap();
+            assert_eq!(tree.empty_hash(height + 1), Some(&hash_pair(below, below)));
+        }
+        assert_eq!(tree.empty_hash(16), Some(tree.root()));
+        assert_eq!(tree.empty_hash(17), None);
+
+        // With one key set, every sibling on its path is still an empty subtree
+        tree.insert("only".to_string(), "value".to_string());
+        let proof = tree.get_proof(&"only".to_string());
+        for (height, sibling) in proof.siblings.iter().enumerate() {
+            assert_eq!(Some(sibling), tree.empty_hash(height));
+        }
+    }
+
+    #[test]
+    fn test_inclusion_and_non_membership_proofs() {
+        for depth in [13, 160, 256] {
+            let mut tree =
+                SparseMerkleTree::<String, String, DomainSeparated>::with_hasher(depth).unwrap();
+            for i in 0..8 {
+                tree.insert(format!("key-{}", i), format!("value-{}", i));
+            }
+
+            let proof = tree.get_proof(&"key-3".to_string());
+            assert_eq!(proof.siblings.len(), depth);
+            assert_eq!(
+                proof.leaf_hash,
+                Some("value-3".to_string().hash_with::<DomainSeparated>())
+            );
+            assert!(proof.verify_with::<DomainSeparated>());
+            assert!(!proof.verify());
+
+            let absent = tree.get_proof(&"untouched".to_string());
+            assert_eq!(absent.leaf_hash, None);
+            assert!(absent.verify_with::<DomainSeparated>());
+
+            // Claiming a value for the empty slot breaks the proof
+            let mut forged = absent.clone();
+            forged.leaf_hash = Some([7u8; 32]);
+            assert!(!forged.verify_with::<DomainSeparated>());
+        }
+    }
+
+    #[test]
+    fn test_set_uses_keys_and_value_hashes_as_given() {
+        let mut tree: SparseMerkleTree<[u8; 32], [u8; 32]> = SparseMerkleTree::new(256).unwrap();
+        let key = hash_data(b"derived address");
+        let value_hash = hash_data(b"balance 10");
+        tree.set(key, value_hash).unwrap();
+        assert_eq!(tree.leaf_at(&key), Some(&value_hash));
+        assert_eq!(tree.leaf_at(&[1u8; 32]), None);
+
+        // The proof's path is the key itself and its leaf is the value hash, unhashed
+        let proof = tree.proof_at(&key).unwrap();
+        assert_eq!(proof.path, key);
+        assert_eq!(proof.leaf_hash, Some(value_hash));
+        assert!(proof.verify());
+        let mut expected = value_hash;
+        for (height, sibling) in proof.siblings.iter().enumerate() {
+            assert_eq!(Some(sibling), tree.empty_hash(height));
+            expected = if get_bit(&key, 255 - height) {
+                hash_pair(sibling, &expected)
+            } else {
+                hash_pair(&expected, sibling)
+            };
+        }
+        assert_eq!(&expected, tree.root());
+
+        let absent = tree.proof_at(&[1u8; 32]).unwrap();
+        assert_eq!(absent.leaf_hash, None);
+        assert!(absent.verify());
+
+        // On a shallower tree a key must fit in the slot bits
+        let mut shallow: SparseMerkleTree<[u8; 32], [u8; 32]> = SparseMerkleTree::new(8).unwrap();
+        let mut slot = [0u8; 32];
+        slot[0] = 0b1010_0000;
+        shallow.set(slot, value_hash).unwrap();
+        assert!(shallow.proof_at(&slot).unwrap().verify());
+        slot[31] = 1;
+        assert!(matches!(
+            shallow.set(slot, value_hash),
+            Err(MerkleError::InvalidLeafValue(_))
+        ));
+        assert!(shallow.proof_at(&slot).is_err());
+    }
+
+    #[test]
+    fn test_empty_tree_and_bad_depths() {
+        let tree: SparseMerkleTree<String, String> = SparseMerkleTree::new(3).unwrap();
+        let empty_leaf = [0u8; 32];
+        let mut expected = empty_leaf;
+        for _ in 0..3 {
+            expected = crate::hash_pair(&expected, &expected);
+        }
+        assert_eq!(tree.root(), &expected);
+        assert!(tree.get_proof(&"A".to_string()).verify());
+
+        for depth in [0, 257] {
+            assert_eq!(
+                SparseMerkleTree::<String, String>::new(depth).err(),
+                Some(MerkleError::InvalidDepth(depth))
+            );
+        }
+
+        // Bits below the slot are not free to change
+        let mut proof = tree.get_proof(&"A".to_string());
+        proof.path[31] |= 1;
+        assert!(!proof.verify());
+    }
+}
@@ -0,0 +1,524 @@
+// OpenZeppelin `StandardMerkleTree` compatibility, enabled with the `keccak` feature.
+// Builds the same leaves, tree, root and proofs as the `@openzeppelin/merkle-tree` JS package,
+// and reads/writes its JSON dump format ("standard-v1").
+//
+// The JS tree is not laid out like `MerkleTree`: all nodes live in one array with the root at index 0,
+// the children of node i at 2i + 1 and 2i + 2, and the (sorted) leaves filling the end of the array.
+// For leaf counts that are not a power of two that gives different roots, so it gets its own type.
+
+use crate::{
+    Keccak256Hasher, MerkleError, MerkleHasher, OpenZeppelinHasher, from_hex, to_hex,
+    verify_sorted_proof_with,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The only dump format the JS package writes today.
+pub const STANDARD_FORMAT: &str = "standard-v1";
+
+/// One input value and the position of its leaf in the tree array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct StandardValue {
+    /// The value as written in the dump, one string per entry of the leaf encoding.
+    pub value: Vec<String>,
+    /// Where the value's leaf sits in the tree array.
+    pub tree_index: usize,
+}
+
+/// The JSON dump written by the JS package's `tree.dump()`:
+/// `{"format": "standard-v1", "leafEncoding": [...], "tree": ["0x..", ...], "values": [{"value": [...], "treeIndex": n}]}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct StandardTreeDump {
+    pub format: String,
+    pub leaf_encoding: Vec<String>,
+    pub tree: Vec<String>,
+    pub values: Vec<StandardValue>,
+}
+
+/// A tree that matches OpenZeppelin's `StandardMerkleTree`:
+/// leaves are `keccak256(keccak256(abi.encode(value)))`, pairs are sorted before hashing,
+/// and proofs are plain lists of sibling hashes that Solidity's `MerkleProof.verify` accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandardMerkleTree {
+    // The whole tree in one array: root first, sorted leaves last.
+    tree: Vec<[u8; 32]>,
+    // The values in the order they were given, each pointing at its leaf.
+    values: Vec<StandardValue>,
+    leaf_encoding: Vec<String>,
+}
+
+// Double-hashed leaves and Keccak-256 over the sorted children.
+type StandardHasher = OpenZeppelinHasher;
+
+/// The leaf for a value that is already ABI-encoded: `keccak256(keccak256(encoded))`.
+/// Hashing twice keeps a 64-byte leaf from ever being mistaken for an internal node.
+pub fn standard_leaf_hash(encoded: &[u8]) -> [u8; 32] {
+    StandardHasher::hash_leaf(encoded)
+}
+
+/// `abi.encode` for static types: `address`, `bool`, `uint8` to `uint256` (decimal or `0x` hex)
+/// and `bytes1` to `bytes32`. Every value takes one 32-byte word.
+/// Dynamic types (`string`, `bytes`, arrays) are not supported; encode those yourself and use `from_encoded`.
+pub fn abi_encode(leaf_encoding: &[String], value: &[String]) -> Result<Vec<u8>, MerkleError> {
+    if leaf_encoding.len() != value.len() {
+        return Err(MerkleError::InvalidLeafValue(format!(
+            "expected {} values, got {}",
+            leaf_encoding.len(),
+            value.len()
+        )));
+    }
+
+    let mut encoded = Vec::with_capacity(32 * value.len());
+    for (kind, item) in leaf_encoding.iter().zip(value) {
+        encoded.extend_from_slice(&encode_word(kind, item)?);
+    }
+
+    Ok(encoded)
+}
+
+// Encodes one static value into its 32-byte ABI word.
+fn encode_word(kind: &str, item: &str) -> Result<[u8; 32], MerkleError> {
+    let invalid = || MerkleError::InvalidLeafValue(format!("{:?} is not a valid {}", item, kind));
+    let mut word = [0u8; 32];
+
+    if kind == "address" {
+        // 20 bytes, right-aligned
+        let bytes =
+            decode_hex(item.strip_prefix("0x").ok_or_else(invalid)?, 20).ok_or_else(invalid)?;
+        word[12..].copy_from_slice(&bytes);
+    } else if kind == "bool" {
+        word[31] = match item {
+            "true" => 1,
+            "false" => 0,
+            _ => return Err(invalid()),
+        };
+    } else if let Some(bits) = kind.strip_prefix("uint") {
+        let bits = if bits.is_empty() {
+            Ok(256)
+        } else {
+            bits.parse::<usize>()
+        };
+        let bits = bits
+            .ok()
+            .filter(|b| *b > 0 && *b <= 256 && b.is_multiple_of(8));
+        let bits = bits.ok_or_else(|| unsupported(kind))?;
+
+        word = match item.strip_prefix("0x") {
+            Some(hex) if hex.len() <= 64 => {
+                let padded = format!("{:0>64}", hex);
+                decode_hex(&padded, 32)
+                    .ok_or_else(invalid)?
+                    .try_into()
+                    .unwrap()
+            }
+            Some(_) => return Err(invalid()),
+            None => parse_decimal(item).ok_or_else(invalid)?,
+        };
+        // Everything above the type's width has to be zero
+        if word[..32 - bits / 8].iter().any(|byte| *byte != 0) {
+            return Err(invalid());
+        }
+    } else if let Some(size) = kind.strip_prefix("bytes") {
+        let size = size.parse::<usize>().ok().filter(|s| *s > 0 && *s <= 32);
+        let size = size.ok_or_else(|| unsupported(kind))?;
+
+        // Fixed-size bytes are left-aligned
+        let bytes =
+            decode_hex(item.strip_prefix("0x").ok_or_else(invalid)?, size).ok_or_else(invalid)?;
+        word[..size].copy_from_slice(&bytes);
+    } else {
+        return Err(unsupported(kind));
+    }
+
+    Ok(word)
+}
+
+fn unsupported(kind: &str) -> MerkleError {
+    MerkleError::InvalidLeafValue(format!("unsupported leaf encoding type {:?}", kind))
+}
+
+// Decodes exactly `len` bytes of hex, or None.
+fn decode_hex(hex: &str, len: usize) -> Option<Vec<u8>> {
+    // from_str_radix alone would also take a sign, e.g. "+f"
+    if hex.len() != len * 2 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..len)
+        .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+        .collect()
+}
+
+// Parses a non-negative decimal integer into a 256-bit big-endian word, or None if it doesn't fit.
+fn parse_decimal(digits: &str) -> Option<[u8; 32]> {
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut word = [0u8; 32];
+    for digit in digits.chars() {
+        let mut carry = digit.to_digit(10)?;
+        // word = word * 10 + digit, from the lowest byte up
+        for byte in word.iter_mut().rev() {
+            let next = *byte as u32 * 10 + carry;
+            *byte = next as u8;
+            carry = next >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+
+    Some(word)
+}
+
+// "0x"-prefixed hex, the way the JS package prints hashes.
+fn to_prefixed_hex(hash: &[u8; 32]) -> String {
+    format!("0x{}", to_hex(hash))
+}
+
+impl StandardMerkleTree {
+    /// Same as the JS `StandardMerkleTree.of(values, leafEncoding)`, for the static types `abi_encode` supports.
+    /// Returns an Error if there are no values or one of them doesn't fit its type.
+    pub fn of(values: Vec<Vec<String>>, leaf_encoding: Vec<String>) -> Result<Self, MerkleError> {
+        let encoded = values
+            .into_iter()
+            .map(|value| {
+                let bytes = abi_encode(&leaf_encoding, &value)?;
+                Ok((value, bytes))
+            })
+            .collect::<Result<Vec<_>, MerkleError>>()?;
+
+        Self::from_encoded(encoded, leaf_encoding)
+    }
+
+    /// Builds the tree from values that were already ABI-encoded elsewhere (e.g. with dynamic types).
+    /// Each entry pairs the value as it should appear in the dump with its `abi.encode` bytes.
+    pub fn from_encoded(
+        values: Vec<(Vec<String>, Vec<u8>)>,
+        leaf_encoding: Vec<String>,
+    ) -> Result<Self, MerkleError> {
+        if values.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        let leaves: Vec<[u8; 32]> = values
+            .iter()
+            .map(|(_, encoded)| standard_leaf_hash(encoded))
+            .collect();
+
+        // 1. Sort the leaves by hash, like the JS package does by default
+        let mut order: Vec<usize> = (0..leaves.len()).collect();
+        order.sort_by(|a, b| leaves[*a].cmp(&leaves[*b]));
+
+        // 2. Place them at the end of the array, smallest hash last
+        let tree_len = 2 * leaves.len() - 1;
+        let mut tree = vec![[0u8; 32]; tree_len];
+        let mut tree_indices = vec![0; leaves.len()];
+        for (position, &value_index) in order.iter().enumerate() {
+            let tree_index = tree_len - 1 - position;
+            tree[tree_index] = leaves[value_index];
+            tree_indices[value_index] = tree_index;
+        }
+
+        // 3. Fill in the internal nodes from the back, so both children are always ready
+        for i in (0..leaves.len() - 1).rev() {
+            tree[i] = StandardHasher::hash_nodes(&tree[2 * i + 1], &tree[2 * i + 2]);
+        }
+
+        let values = values
+            .into_iter()
+            .zip(tree_indices)
+            .map(|((value, _), tree_index)| StandardValue { value, tree_index })
+            .collect();
+
+        Ok(StandardMerkleTree {
+            tree,
+            values,
+            leaf_encoding,
+        })
+    }
+
+    /// The raw 32-byte Merkle Root.
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        &self.tree[0]
+    }
+
+    /// The Merkle Root as a 64-character hex string (the JS package adds a `0x` in front).
+    pub fn root_hex(&self) -> String {
+        to_hex(self.root_bytes())
+    }
+
+    /// The values in the order they were given.
+    pub fn values(&self) -> &[StandardValue] {
+        &self.values
+    }
+
+    /// The Solidity types of each value, e.g. `["address", "uint256"]`.
+    pub fn leaf_encoding(&self) -> &[String] {
+        &self.leaf_encoding
+    }
+
+    /// The leaf of the value at `value_index`.
+    pub fn leaf_hash(&self, value_index: usize) -> Result<[u8; 32], MerkleError> {
+        let value = self
+            .values
+            .get(value_index)
+            .ok_or(MerkleError::IndexOutOfBounds {
+                index: value_index,
+                len: self.values.len(),
+            })?;
+        Ok(self.tree[value.tree_index])
+    }
+
+    /// Same as the JS `tree.getProof(i)`: the sibling hashes from the leaf of value `value_index` up to the root.
+    /// Check it with `verify_sorted_proof_with::<Keccak256Hasher>` or `MerkleProof.verify` on chain.
+    pub fn get_proof(&self, value_index: usize) -> Result<Vec<[u8; 32]>, MerkleError> {
+        let value = self
+            .values
+            .get(value_index)
+            .ok_or(MerkleError::IndexOutOfBounds {
+                index: value_index,
+                len: self.values.len(),
+            })?;
+
+        let mut proof = Vec::new();
+        let mut index = value.tree_index;
+        while index > 0 {
+            // Left children have odd indices, right children even ones
+            let sibling = if index.is_multiple_of(2) {
+                index - 1
+            } else {
+                index + 1
+            };
+            proof.push(self.tree[sibling]);
+            index = (index - 1) / 2;
+        }
+
+        Ok(proof)
+    }
+
+    /// Checks `proof` for the value at `value_index` against this tree's root.
+    pub fn verify(&self, value_index: usize, proof: &[[u8; 32]]) -> bool {
+        match self.leaf_hash(value_index) {
+            Ok(leaf) => {
+                verify_sorted_proof_with::<Keccak256Hasher>(self.root_bytes(), &leaf, proof)
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Same as the JS `tree.dump()`.
+    pub fn dump(&self) -> StandardTreeDump {
+        StandardTreeDump {
+            format: STANDARD_FORMAT.to_string(),
+            leaf_encoding: self.leaf_encoding.clone(),
+            tree: self.tree.iter().map(to_prefixed_hex).collect(),
+            values: self.values.clone(),
+        }
+    }
+
+    /// Same as the JS `StandardMerkleTree.load(dump)`, and just as strict:
+    /// every internal node is re-hashed and every value is re-encoded and checked against its leaf.
+    /// Dumps with leaf types `abi_encode` doesn't support are rejected.
+    pub fn load(dump: StandardTreeDump) -> Result<Self, MerkleError> {
+        if dump.format != STANDARD_FORMAT {
+            return Err(MerkleError::InvalidLayers(format!(
+                "unknown dump format {:?}",
+                dump.format
+            )));
+        }
+        if dump.values.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        // 1. Decode the hashes, with or without the 0x prefix
+        let tree = dump
+            .tree
+            .iter()
+            .map(|hex| from_hex(hex.strip_prefix("0x").unwrap_or(hex)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 2. The array must hold exactly one leaf per value, plus the nodes above them
+        let leaf_count = dump.values.len();
+        if tree.len() != 2 * leaf_count - 1 {
+            return Err(MerkleError::InvalidLayers(format!(
+                "{} values need {} tree nodes, got {}",
+                leaf_count,
+                2 * leaf_count - 1,
+                tree.len()
+            )));
+        }
+        for i in 0..leaf_count - 1 {
+            if tree[i] != StandardHasher::hash_nodes(&tree[2 * i + 1], &tree[2 * i + 2]) {
+                return Err(MerkleError::InvalidLayers(format!(
+                    "node {} does not match its children",
+                    i
+                )));
+            }
+        }
+
+        // 3. Every value must point at its own leaf, and no two values at the same one
+        let mut seen = vec![false; tree.len()];
+        for value in &dump.values {
+            let index = value.tree_index;
+            if index < leaf_count - 1 || index >= tree.len() || seen[index] {
+                return Err(MerkleError::InvalidLayers(format!(
+                    "tree index {} is not a free leaf",
+                    index
+                )));
+            }
+            seen[index] = true;
+
+            let encoded = abi_encode(&dump.leaf_encoding, &value.value)?;
+            if tree[index] != standard_leaf_hash(&encoded) {
+                return Err(MerkleError::InvalidLayers(format!(
+                    "leaf {} does not match its value",
+                    index
+                )));
+            }
+        }
+
+        Ok(StandardMerkleTree {
+            tree,
+            values: dump.values,
+            leaf_encoding: dump.leaf_encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn airdrop() -> StandardMerkleTree {
+        let values = vec![
+            vec![
+                "0x1111111111111111111111111111111111111111".to_string(),
+                "5000000000000000000".to_string(),
+            ],
+            vec![
+                "0x2222222222222222222222222222222222222222".to_string(),
+                "2500000000000000000".to_string(),
+            ],
+        ];
+        StandardMerkleTree::of(values, vec!["address".to_string(), "uint256".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_matches_openzeppelin_readme_root() {
+        // The example from the @openzeppelin/merkle-tree README, which prints this root
+        let tree = airdrop();
+        assert_eq!(
+            tree.root_hex(),
+            "d4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77"
+        );
+
+        for i in 0..2 {
+            let proof = tree.get_proof(i).unwrap();
+            assert_eq!(proof.len(), 1);
+            assert!(tree.verify(i, &proof));
+        }
+        assert_eq!(
+            tree.get_proof(2),
+            Err(MerkleError::IndexOutOfBounds { index: 2, len: 2 })
+        );
+    }
+
+    #[test]
+    fn test_proofs_for_uneven_tree() {
+        let values: Vec<Vec<String>> = (1..=5).map(|i| vec![i.to_string()]).collect();
+        let tree = StandardMerkleTree::of(values, vec!["uint256".to_string()]).unwrap();
+
+        for i in 0..5 {
+            let proof = tree.get_proof(i).unwrap();
+            let leaf = tree.leaf_hash(i).unwrap();
+            assert!(verify_sorted_proof_with::<Keccak256Hasher>(
+                tree.root_bytes(),
+                &leaf,
+                &proof
+            ));
+
+            let mut tampered = proof.clone();
+            tampered[0][31] ^= 1;
+            assert!(!tree.verify(i, &tampered));
+        }
+    }
+
+    #[test]
+    fn test_abi_encode_static_types() {
+        let kinds: Vec<String> = ["address", "bool", "uint8", "bytes4"]
+            .iter()
+            .map(|kind| kind.to_string())
+            .collect();
+        let value: Vec<String> = [
+            "0x00000000000000000000000000000000000000ff",
+            "true",
+            "0x2a",
+            "0xdeadbeef",
+        ]
+        .iter()
+        .map(|item| item.to_string())
+        .collect();
+        let encoded = abi_encode(&kinds, &value).unwrap();
+        assert_eq!(encoded.len(), 4 * 32);
+        assert_eq!(encoded[31], 0xff);
+        assert_eq!(encoded[63], 1);
+        assert_eq!(encoded[95], 42);
+        assert_eq!(&encoded[96..100], &[0xde, 0xad, 0xbe, 0xef]);
+
+        // Values that don't fit their type are rejected
+        let uint8 = vec!["uint8".to_string()];
+        assert!(abi_encode(&uint8, &["256".to_string()]).is_err());
+        assert!(abi_encode(&["address".to_string()], &["0x1234".to_string()]).is_err());
+        let signed = format!("0x{}", "+f".repeat(20));
+        assert!(abi_encode(&["address".to_string()], &[signed]).is_err());
+        assert!(abi_encode(&["bytes1".to_string()], &["0x+f".to_string()]).is_err());
+        assert!(abi_encode(&["string".to_string()], &["hi".to_string()]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_load_dump() {
+        // Dump of the README example in the JS package's format
+        let json = r#"{
+            "format": "standard-v1",
+            "leafEncoding": ["address", "uint256"],
+            "tree": [
+                "0xd4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77",
+                "0xeb02c421cfa48976e66dfb29120745909ea3a0f843456c263cf8f1253483e283",
+                "0xb92c48e9d7abe27fd8dfd6b5dfdbfb1c9a463f80c712b66f3a5180a090cccafc"
+            ],
+            "values": [
+                {"value": ["0x1111111111111111111111111111111111111111", "5000000000000000000"], "treeIndex": 1},
+                {"value": ["0x2222222222222222222222222222222222222222", "2500000000000000000"], "treeIndex": 2}
+            ]
+        }"#;
+        let dump: StandardTreeDump = serde_json::from_str(json).unwrap();
+        let tree = StandardMerkleTree::load(dump.clone()).unwrap();
+        assert_eq!(tree, airdrop());
+        assert_eq!(tree.dump(), dump);
+
+        // Hashes must be plain hex digits: "+e" would otherwise decode to the same byte as "0e"
+        let mut signed = dump.clone();
+        assert_eq!(&signed.tree[0][34..36], "0e");
+        signed.tree[0].replace_range(34..35, "+");
+        assert!(StandardMerkleTree::load(signed).is_err());
+
+        // A value that doesn't match its leaf is caught
+        let mut tampered = dump;
+        tampered.values[0].value[1] = "6000000000000000000".to_string();
+        assert!(StandardMerkleTree::load(tampered).is_err());
+    }
+}
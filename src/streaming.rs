@@ -0,0 +1,127 @@
+// Streaming roots: the root of a tree over any number of items without ever holding its layers.
+// Only the roots of the completed perfect subtrees are kept, at most one per level (O(log n) memory).
+
+use crate::{
+    Hashable, MerkleError, MerkleHasher, PaddingStrategy, Sha256Hasher, hash_pair_with, lone_parent,
+};
+use alloc::vec::Vec;
+
+/// The root `MerkleTree::new` would give for the same items, computed in one pass over `iter`.
+/// Items are hashed and dropped as they come, so e.g. a file reader or database cursor can be passed straight in.
+/// Returns an Error if the iterator is empty.
+pub fn compute_root<T: Hashable, I: IntoIterator<Item = T>>(
+    iter: I,
+) -> Result<[u8; 32], MerkleError> {
+    compute_root_with::<Sha256Hasher, T, I>(iter, PaddingStrategy::default())
+}
+
+/// Same as `compute_root`, for trees built with another hasher or `PaddingStrategy`,
+/// e.g. `compute_root_with::<DomainSeparated, _, _>(items, PaddingStrategy::PromoteOdd)` for an RFC 6962 root.
+pub fn compute_root_with<H: MerkleHasher, T: Hashable, I: IntoIterator<Item = T>>(
+    iter: I,
+    padding: PaddingStrategy,
+) -> Result<[u8; 32], MerkleError> {
+    fold_leaf_hashes::<H>(iter.into_iter().map(|item| item.hash_with::<H>()), padding)
+        .map(|folded| folded.root)
+}
+
+// The outcome of folding a run of leaf hashes.
+pub(crate) struct Folded {
+    pub(crate) root: [u8; 32],
+    // Levels above the leaves, same as `MerkleTree::depth` for a tree over just these leaves.
+    pub(crate) level: usize,
+    // The most subtrees that were pending at once. Only the tests read it, to check the memory bound.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) most_pending: usize,
+}
+
+// The streaming algorithm itself, over leaf hashes that are already computed (`LazyMerkleTree` reuses it).
+pub(crate) fn fold_leaf_hashes<H: MerkleHasher>(
+    hashes: impl IntoIterator<Item = [u8; 32]>,
+    padding: PaddingStrategy,
+) -> Result<Folded, MerkleError> {
+    // (level, root) of every completed subtree, levels strictly falling towards the top of the stack
+    let mut peaks: Vec<(usize, [u8; 32])> = Vec::new();
+    let mut most_pending = 0;
+
+    // 1. Push each leaf and merge while the top two subtrees are the same size
+    for hash in hashes {
+        let mut node = (0, hash);
+        while let Some(&(level, left)) = peaks.last() {
+            if level != node.0 {
+                break;
+            }
+            peaks.pop();
+            node = (level + 1, hash_pair_with::<H>(&left, &node.1));
+        }
+        peaks.push(node);
+        most_pending = most_pending.max(peaks.len());
+    }
+
+    // 2. Close the ragged right edge: the smallest subtree is the lone last node of every level
+    //    up to the next peak, so it goes through the padding until it is that peak's size, then merges into it
+    let (mut level, mut root) = peaks.pop().ok_or(MerkleError::EmptyInput)?;
+    while let Some((left_level, left)) = peaks.pop() {
+        while level < left_level {
+            root = lone_parent::<H>(&root, padding);
+            level += 1;
+        }
+        root = hash_pair_with::<H>(&left, &root);
+        level += 1;
+    }
+
+    Ok(Folded {
+        root,
+        level,
+        most_pending,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainSeparated, MerkleTree};
+    use alloc::string::{String, ToString};
+
+    proptest::proptest! {
+        // Big trees are slow to build in debug builds, so fewer cases than the default 256
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_matches_merkle_tree(size in 1usize..3000, strategy in 0usize..4) {
+            let padding = [
+                PaddingStrategy::DuplicateSelf,
+                PaddingStrategy::PromoteOdd,
+                PaddingStrategy::FixedZeroHash,
+                PaddingStrategy::Custom([7u8; 32]),
+            ][strategy];
+            let items = || (0..size).map(|i| i.to_string());
+
+            let tree = MerkleTree::<String, DomainSeparated>::with_padding(
+                items().collect(),
+                padding,
+            )
+            .unwrap();
+            let folded = fold_leaf_hashes::<DomainSeparated>(tree.leaves().iter().copied(), padding)
+                .unwrap();
+            proptest::prop_assert_eq!(
+                &compute_root_with::<DomainSeparated, _, _>(items(), padding).unwrap(),
+                tree.root_bytes()
+            );
+            proptest::prop_assert_eq!(folded.level, tree.depth());
+            // Never more than one pending subtree per level
+            proptest::prop_assert!(folded.most_pending <= tree.depth() + 1);
+        }
+    }
+
+    #[test]
+    fn test_default_root_and_empty_input() {
+        let data: Vec<String> = (0..11).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data.clone()).unwrap();
+        assert_eq!(&compute_root(data).unwrap(), tree.root_bytes());
+        assert_eq!(
+            compute_root(core::iter::empty::<String>()),
+            Err(MerkleError::EmptyInput)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+// Sum Merkle trees: every node carries the total of the values below it next to its hash,
+// and the hash commits to both children's sums, so the root proves the grand total (e.g. proof of reserves).
+
+use crate::{Hashable, MerkleError, MerkleHasher, ProofDirection, Sha256Hasher, to_hex};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Add;
+
+/// A Merkle Tree whose nodes are `(sum, hash)` pairs.
+/// A leaf `(item, value)` becomes `(value, H(item_hash || value_hash))`, and a parent is
+/// `(left_sum + right_sum, H(left_sum_hash || left_hash || right_sum_hash || right_hash))`,
+/// where a sum's hash is its `Hashable` leaf hash (big-endian bytes for the integer types).
+///
+/// A lone last node is carried up unchanged instead of being paired with itself, so no value is counted twice.
+/// Sums use `V`'s `+`, so an overflowing integer panics in debug builds like any other addition.
+///
+/// With a signed `V`, a negative leaf lowers every total above it, so a prover could hide liabilities behind it
+/// and still hand out proofs that verify. Use an unsigned `V` for proof of reserves, or have verifiers check
+/// `SumProof::sums_are_non_negative` next to `verify`.
+#[derive(Debug)]
+pub struct SumMerkleTree<
+    T: Hashable,
+    V: Add<Output = V> + Copy + Hashable,
+    H: MerkleHasher = Sha256Hasher,
+> {
+    // layers[0] = leaves, layers[last] = root, like `MerkleTree`.
+    layers: Vec<Vec<(V, [u8; 32])>>,
+    // Marker to link the tree to type T and hasher H without storing either.
+    _marker: PhantomData<(T, H)>,
+}
+
+/// Proof that one leaf with its value is part of a `SumMerkleTree` with a given total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumProof<V> {
+    /// Position of the leaf in the bottom layer.
+    pub leaf_index: usize,
+    /// The leaf's value.
+    pub leaf_sum: V,
+    /// The leaf's hash, which already commits to `leaf_sum`.
+    pub leaf_hash: [u8; 32],
+    /// Sibling nodes from the leaf layer up. Carried-up nodes have no step.
+    pub siblings: Vec<SumProofNode<V>>,
+    /// The total at the root of the tree the proof was taken from.
+    pub root_sum: V,
+    /// The root of the tree the proof was taken from.
+    pub root: [u8; 32],
+}
+
+/// One step of a `SumProof`: a sibling's sum and hash, and the side it goes on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumProofNode<V> {
+    pub direction: ProofDirection,
+    pub sum: V,
+    pub hash: [u8; 32],
+}
+
+impl<T: Hashable, V: Add<Output = V> + Copy + Hashable> SumMerkleTree<T, V> {
+    /// Creates a SHA-256 sum tree from `(item, value)` pairs. Returns an Error if the data is empty.
+    pub fn new(data: Vec<(T, V)>) -> Result<Self, MerkleError> {
+        Self::with_hasher(data)
+    }
+}
+
+impl<T: Hashable, V: Add<Output = V> + Copy + Hashable, H: MerkleHasher> SumMerkleTree<T, V, H> {
+    /// Same as `new`, hashing with `H`.
+    pub fn with_hasher(data: Vec<(T, V)>) -> Result<Self, MerkleError> {
+        if data.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        // 1. Bind every value into its leaf hash, then pair nodes up until one root is left
+        let mut layers = Vec::new();
+        layers.push(
+            data.iter()
+                .map(|(item, value)| (*value, leaf_hash::<H, _>(&item.hash_with::<H>(), value)))
+                .collect::<Vec<_>>(),
+        );
+        while layers[layers.len() - 1].len() > 1 {
+            let layer = &layers[layers.len() - 1];
+            let next_layer = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => combine::<H, V>(left, right),
+                    // The odd node out keeps its sum and hash
+                    _ => pair[0],
+                })
+                .collect();
+            layers.push(next_layer);
+        }
+
+        Ok(SumMerkleTree {
+            layers,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The total of every leaf value.
+    pub fn root_sum(&self) -> V {
+        self.layers[self.layers.len() - 1][0].0
+    }
+
+    /// The raw 32-byte Merkle Root, which commits to `root_sum` as well.
+    pub fn root_bytes(&self) -> &[u8; 32] {
+        &self.layers[self.layers.len() - 1][0].1
+    }
+
+    /// Returns the Merkle Root as a 64-character hex string.
+    pub fn root_hex(&self) -> String {
+        to_hex(self.root_bytes())
+    }
+
+    /// Number of leaves the tree covers.
+    pub fn leaf_count(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    /// The `(value, hash)` pairs of the leaf layer, in input order.
+    pub fn leaves(&self) -> &[(V, [u8; 32])] {
+        &self.layers[0]
+    }
+
+    /// Builds a `SumProof` for the leaf at `leaf_index`.
+    pub fn get_proof(&self, leaf_index: usize) -> Result<SumProof<V>, MerkleError> {
+        let leaf_count = self.layers[0].len();
+        if leaf_index >= leaf_count {
+            return Err(MerkleError::IndexOutOfBounds {
+                index: leaf_index,
+                len: leaf_count,
+            });
+        }
+
+        let mut siblings = Vec::new();
+        let mut index = leaf_index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            let (direction, sibling) = if index.is_multiple_of(2) {
+                (ProofDirection::Right, layer.get(index + 1))
+            } else {
+                (ProofDirection::Left, layer.get(index - 1))
+            };
+            // No right neighbour: we were carried up, so there is no step
+            if let Some(&(sum, hash)) = sibling {
+                siblings.push(SumProofNode {
+                    direction,
+                    sum,
+                    hash,
+                });
+            }
+            index /= 2;
+        }
+
+        let (leaf_sum, leaf_hash) = self.layers[0][leaf_index];
+        Ok(SumProof {
+            leaf_index,
+            leaf_sum,
+            leaf_hash,
+            siblings,
+            root_sum: self.root_sum(),
+            root: *self.root_bytes(),
+        })
+    }
+}
+
+impl<V: Add<Output = V> + Copy + Hashable + PartialEq> SumProof<V> {
+    /// Recomputes the root and the total from the leaf and the siblings and checks both against the proof's own.
+    /// Compare `leaf_hash` with your own item and value, and `root` with a root you trust, before relying on it.
+    pub fn verify(&self) -> bool {
+        self.verify_with::<Sha256Hasher>()
+    }
+
+    /// Same as `verify`, for trees built with a hasher other than SHA-256.
+    pub fn verify_with<H: MerkleHasher>(&self) -> bool {
+        let mut current = (self.leaf_sum, self.leaf_hash);
+        for node in &self.siblings {
+            let sibling = (node.sum, node.hash);
+            current = match node.direction {
+                ProofDirection::Left => combine::<H, V>(&sibling, &current),
+                ProofDirection::Right => combine::<H, V>(&current, &sibling),
+            };
+        }
+        current.0 == self.root_sum && current.1 == self.root
+    }
+
+    /// Checks that `item` with `value` is the proven leaf. Call it before `verify`.
+    pub fn proves<T: Hashable>(&self, item: &T, value: V) -> bool {
+        self.proves_with::<Sha256Hasher, T>(item, value)
+    }
+
+    /// Same as `proves`, for trees built with a hasher other than SHA-256.
+    pub fn proves_with<H: MerkleHasher, T: Hashable>(&self, item: &T, value: V) -> bool {
+        self.leaf_sum == value
+            && self.leaf_hash == leaf_hash::<H, V>(&item.hash_with::<H>(), &value)
+    }
+}
+
+impl<V: PartialOrd + Default> SumProof<V> {
+    /// Whether `leaf_sum` and every sibling's sum are at least `V::default()` (zero for the integer types).
+    /// `verify` alone accepts negative sums, because they hash like any other value; with a signed `V`,
+    /// check this as well so a negative node can't lower the total the proof is checked against.
+    pub fn sums_are_non_negative(&self) -> bool {
+        let zero = V::default();
+        self.leaf_sum >= zero && self.siblings.iter().all(|node| node.sum >= zero)
+    }
+}
+
+// A leaf commits to the item and to its value.
+fn leaf_hash<H: MerkleHasher, V: Hashable>(item_hash: &[u8; 32], value: &V) -> [u8; 32] {
+    H::hash_parts(&[item_hash, &value.hash_with::<H>()])
+}
+
+// A parent commits to both children's sums and hashes, so neither can be swapped out on its own.
+fn combine<H: MerkleHasher, V: Add<Output = V> + Copy + Hashable>(
+    left: &(V, [u8; 32]),
+    right: &(V, [u8; 32]),
+) -> (V, [u8; 32]) {
+    let hash = H::hash_parts(&[
+        &left.0.hash_with::<H>(),
+        &left.1,
+        &right.0.hash_with::<H>(),
+        &right.1,
+    ]);
+    (left.0 + right.0, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DomainSeparated;
+    use alloc::string::ToString;
+
+    fn accounts(values: &[u64]) -> Vec<(String, u64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (alloc::format!("account-{}", i), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_root_sum_and_proofs() {
+        let tree = SumMerkleTree::new(accounts(&[10, 20, 30])).unwrap();
+        assert_eq!(tree.root_sum(), 60);
+        assert_eq!(tree.leaf_count(), 3);
+
+        for i in 0..3 {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(proof.verify());
+            assert!(proof.proves(&alloc::format!("account-{}", i), [10, 20, 30][i]));
+            assert_eq!(proof.root_sum, 60);
+        }
+        // The third leaf is carried up, so it only needs the first pair
+        assert_eq!(tree.get_proof(2).unwrap().siblings.len(), 1);
+        assert!(tree.get_proof(3).is_err());
+    }
+
+    #[test]
+    fn test_sums_are_committed() {
+        let tree = SumMerkleTree::new(accounts(&[10, 20, 30, 40, 50])).unwrap();
+        assert_eq!(tree.root_sum(), 150);
+
+        // Same items, one value changed: the root moves too
+        let other = SumMerkleTree::new(accounts(&[10, 20, 31, 40, 50])).unwrap();
+        assert_ne!(tree.root_bytes(), other.root_bytes());
+
+        // Hiding part of a sibling's total breaks the proof even with the root sum lowered to match
+        let mut proof = tree.get_proof(4).unwrap();
+        proof.siblings[0].sum -= 5;
+        proof.root_sum -= 5;
+        assert!(!proof.verify());
+
+        // Claiming another value for the leaf is caught by `proves`
+        let proof = tree.get_proof(1).unwrap();
+        assert!(!proof.proves(&"account-1".to_string(), 21));
+    }
+
+    #[test]
+    fn test_negative_values_lower_the_total() {
+        // A -60 entry hides 60 of the 100 owed to the first account, and both proofs still verify
+        let tree = SumMerkleTree::new(vec![
+            ("alice".to_string(), 100i64),
+            ("bob".to_string(), -60),
+        ])
+        .unwrap();
+        assert_eq!(tree.root_sum(), 40);
+        let alice = tree.get_proof(0).unwrap();
+        assert!(alice.verify() && alice.proves(&"alice".to_string(), 100));
+        let bob = tree.get_proof(1).unwrap();
+        assert!(bob.verify());
+
+        // The sign check is what catches it, from either side
+        assert!(!alice.sums_are_non_negative());
+        assert!(!bob.sums_are_non_negative());
+
+        let honest =
+            SumMerkleTree::new(vec![("alice".to_string(), 100i64), ("bob".to_string(), 60)])
+                .unwrap();
+        assert!((0..2).all(|i| honest.get_proof(i).unwrap().sums_are_non_negative()));
+        assert!(
+            SumMerkleTree::new(accounts(&[0, 5]))
+                .unwrap()
+                .get_proof(0)
+                .unwrap()
+                .sums_are_non_negative()
+        );
+    }
+
+    #[test]
+    fn test_other_hasher_and_empty_input() {
+        let tree =
+            SumMerkleTree::<String, u64, DomainSeparated>::with_hasher(accounts(&[1; 7])).unwrap();
+        assert_eq!(tree.root_sum(), 7);
+        let proof = tree.get_proof(6).unwrap();
+        assert!(proof.verify_with::<DomainSeparated>());
+        assert!(!proof.verify());
+
+        assert_eq!(
+            SumMerkleTree::<String, u64>::new(vec![]).err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+}
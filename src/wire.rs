@@ -0,0 +1,724 @@
+// Compact binary proofs and trees, for sending them in bulk where hex JSON would double the size.
+//
+// Proof layout, all sizes in bytes:
+//
+//   offset  size    field
+//   0       8       leaf_index, unsigned little-endian
+//   8       1       N, the number of siblings
+//   9       33 * N  one entry per sibling, leaf level first:
+//                     32 bytes of hash, then 1 direction byte (0 = Left, 1 = Right)
+//
+// Nothing else is sent: the verifier already knows the leaf and the root it expects, and
+// `leaf_count` only matters for `verify_padded`. A buffer of any other length is rejected.
+//
+// Compact proof layout, for verifiers where every byte counts (a depth-20 proof is 647 bytes):
+//
+//   size           field
+//   1 to 10        leaf_index as an unsigned LEB128 varint (7 bits per byte, low bits first)
+//   1              N, the number of siblings
+//   ceil(N / 8)    directions, one bit per sibling, leaf level in the lowest bit of the first byte
+//                    (0 = Left, 1 = Right); unused high bits of the last byte must be 0
+//   32 * N         the sibling hashes, leaf level first
+//
+// The hex string form is one version byte (1) followed by the compact layout, all as lowercase hex,
+// so a proof can be pasted into a terminal or a ticket as a single token.
+//
+// Tree layout:
+//
+//   offset  size    field
+//   0       4       magic, the ASCII bytes "MRKT"
+//   4       1       format version, currently 1
+//   5       4       hasher id: the first 4 bytes of H(H_leaf("merkle-tree hasher id"), 32 zero bytes)
+//   9       1       padding (0 = DuplicateSelf, 1 = PromoteOdd, 2 = FixedZeroHash, 3 = Custom)
+//   10      32      the Custom padding hash, zeros for the other strategies
+//   42      8       n, the leaf count, unsigned little-endian
+//   50      32 * m  every layer's digests back to back, leaves first, the root last
+//
+// m is fixed by n (ceil(n / 2^i) nodes on level i), so the length is checked before anything is allocated.
+// The hasher id is computed rather than assigned, so any `MerkleHasher`, wrapped or custom, gets one and a tree
+// can't be read back with a different hasher than it was written with.
+
+use crate::arena::Layers;
+use crate::leaf_index::LeafIndex;
+use crate::{
+    Hashable, MerkleError, MerkleHasher, MerkleProof, MerkleTree, PaddingStrategy, ProofDirection,
+    ProofNode, check_layers, to_hex,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+const HEADER_LEN: usize = 9;
+const STEP_LEN: usize = 33;
+
+const HEX_STRING_VERSION: u8 = 1;
+
+const TREE_MAGIC: [u8; 4] = *b"MRKT";
+const TREE_VERSION: u8 = 1;
+const TREE_HEADER_LEN: usize = 50;
+
+impl MerkleProof {
+    /// Encodes `leaf_index` and the siblings in the layout above: 9 + 33 bytes per level.
+    /// Panics if the proof has more than 255 siblings, which no tree that fits in memory produces.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let steps = u8::try_from(self.siblings.len()).expect("a proof has at most 255 siblings");
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + STEP_LEN * self.siblings.len());
+        bytes.extend_from_slice(&(self.leaf_index as u64).to_le_bytes());
+        bytes.push(steps);
+        for node in &self.siblings {
+            bytes.extend_from_slice(&node.hash);
+            bytes.push(match node.direction {
+                ProofDirection::Left => 0,
+                ProofDirection::Right => 1,
+            });
+        }
+        bytes
+    }
+
+    /// Parses a proof written by `to_bytes`. Returns an Error if the buffer is truncated, too long,
+    /// or has a direction byte other than 0 or 1.
+    /// `leaf_hash` and `root` are not part of the format and come back zeroed, `leaf_count` at 0:
+    /// set them to the values you expect before calling `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(MerkleError::InvalidProof(format!(
+                "{} bytes is shorter than the {}-byte header",
+                bytes.len(),
+                HEADER_LEN
+            )));
+        }
+
+        // 1. Header: the index must fit this platform's usize
+        let mut index = [0u8; 8];
+        index.copy_from_slice(&bytes[..8]);
+        let leaf_index = usize::try_from(u64::from_le_bytes(index))
+            .map_err(|_| MerkleError::InvalidProof("leaf index does not fit usize".to_string()))?;
+        let steps = bytes[8] as usize;
+
+        // 2. The rest must be exactly the announced number of entries
+        let expected = HEADER_LEN + STEP_LEN * steps;
+        if bytes.len() != expected {
+            return Err(MerkleError::InvalidProof(format!(
+                "{} siblings need {} bytes, got {}",
+                steps,
+                expected,
+                bytes.len()
+            )));
+        }
+
+        let mut siblings = Vec::with_capacity(steps);
+        for step in bytes[HEADER_LEN..].chunks_exact(STEP_LEN) {
+            let direction = match step[32] {
+                0 => ProofDirection::Left,
+                1 => ProofDirection::Right,
+                flag => {
+                    return Err(MerkleError::InvalidProof(format!(
+                        "unknown direction byte {}",
+                        flag
+                    )));
+                }
+            };
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&step[..32]);
+            siblings.push(ProofNode { direction, hash });
+        }
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count: 0,
+            leaf_hash: [0u8; 32],
+            siblings,
+            root: [0u8; 32],
+        })
+    }
+}
+
+impl<T: Hashable, H: MerkleHasher> MerkleTree<T, H> {
+    /// Encodes the whole tree in the layout above: a 50-byte header, then 32 bytes per node.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (padding, custom) = match self.padding {
+            PaddingStrategy::DuplicateSelf => (0, [0u8; 32]),
+            PaddingStrategy::PromoteOdd => (1, [0u8; 32]),
+            PaddingStrategy::FixedZeroHash => (2, [0u8; 32]),
+            PaddingStrategy::Custom(sibling) => (3, sibling),
+        };
+
+        let mut bytes = Vec::with_capacity(TREE_HEADER_LEN + 32 * self.node_count());
+        bytes.extend_from_slice(&TREE_MAGIC);
+        bytes.push(TREE_VERSION);
+        bytes.extend_from_slice(&hasher_id::<H>());
+        bytes.push(padding);
+        bytes.extend_from_slice(&custom);
+        bytes.extend_from_slice(&(self.leaf_count() as u64).to_le_bytes());
+        for layer in self.layers.iter() {
+            bytes.extend(layer.iter().flatten());
+        }
+        bytes
+    }
+
+    /// Reads a tree written by `to_bytes` and checks every parent against its children, like `from_layers`.
+    /// Returns `MerkleError::UnsupportedVersion` for a format version other than 1, `InvalidLayers` if the
+    /// header is malformed, the hasher id is not `H`'s, or the length doesn't match the leaf count.
+    /// A leaf count of 0 with no digests after the header reads back as an empty `with_capacity`-style tree.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        let tree = Self::from_bytes_unchecked(bytes)?;
+        // An empty tree has no root for the layers to funnel into, so there is nothing to check
+        if tree.leaf_count() > 0 {
+            check_layers::<H>(&tree.layers, tree.padding)?;
+        }
+        Ok(tree)
+    }
+
+    /// Same as `from_bytes` without re-hashing the layers, for bytes from storage you trust.
+    /// The header and length are still checked, but a flipped bit in a node goes unnoticed
+    /// and shows up as proofs that fail to verify.
+    pub fn from_bytes_unchecked(bytes: &[u8]) -> Result<Self, MerkleError> {
+        if bytes.len() < TREE_HEADER_LEN {
+            return Err(MerkleError::InvalidLayers(format!(
+                "{} bytes is shorter than the {}-byte header",
+                bytes.len(),
+                TREE_HEADER_LEN
+            )));
+        }
+
+        // 1. Header: magic, version and hasher have to be the ones this tree type writes
+        if bytes[..4] != TREE_MAGIC {
+            return Err(MerkleError::InvalidLayers(
+                "missing the tree magic bytes".to_string(),
+            ));
+        }
+        if bytes[4] != TREE_VERSION {
+            return Err(MerkleError::UnsupportedVersion(bytes[4] as u32));
+        }
+        if bytes[5..9] != hasher_id::<H>() {
+            return Err(MerkleError::InvalidLayers(
+                "tree was written with a different hasher".to_string(),
+            ));
+        }
+        let mut custom = [0u8; 32];
+        custom.copy_from_slice(&bytes[10..42]);
+        let padding = match bytes[9] {
+            0 => PaddingStrategy::DuplicateSelf,
+            1 => PaddingStrategy::PromoteOdd,
+            2 => PaddingStrategy::FixedZeroHash,
+            3 => PaddingStrategy::Custom(custom),
+            tag => {
+                return Err(MerkleError::InvalidLayers(format!(
+                    "unknown padding tag {}",
+                    tag
+                )));
+            }
+        };
+
+        // 2. The body must hold exactly the nodes a tree over n leaves has. n can't be larger than
+        //    the number of digests sent, which also keeps the arithmetic below from overflowing.
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&bytes[42..50]);
+        let digests = (bytes.len() - TREE_HEADER_LEN) / 32;
+        let leaf_count = u64::from_le_bytes(count);
+        if leaf_count > digests as u64 {
+            return Err(MerkleError::InvalidLayers(format!(
+                "{} leaves don't fit in {} bytes",
+                leaf_count,
+                bytes.len()
+            )));
+        }
+        let leaf_count = leaf_count as usize;
+        let expected = TREE_HEADER_LEN + 32 * node_count(leaf_count);
+        if bytes.len() != expected {
+            return Err(MerkleError::InvalidLayers(format!(
+                "a tree of {} leaves needs {} bytes, got {}",
+                leaf_count,
+                expected,
+                bytes.len()
+            )));
+        }
+
+        // 3. Only now allocate, and copy the digests in layer by layer
+        let mut layers = Layers::for_leaves(leaf_count);
+        let mut digests = bytes[TREE_HEADER_LEN..].chunks_exact(32);
+        for level in 0..layers.len() {
+            for (node, digest) in layers[level].iter_mut().zip(&mut digests) {
+                node.copy_from_slice(digest);
+            }
+        }
+
+        Ok(MerkleTree {
+            layers,
+            padding,
+            leaf_index: LeafIndex::new(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+// Fingerprint of a hasher: it covers the leaf hashing and the node order, so e.g. `SortedPairs` and
+// `DomainSeparated` wrappers get ids of their own. The JSON proof document reuses it.
+pub(crate) fn hasher_id<H: MerkleHasher>() -> [u8; 4] {
+    let probe = H::hash_nodes(&H::hash_leaf(b"merkle-tree hasher id"), &[0u8; 32]);
+    [probe[0], probe[1], probe[2], probe[3]]
+}
+
+// Nodes in a tree over `leaf_count` leaves, root included (at most 2n - 1).
+fn node_count(leaf_count: usize) -> usize {
+    let mut total = leaf_count;
+    let mut len = leaf_count;
+    while len > 1 {
+        len = len.div_ceil(2);
+        total += len;
+    }
+    total
+}
+
+impl MerkleProof {
+    /// Encodes the proof in the compact layout above: a varint index, packed directions, then raw hashes.
+    /// Panics if the proof has more than 255 siblings, like `to_bytes`.
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        let steps = u8::try_from(self.siblings.len()).expect("a proof has at most 255 siblings");
+
+        let mut bytes =
+            Vec::with_capacity(11 + self.siblings.len().div_ceil(8) + 32 * self.siblings.len());
+        let mut index = self.leaf_index as u64;
+        while index >= 0x80 {
+            bytes.push(index as u8 | 0x80);
+            index >>= 7;
+        }
+        bytes.push(index as u8);
+        bytes.push(steps);
+
+        let mut bits = vec![0u8; self.siblings.len().div_ceil(8)];
+        for (level, node) in self.siblings.iter().enumerate() {
+            if node.direction == ProofDirection::Right {
+                bits[level / 8] |= 1 << (level % 8);
+            }
+        }
+        bytes.extend_from_slice(&bits);
+        for node in &self.siblings {
+            bytes.extend_from_slice(&node.hash);
+        }
+        bytes
+    }
+
+    /// Parses a proof written by `to_compact_bytes`. Returns an Error if the varint is malformed,
+    /// the buffer is not exactly as long as the sibling count says, or an unused direction bit is set.
+    /// As with `from_bytes`, `leaf_hash` and `root` come back zeroed and `leaf_count` at 0.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        // 1. The varint: at most 10 bytes, and nothing past the 64th bit
+        let mut index = 0u64;
+        let mut read = 0;
+        loop {
+            let byte = *bytes
+                .get(read)
+                .ok_or_else(|| MerkleError::InvalidProof("leaf index is cut short".to_string()))?;
+            if read == 9 && byte > 1 {
+                return Err(MerkleError::InvalidProof(
+                    "leaf index does not fit 64 bits".to_string(),
+                ));
+            }
+            index |= u64::from(byte & 0x7f) << (7 * read);
+            read += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        let leaf_index = usize::try_from(index)
+            .map_err(|_| MerkleError::InvalidProof("leaf index does not fit usize".to_string()))?;
+
+        // 2. The sibling count fixes the size of everything after it
+        let steps = *bytes
+            .get(read)
+            .ok_or_else(|| MerkleError::InvalidProof("missing the sibling count".to_string()))?
+            as usize;
+        let (bits, hashes) = bytes[read + 1..]
+            .split_at_checked(steps.div_ceil(8))
+            .ok_or_else(|| {
+                MerkleError::InvalidProof(format!(
+                    "{} siblings need {} direction bytes",
+                    steps,
+                    steps.div_ceil(8)
+                ))
+            })?;
+        if hashes.len() != 32 * steps {
+            return Err(MerkleError::InvalidProof(format!(
+                "{} siblings need {} hash bytes, got {}",
+                steps,
+                32 * steps,
+                hashes.len()
+            )));
+        }
+        if !steps.is_multiple_of(8) && bits[bits.len() - 1] >> (steps % 8) != 0 {
+            return Err(MerkleError::InvalidProof(
+                "direction bits set past the last sibling".to_string(),
+            ));
+        }
+
+        let siblings = hashes
+            .chunks_exact(32)
+            .enumerate()
+            .map(|(level, hash)| {
+                let direction = if bits[level / 8] >> (level % 8) & 1 == 1 {
+                    ProofDirection::Right
+                } else {
+                    ProofDirection::Left
+                };
+                let mut node = ProofNode {
+                    direction,
+                    hash: [0u8; 32],
+                };
+                node.hash.copy_from_slice(hash);
+                node
+            })
+            .collect();
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_count: 0,
+            leaf_hash: [0u8; 32],
+            siblings,
+            root: [0u8; 32],
+        })
+    }
+}
+
+impl MerkleProof {
+    /// The proof as one hex token: a version byte, then `to_compact_bytes`.
+    pub fn to_hex_string(&self) -> String {
+        let mut bytes = vec![HEX_STRING_VERSION];
+        bytes.extend(self.to_compact_bytes());
+        to_hex(&bytes)
+    }
+
+    /// Parses a token written by `to_hex_string`. Whitespace anywhere and a leading `0x` are ignored.
+    /// Returns `MerkleError::UnsupportedVersion` for a version other than 1, `InvalidProof` for an odd number
+    /// of digits, a character that isn't hex, or a proof that doesn't parse.
+    /// As with `from_bytes`, `leaf_hash` and `root` come back zeroed and `leaf_count` at 0.
+    pub fn from_hex_string(text: &str) -> Result<Self, MerkleError> {
+        let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let digits = digits.strip_prefix("0x").unwrap_or(&digits);
+        if !digits.len().is_multiple_of(2) {
+            return Err(MerkleError::InvalidProof(format!(
+                "odd number of hex digits ({})",
+                digits.len()
+            )));
+        }
+
+        let bytes = digits
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                // Checked up front, since from_str_radix would also take a sign ("+1")
+                Some(pair)
+                    .filter(|pair| pair.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|pair| core::str::from_utf8(pair).ok())
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| {
+                        MerkleError::InvalidProof(format!(
+                            "not a hex byte: {:?}",
+                            String::from_utf8_lossy(pair)
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        match bytes.split_first() {
+            None => Err(MerkleError::InvalidProof("empty proof string".to_string())),
+            Some((&HEX_STRING_VERSION, rest)) => Self::from_compact_bytes(rest),
+            Some((&version, _)) => Err(MerkleError::UnsupportedVersion(version as u32)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DomainSeparated;
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let data: Vec<String> = (0..11).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(data).unwrap();
+
+        for i in 0..11 {
+            let proof = tree.get_proof(i).unwrap();
+            let bytes = proof.to_bytes();
+            assert_eq!(bytes.len(), 9 + 33 * proof.siblings.len());
+
+            let mut parsed = MerkleProof::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed.leaf_index, i);
+            assert_eq!(parsed.siblings, proof.siblings);
+
+            parsed.leaf_hash = proof.leaf_hash;
+            parsed.root = *tree.root_bytes();
+            assert!(parsed.verify());
+        }
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_malformed_buffers() {
+        let tree = MerkleTree::new((0..8).map(|i| i.to_string()).collect()).unwrap();
+        let bytes = tree.get_proof(5).unwrap().to_bytes();
+
+        // Every strict prefix is cut short somewhere
+        for len in 0..bytes.len() {
+            assert!(matches!(
+                MerkleProof::from_bytes(&bytes[..len]),
+                Err(MerkleError::InvalidProof(_))
+            ));
+        }
+
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(MerkleProof::from_bytes(&longer).is_err());
+
+        // A length byte claiming more siblings than were sent
+        let mut lying = bytes.clone();
+        lying[8] += 1;
+        assert!(MerkleProof::from_bytes(&lying).is_err());
+
+        let mut bad_flag = bytes;
+        bad_flag[9 + 32] = 2;
+        assert_eq!(
+            MerkleProof::from_bytes(&bad_flag).err(),
+            Some(MerkleError::InvalidProof(
+                "unknown direction byte 2".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_tree_bytes_round_trip() {
+        for (size, padding) in [
+            (1, PaddingStrategy::DuplicateSelf),
+            (13, PaddingStrategy::PromoteOdd),
+            (8, PaddingStrategy::FixedZeroHash),
+            (6, PaddingStrategy::Custom([9u8; 32])),
+        ] {
+            let tree = MerkleTree::<String>::with_padding(
+                (0..size).map(|i| i.to_string()).collect(),
+                padding,
+            )
+            .unwrap();
+            let bytes = tree.to_bytes();
+            assert_eq!(bytes.len(), 50 + 32 * tree.node_count());
+
+            let restored = MerkleTree::<String>::from_bytes(&bytes).unwrap();
+            assert_eq!(restored.layers(), tree.layers());
+            assert_eq!(restored.padding(), padding);
+            assert!(restored.get_proof(size - 1).unwrap().verify());
+        }
+    }
+
+    #[test]
+    fn test_empty_tree_bytes_round_trip() {
+        let empty = MerkleTree::<String>::with_capacity(4);
+        let bytes = empty.to_bytes();
+        assert_eq!(bytes.len(), 50);
+        assert_eq!(bytes[42..50], 0u64.to_le_bytes());
+
+        let mut restored = MerkleTree::<String>::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.leaf_count(), 0);
+        assert_eq!(restored.layers(), empty.layers());
+        assert_eq!(restored.to_bytes(), bytes);
+        assert_eq!(
+            MerkleTree::<String>::from_bytes_unchecked(&bytes)
+                .unwrap()
+                .leaf_count(),
+            0
+        );
+
+        // It grows like the original did
+        restored.push("a".to_string());
+        assert_eq!(restored, MerkleTree::new(vec!["a".to_string()]).unwrap());
+
+        // No leaves means no digests either
+        let mut trailing = bytes;
+        trailing.extend_from_slice(&[0u8; 32]);
+        assert!(MerkleTree::<String>::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_tree_bytes_reject_truncated_and_foreign_input() {
+        let tree = MerkleTree::<String>::new((0..5).map(|i| i.to_string()).collect()).unwrap();
+        let bytes = tree.to_bytes();
+
+        for len in 0..bytes.len() {
+            assert!(MerkleTree::<String>::from_bytes(&bytes[..len]).is_err());
+        }
+
+        // A huge leaf count is turned away before anything is allocated for it
+        let mut huge = bytes.clone();
+        huge[42..50].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            MerkleTree::<String>::from_bytes(&huge),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert_eq!(
+            MerkleTree::<String>::from_bytes(&version).err(),
+            Some(MerkleError::UnsupportedVersion(2))
+        );
+
+        assert!(MerkleTree::<String, DomainSeparated>::from_bytes(&bytes).is_err());
+
+        // A flipped bit in the root is only caught by the checked variant
+        let mut flipped = bytes;
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            MerkleTree::<String>::from_bytes(&flipped),
+            Err(MerkleError::InvalidLayers(_))
+        ));
+        let trusted = MerkleTree::<String>::from_bytes_unchecked(&flipped).unwrap();
+        assert_ne!(trusted.root_bytes(), tree.root_bytes());
+    }
+
+    #[test]
+    fn test_compact_bytes_round_trip() {
+        let tree = MerkleTree::new((0..300).map(|i| i.to_string()).collect()).unwrap();
+        for i in [0, 1, 127, 128, 299] {
+            let proof = tree.get_proof(i).unwrap();
+            let mut parsed = MerkleProof::from_compact_bytes(&proof.to_compact_bytes()).unwrap();
+            assert_eq!(parsed.leaf_index, i);
+            assert_eq!(parsed.siblings, proof.siblings);
+            parsed.leaf_hash = proof.leaf_hash;
+            parsed.root = proof.root;
+            assert!(parsed.verify());
+        }
+
+        // A depth-20 proof: a 3-byte varint, the count, 3 bytes of bits and 20 hashes
+        let deep = MerkleProof {
+            leaf_index: (1 << 20) - 1,
+            leaf_count: 0,
+            leaf_hash: [0u8; 32],
+            siblings: (0..20u8)
+                .map(|level| ProofNode {
+                    direction: if level % 3 == 0 {
+                        ProofDirection::Right
+                    } else {
+                        ProofDirection::Left
+                    },
+                    hash: [level; 32],
+                })
+                .collect(),
+            root: [0u8; 32],
+        };
+        let bytes = deep.to_compact_bytes();
+        assert_eq!(bytes.len(), 647);
+        assert_eq!(MerkleProof::from_compact_bytes(&bytes).unwrap(), deep);
+
+        // A single-leaf tree has an empty proof: just the index and a zero count
+        let single = MerkleTree::new(vec![String::from("a")]).unwrap();
+        assert_eq!(single.get_proof(0).unwrap().to_compact_bytes(), [0, 0]);
+        let huge = MerkleProof {
+            leaf_index: usize::MAX,
+            ..single.get_proof(0).unwrap()
+        };
+        assert_eq!(
+            MerkleProof::from_compact_bytes(&huge.to_compact_bytes())
+                .unwrap()
+                .leaf_index,
+            usize::MAX
+        );
+    }
+
+    #[test]
+    fn test_compact_bytes_reject_malformed_buffers() {
+        let tree = MerkleTree::new((0..11).map(|i| i.to_string()).collect()).unwrap();
+        let bytes = tree.get_proof(9).unwrap().to_compact_bytes();
+        // Index 9, 4 siblings, one byte of bits
+        assert_eq!(&bytes[..2], [9, 4]);
+
+        for len in 0..bytes.len() {
+            assert!(MerkleProof::from_compact_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(MerkleProof::from_compact_bytes(&trailing).is_err());
+
+        // A bit for a fifth sibling that was never sent
+        let mut extra_bit = bytes.clone();
+        extra_bit[2] |= 1 << 4;
+        assert_eq!(
+            MerkleProof::from_compact_bytes(&extra_bit).err(),
+            Some(MerkleError::InvalidProof(
+                "direction bits set past the last sibling".to_string()
+            ))
+        );
+
+        // A count that doesn't match the hashes that follow
+        let mut lying = bytes;
+        lying[1] = 3;
+        assert!(MerkleProof::from_compact_bytes(&lying).is_err());
+
+        // A varint that runs on past 64 bits
+        assert!(MerkleProof::from_compact_bytes(&[0xff; 11]).is_err());
+        assert!(
+            MerkleProof::from_compact_bytes(&[
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02, 0
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_hex_string_round_trip() {
+        for size in [1, 2, 5, 64, 1000] {
+            let tree = MerkleTree::new((0..size).map(|i| i.to_string()).collect()).unwrap();
+            let proof = tree.get_proof(size - 1).unwrap();
+            let token = proof.to_hex_string();
+            assert!(token.starts_with("01"));
+
+            let parsed = MerkleProof::from_hex_string(&token).unwrap();
+            assert_eq!(parsed.siblings, proof.siblings);
+            assert_eq!(parsed.leaf_index, size - 1);
+
+            // Pasted with a prefix, wrapped across lines and indented
+            let (head, tail) = token.split_at(token.len() / 2);
+            let pasted = alloc::format!("  0x{}\n    {} \n", head, tail);
+            assert_eq!(MerkleProof::from_hex_string(&pasted).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn test_hex_string_errors() {
+        let tree = MerkleTree::new((0..4).map(|i| i.to_string()).collect()).unwrap();
+        let token = tree.get_proof(1).unwrap().to_hex_string();
+
+        assert_eq!(
+            MerkleProof::from_hex_string(&token[1..]).err(),
+            Some(MerkleError::InvalidProof(
+                "odd number of hex digits (135)".to_string()
+            ))
+        );
+        assert!(matches!(
+            MerkleProof::from_hex_string(&token.replacen('0', "g", 1)),
+            Err(MerkleError::InvalidProof(_))
+        ));
+        // "+1" would otherwise be read as the version byte 1
+        assert_eq!(
+            MerkleProof::from_hex_string(&token.replacen("01", "+1", 1)).err(),
+            Some(MerkleError::InvalidProof(
+                "not a hex byte: \"+1\"".to_string()
+            ))
+        );
+        assert_eq!(
+            MerkleProof::from_hex_string(&token.replacen("01", "02", 1)).err(),
+            Some(MerkleError::UnsupportedVersion(2))
+        );
+        assert!(MerkleProof::from_hex_string(&token[..token.len() - 2]).is_err());
+        assert!(MerkleProof::from_hex_string(" 0x ").is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_hex_string_never_panics(text in "\\PC*", hex in "(0x)?01[0-9a-f ]{0,200}") {
+            let _ = MerkleProof::from_hex_string(&text);
+            let _ = MerkleProof::from_hex_string(&hex);
+        }
+    }
+}
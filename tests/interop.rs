@@ -0,0 +1,19 @@
+// Cross-implementation checks: our default roots must match other Merkle libraries
+// wherever the two agree on tree shape (power-of-two leaf counts, so no odd-node handling is involved).
+
+use merkle_tree::MerkleTree;
+use rs_merkle::Hasher;
+use rs_merkle::algorithms::Sha256;
+
+#[test]
+fn test_root_matches_rs_merkle() {
+    for count in [1, 2, 4, 8, 16] {
+        let values: Vec<String> = (0..count).map(|i| format!("leaf-{}", i)).collect();
+
+        let leaves: Vec<[u8; 32]> = values.iter().map(|v| Sha256::hash(v.as_bytes())).collect();
+        let theirs = rs_merkle::MerkleTree::<Sha256>::from_leaves(&leaves);
+
+        let ours = MerkleTree::new(values).unwrap();
+        assert_eq!(Some(*ours.root_bytes()), theirs.root(), "{} leaves", count);
+    }
+}